@@ -111,6 +111,21 @@ impl EventBuilder {
         })
     }
 
+    /// Create encrypted direct msg event, using NIP44 encryption
+    pub fn new_encrypted_direct_msg_nip44(
+        sender_keys: Arc<Keys>,
+        receiver_pubkey: String,
+        content: String,
+    ) -> Result<Self> {
+        Ok(Self {
+            builder: EventBuilderSdk::new_encrypted_direct_msg_nip44(
+                sender_keys.deref(),
+                XOnlyPublicKey::from_str(&receiver_pubkey)?,
+                content,
+            )?,
+        })
+    }
+
     /// Create delete event
     pub fn delete(ids: Vec<String>, reason: Option<String>) -> Result<Self> {
         let mut new_ids: Vec<EventId> = Vec::with_capacity(ids.len());
@@ -1,6 +1,7 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use std::fmt;
 use std::ops::Deref;
 use std::str::FromStr;
 
@@ -8,6 +9,7 @@ use nostr::key::{Keys as KeysSdk, XOnlyPublicKey};
 use nostr::nips::nip06::FromMnemonic;
 use nostr::nips::nip19::{FromBech32, ToBech32};
 use nostr::secp256k1::SecretKey;
+use nostr::zeroize::Zeroizing;
 
 use crate::error::{NostrError, Result};
 
@@ -16,6 +18,13 @@ pub struct Keys {
     keys: KeysSdk,
 }
 
+impl fmt::Debug for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Redacted: don't print the secret key
+        self.keys.fmt(f)
+    }
+}
+
 impl Deref for Keys {
     type Target = KeysSdk;
     fn deref(&self) -> &Self::Target {
@@ -25,6 +34,7 @@ impl Deref for Keys {
 
 impl Keys {
     pub fn new(sk: String) -> Result<Self> {
+        let sk = Zeroizing::new(sk);
         let sk = SecretKey::from_str(&sk)?;
 
         Ok(Self {
@@ -41,7 +51,8 @@ impl Keys {
     }
 
     pub fn from_bech32(sk: String) -> Result<Self> {
-        let sk = SecretKey::from_bech32(sk)?;
+        let sk = Zeroizing::new(sk);
+        let sk = SecretKey::from_bech32(sk.as_str())?;
         Ok(Self {
             keys: KeysSdk::new(sk),
         })
@@ -67,6 +78,17 @@ impl Keys {
         })
     }
 
+    pub fn from_mnemonic_with_account(
+        mnemonic: String,
+        passphrase: Option<String>,
+        account: u32,
+    ) -> Result<Self> {
+        Ok(Self {
+            keys: KeysSdk::from_mnemonic_with_account(mnemonic, passphrase, account)
+                .map_err(|e| NostrError::Generic { err: e.to_string() })?,
+        })
+    }
+
     pub fn public_key(&self) -> String {
         self.keys.public_key().to_string()
     }
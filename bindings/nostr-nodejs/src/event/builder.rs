@@ -111,6 +111,22 @@ impl JsEventBuilder {
         })
     }
 
+    #[napi(factory)]
+    pub fn new_encrypted_direct_msg_nip44(
+        sender_keys: &JsKeys,
+        receiver_pubkey: &JsPublicKey,
+        content: String,
+    ) -> Result<Self> {
+        Ok(Self {
+            builder: EventBuilder::new_encrypted_direct_msg_nip44(
+                sender_keys.deref(),
+                receiver_pubkey.into(),
+                content,
+            )
+            .map_err(into_err)?,
+        })
+    }
+
     #[napi(factory)]
     pub fn repost(event_id: &JsEventId, public_key: &JsPublicKey) -> Self {
         Self {
@@ -1,10 +1,12 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use std::fmt;
 use std::ops::Deref;
 
 use napi::Result;
 use nostr::prelude::*;
+use nostr::zeroize::Zeroizing;
 
 mod public_key;
 mod secret_key;
@@ -18,6 +20,13 @@ pub struct JsKeys {
     inner: Keys,
 }
 
+impl fmt::Debug for JsKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Redacted: don't print the secret key
+        self.inner.fmt(f)
+    }
+}
+
 impl Deref for JsKeys {
     type Target = Keys;
     fn deref(&self) -> &Self::Target {
@@ -52,6 +61,7 @@ impl JsKeys {
     /// Init [`Keys`] from `hex` or `bech32` secret key string
     #[napi(factory)]
     pub fn from_sk_str(secret_key: String) -> Result<Self> {
+        let secret_key = Zeroizing::new(secret_key);
         Ok(Self {
             inner: Keys::from_sk_str(&secret_key).map_err(into_err)?,
         })
@@ -73,7 +83,7 @@ impl JsKeys {
         }
     }
 
-    /// Derive keys from BIP-39 mnemonics (ENGLISH wordlist).
+    /// Derive keys from BIP-39 mnemonics (first account)
     #[napi(factory)]
     pub fn from_mnemonic(mnemonic: String, passphrase: Option<String>) -> Result<Self> {
         Ok(Self {
@@ -81,6 +91,19 @@ impl JsKeys {
         })
     }
 
+    /// Derive keys of `account` from BIP-39 mnemonics
+    #[napi(factory)]
+    pub fn from_mnemonic_with_account(
+        mnemonic: String,
+        passphrase: Option<String>,
+        account: u32,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: Keys::from_mnemonic_with_account(mnemonic, passphrase, account)
+                .map_err(into_err)?,
+        })
+    }
+
     /// Get public key
     #[napi]
     pub fn public_key(&self) -> JsPublicKey {
@@ -1,11 +1,13 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use std::fmt;
 use std::ops::Deref;
 use std::str::FromStr;
 
 use napi::Result;
 use nostr::prelude::*;
+use nostr::zeroize::{self, Zeroizing};
 
 use crate::error::into_err;
 
@@ -14,6 +16,19 @@ pub struct JsSecretKey {
     inner: SecretKey,
 }
 
+impl fmt::Debug for JsSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(<redacted>)")
+    }
+}
+
+impl Drop for JsSecretKey {
+    fn drop(&mut self) {
+        // SAFETY: `SecretKey` is a plain byte array without `Drop` and it's never used again
+        unsafe { zeroize::zeroize_flat_type(&mut self.inner) };
+    }
+}
+
 impl Deref for JsSecretKey {
     type Target = SecretKey;
     fn deref(&self) -> &Self::Target {
@@ -37,6 +52,7 @@ impl From<&JsSecretKey> for SecretKey {
 impl JsSecretKey {
     #[napi(factory)]
     pub fn from_hex(hex: String) -> Result<Self> {
+        let hex = Zeroizing::new(hex);
         Ok(Self {
             inner: SecretKey::from_str(&hex).map_err(into_err)?,
         })
@@ -44,8 +60,9 @@ impl JsSecretKey {
 
     #[napi(factory)]
     pub fn from_bech32(sk: String) -> Result<Self> {
+        let sk = Zeroizing::new(sk);
         Ok(Self {
-            inner: SecretKey::from_bech32(sk).map_err(into_err)?,
+            inner: SecretKey::from_bech32(sk.as_str()).map_err(into_err)?,
         })
     }
 
@@ -0,0 +1,22 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use std::ops::Deref;
+
+use napi::Result;
+use nostr::nips::nip44::{self, Version};
+
+use crate::error::into_err;
+use crate::{JsPublicKey, JsSecretKey};
+
+/// Encrypt (NIP44)
+#[napi]
+pub fn nip44_encrypt(sk: &JsSecretKey, pk: &JsPublicKey, content: String) -> Result<String> {
+    nip44::encrypt(sk.deref(), pk.deref(), content, Version::default()).map_err(into_err)
+}
+
+/// Decrypt (NIP44)
+#[napi]
+pub fn nip44_decrypt(sk: &JsSecretKey, pk: &JsPublicKey, payload: String) -> Result<String> {
+    nip44::decrypt(sk.deref(), pk.deref(), payload).map_err(into_err)
+}
@@ -21,6 +21,7 @@ use crate::relay::JsRelay;
 #[napi(js_name = "Client")]
 pub struct JsClient {
     inner: Client,
+    keys: Keys,
 }
 
 #[napi]
@@ -29,6 +30,7 @@ impl JsClient {
     pub fn new(keys: &JsKeys) -> Self {
         Self {
             inner: Client::new(keys.deref()),
+            keys: keys.deref().clone(),
         }
     }
 
@@ -37,6 +39,7 @@ impl JsClient {
     pub fn new_with_opts(keys: &JsKeys, opts: &JsOptions) -> Self {
         Self {
             inner: Client::new_with_opts(keys.deref(), opts.into()),
+            keys: keys.deref().clone(),
         }
     }
 
@@ -49,7 +52,7 @@ impl JsClient {
     /// Get current `Keys`
     #[napi]
     pub fn keys(&self) -> JsKeys {
-        self.inner.keys().into()
+        self.keys.clone().into()
     }
 
     /// Completly shutdown `Client`
@@ -280,6 +283,24 @@ impl JsClient {
             .map(|id| id.into())
     }
 
+    /// Send encrypted direct message, using NIP44 encryption
+    ///
+    /// Warning: the NIP44 payload is sent in a kind 4 event, so the clients supporting only NIP04 can't decrypt it.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/44.md>
+    #[napi]
+    pub async fn send_direct_msg_nip44(
+        &self,
+        receiver: &JsPublicKey,
+        msg: String,
+    ) -> Result<JsEventId> {
+        self.inner
+            .send_direct_msg_nip44(receiver.into(), msg)
+            .await
+            .map_err(into_err)
+            .map(|id| id.into())
+    }
+
     /// Repost event
     #[napi]
     pub async fn repost_event(
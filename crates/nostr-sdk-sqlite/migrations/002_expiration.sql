@@ -0,0 +1,6 @@
+PRAGMA user_version = 2; -- Schema version
+
+-- Expiration timestamp (NIP-40)
+ALTER TABLE events ADD COLUMN expiration INTEGER DEFAULT NULL;
+
+CREATE INDEX IF NOT EXISTS expiration_index ON events(expiration);
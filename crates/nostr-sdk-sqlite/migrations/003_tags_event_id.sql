@@ -0,0 +1,8 @@
+PRAGMA user_version = 3; -- Schema version
+
+-- The tags referenced the raw bytes of the event id, while the events are stored with the hex id
+PRAGMA foreign_keys = OFF;
+UPDATE OR IGNORE tags SET event_id = lower(hex(event_id)) WHERE typeof(event_id) = 'blob';
+-- Remove the duplicates not converted and the tags of missing events
+DELETE FROM tags WHERE event_id NOT IN (SELECT id FROM events);
+PRAGMA foreign_keys = ON;
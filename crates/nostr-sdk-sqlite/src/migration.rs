@@ -8,7 +8,7 @@ use rusqlite::Connection;
 use crate::store::{Error, PooledConnection};
 
 /// Latest database version
-pub const DB_VERSION: usize = 1;
+pub const DB_VERSION: usize = 3;
 
 /// Startup DB Pragmas
 pub const STARTUP_SQL: &str = r##"
@@ -51,12 +51,12 @@ pub fn run(conn: &mut PooledConnection) -> Result<(), Error> {
 
             // for initialized but out-of-date schemas, proceed to
             // upgrade sequentially until we are current.
-            /* if curr_version == 1 {
+            if curr_version == 1 {
                 curr_version = mig_1_to_2(conn)?;
-            } */
-            /* if curr_version == 2 {
+            }
+            if curr_version == 2 {
                 curr_version = mig_2_to_3(conn)?;
-            } */
+            }
 
             if curr_version == DB_VERSION {
                 log::info!("All migration scripts completed successfully (v{DB_VERSION})");
@@ -86,8 +86,92 @@ fn mig_init(conn: &mut PooledConnection) -> Result<usize, Error> {
     Ok(1)
 }
 
-/* fn mig_1_to_2(conn: &mut PooledConnection) -> Result<usize, Error> {
-    conn.execute_batch(include_str!("../migrations/002.sql"))?;
+fn mig_1_to_2(conn: &mut PooledConnection) -> Result<usize, Error> {
+    conn.execute_batch(include_str!("../migrations/002_expiration.sql"))?;
     log::info!("database schema upgraded v1 -> v2");
     Ok(2)
-} */
+}
+
+fn mig_2_to_3(conn: &mut PooledConnection) -> Result<usize, Error> {
+    conn.execute_batch(include_str!("../migrations/003_tags_event_id.sql"))?;
+    log::info!("database schema upgraded v2 -> v3");
+    Ok(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use nostr::{EventBuilder, EventId, Keys, Tag};
+
+    use super::*;
+    use crate::Store;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("nostr-sdk-sqlite-{name}-{}.db", std::process::id()));
+        remove_db(&path);
+        path
+    }
+
+    fn remove_db(path: &Path) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let path = temp_path("migration");
+        let keys = Keys::generate();
+        let event = EventBuilder::new_text_note("hello", &[Tag::Hashtag(String::from("nostr"))])
+            .to_event(&keys)
+            .unwrap();
+        let missing =
+            EventId::from_hex("2be17aa3031bdcb006f0fce80c146dea9c1c0268b0af2398bb673365c6444d45")
+                .unwrap();
+
+        // v1 database, with the tags referencing the raw bytes of the event id
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(include_str!("../migrations/001_init.sql"))
+                .unwrap();
+            conn.execute_batch("PRAGMA foreign_keys = OFF;").unwrap();
+            conn.execute(
+                "INSERT INTO events (id, pubkey, created_at, kind, content, sig) VALUES (?, ?, ?, ?, ?, ?);",
+                (event.id.to_hex(), event.pubkey.to_string(), event.created_at.as_u64(), event.kind.as_u64(), &event.content, event.sig.to_string()),
+            )
+            .unwrap();
+            for event_id in [event.id, missing] {
+                conn.execute(
+                    "INSERT INTO tags (event_id, kind, value) VALUES (?, ?, ?);",
+                    (event_id.as_bytes(), "t", r#"["nostr"]"#),
+                )
+                .unwrap();
+            }
+        }
+
+        Store::open(&path).unwrap().close();
+
+        let mut conn = Connection::open(&path).unwrap();
+        assert_eq!(curr_db_version(&mut conn).unwrap(), DB_VERSION);
+
+        // The tags of the missing events are removed
+        let tags: Vec<(String, String)> = conn
+            .prepare("SELECT event_id, typeof(event_id) FROM tags;")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(tags, vec![(event.id.to_hex(), String::from("text"))]);
+
+        let expiration: Option<u64> = conn
+            .query_row("SELECT expiration FROM events;", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(expiration, None);
+
+        drop(conn);
+        remove_db(&path);
+    }
+}
@@ -5,7 +5,9 @@
 
 use std::net::SocketAddr;
 use std::path::Path;
+use std::sync::Arc;
 
+use nostr::types::time::{Clock, SystemClock};
 use nostr::{Event, Url};
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::OpenFlags;
@@ -27,12 +29,16 @@ pub enum Error {
     /// Migration error
     #[error(transparent)]
     Migration(#[from] MigrationError),
+    /// Expired event
+    #[error("event expired")]
+    EventExpired,
 }
 
 /// Store
 #[derive(Debug, Clone)]
 pub struct Store {
     pool: SqlitePool,
+    clock: Arc<dyn Clock>,
 }
 
 impl Drop for Store {
@@ -42,6 +48,14 @@ impl Drop for Store {
 impl Store {
     /// Open new database
     pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_with_clock(path, Arc::new(SystemClock))
+    }
+
+    /// Open new database, using `clock` to check the expiration of the events (NIP-40)
+    pub fn open_with_clock<P>(path: P, clock: Arc<dyn Clock>) -> Result<Self, Error>
     where
         P: AsRef<Path>,
     {
@@ -50,7 +64,7 @@ impl Store {
             .with_init(|c| c.execute_batch(STARTUP_SQL));
         let pool = r2d2::Pool::new(manager)?;
         migration::run(&mut pool.get()?)?;
-        Ok(Self { pool })
+        Ok(Self { pool, clock })
     }
 
     /// Close SQLite connection
@@ -111,12 +125,19 @@ impl Store {
     }
 
     /// Insert new event
+    ///
+    /// Expired events (NIP-40) are rejected.
     pub fn insert_event(&self, event: Event) -> Result<(), Error> {
+        if event.is_expired_at(self.clock.now()) {
+            return Err(Error::EventExpired);
+        }
+
+        let expiration: Option<u64> = event.expiration().map(|e| e.as_u64());
         let conn = self.pool.get()?;
         // Insert event
         conn.execute(
-            "INSERT OR IGNORE INTO events (id, pubkey, created_at, kind, content, sig) VALUES (?, ?, ?, ?, ?, ?);",
-            (event.id.to_hex(), &event.pubkey.to_string(), event.created_at.as_u64(), event.kind.as_u64(), event.content, event.sig.to_string()),
+            "INSERT OR IGNORE INTO events (id, pubkey, created_at, kind, content, sig, expiration) VALUES (?, ?, ?, ?, ?, ?, ?);",
+            (event.id.to_hex(), &event.pubkey.to_string(), event.created_at.as_u64(), event.kind.as_u64(), event.content, event.sig.to_string(), expiration),
         )?;
         // Insert tags
         let mut stmt =
@@ -125,8 +146,88 @@ impl Store {
             let tag: Vec<String> = tag.as_vec();
             let kind = &tag[0];
             let value = tag.get(1..);
-            stmt.execute((event.id.as_bytes(), kind, serde_json::json!(value)))?;
+            stmt.execute((event.id.to_hex(), kind, serde_json::json!(value)))?;
         }
         Ok(())
     }
+
+    /// Delete expired events (NIP-40)
+    ///
+    /// Return the number of deleted events.
+    pub fn delete_expired_events(&self) -> Result<usize, Error> {
+        let conn = self.pool.get()?;
+        let deleted = conn.execute(
+            "DELETE FROM events WHERE expiration IS NOT NULL AND expiration <= ?;",
+            [self.clock.now().as_u64()],
+        )?;
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use nostr::types::time::FixedClock;
+    use nostr::{EventBuilder, Keys, Timestamp};
+    use rusqlite::Connection;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("nostr-sdk-sqlite-{name}-{}.db", std::process::id()));
+        remove_db(&path);
+        path
+    }
+
+    fn remove_db(path: &Path) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+    }
+
+    #[test]
+    fn test_expired_events() {
+        let path = temp_path("expiration");
+        let now = Timestamp::from(1_700_000_000);
+        let clock = Arc::new(FixedClock::new(now));
+        let store = Store::open_with_clock(&path, clock.clone()).unwrap();
+        let keys = Keys::generate();
+
+        let expired = EventBuilder::new_text_note("expired", &[])
+            .expiration(now - Duration::from_secs(1))
+            .to_event(&keys)
+            .unwrap();
+        assert!(matches!(
+            store.insert_event(expired),
+            Err(Error::EventExpired)
+        ));
+
+        let expiring = EventBuilder::new_text_note("expiring", &[])
+            .expiration(now + Duration::from_secs(60))
+            .to_event(&keys)
+            .unwrap();
+        store.insert_event(expiring).unwrap();
+        let event = EventBuilder::new_text_note("no expiration", &[])
+            .to_event(&keys)
+            .unwrap();
+        store.insert_event(event.clone()).unwrap();
+        assert_eq!(store.delete_expired_events().unwrap(), 0);
+
+        clock.set(now + Duration::from_secs(60));
+        assert_eq!(store.delete_expired_events().unwrap(), 1);
+        assert_eq!(store.delete_expired_events().unwrap(), 0);
+        store.close();
+
+        let conn = Connection::open(&path).unwrap();
+        let id: String = conn
+            .query_row("SELECT id FROM events;", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(id, event.id.to_hex());
+
+        drop(conn);
+        remove_db(&path);
+    }
 }
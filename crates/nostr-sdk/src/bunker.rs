@@ -0,0 +1,322 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP46 bunker
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/46.md>
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+use nostr::event::builder::Error as EventBuilderError;
+use nostr::key::{self, XOnlyPublicKey};
+use nostr::nips::nip46::{
+    self, NostrConnectMessage, NostrConnectRequest, NostrConnectURI, ResponseResult,
+};
+use nostr::nips::{nip04, nip44};
+use nostr::url::Url;
+use nostr::{
+    ClientMessage, Event, EventBuilder, EventId, Filter, Keys, Kind, RelayMessage, SubscriptionId,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+
+use crate::relay::pool::{
+    Error as RelayPoolError, RelayPool, RelayPoolNotification, RelayPoolOptions,
+};
+use crate::RelayOptions;
+
+/// Number of handled events remembered to skip the ones received from more relays
+const MAX_HANDLED_EVENTS: usize = 1024;
+
+/// [`Bunker`] error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Keys error
+    #[error(transparent)]
+    Keys(#[from] key::Error),
+    /// NIP04 error
+    #[error(transparent)]
+    NIP04(#[from] nip04::Error),
+    /// NIP44 error
+    #[error(transparent)]
+    NIP44(#[from] nip44::Error),
+    /// NIP46 error
+    #[error(transparent)]
+    NIP46(#[from] nip46::Error),
+    /// Event builder error
+    #[error(transparent)]
+    EventBuilder(#[from] EventBuilderError),
+    /// Relay pool error
+    #[error(transparent)]
+    RelayPool(#[from] RelayPoolError),
+    /// Only `nostrconnect://` URIs can be used to connect to a client
+    #[error("expected a nostrconnect URI")]
+    UnexpectedURI,
+    /// The `connect` request is for another remote signer
+    #[error("public key mismatch")]
+    PublicKeyMismatch,
+    /// Invalid secret
+    #[error("invalid secret")]
+    InvalidSecret,
+    /// The client didn't send the `connect` request or was not approved
+    #[error("unauthorized")]
+    Unauthorized,
+}
+
+/// NIP46 bunker
+///
+/// Minimal remote signer: serves the requests of the connected clients with the local [`Keys`].
+#[derive(Debug, Clone)]
+pub struct Bunker {
+    keys: Keys,
+    pool: RelayPool,
+    secret: Option<String>,
+    subscription_id: SubscriptionId,
+    clients: Arc<Mutex<HashSet<XOnlyPublicKey>>>,
+}
+
+impl Bunker {
+    /// New [`Bunker`] listening on `relays`
+    ///
+    /// If a `secret` is set, clients must send it with the `connect` request.
+    /// Without `secret`, only the clients approved with [`Bunker::allow_client`] are served.
+    pub async fn new(keys: Keys, relays: Vec<Url>, secret: Option<String>) -> Result<Self, Error> {
+        Self::new_with_opts(keys, relays, secret, RelayPoolOptions::default()).await
+    }
+
+    /// New [`Bunker`] listening on `relays`, with [`RelayPoolOptions`]
+    pub async fn new_with_opts(
+        keys: Keys,
+        relays: Vec<Url>,
+        secret: Option<String>,
+        opts: RelayPoolOptions,
+    ) -> Result<Self, Error> {
+        let pool = RelayPool::new_with_opts(opts);
+        for url in relays.into_iter() {
+            pool.add_relay(url, None, RelayOptions::default()).await?;
+        }
+        pool.connect(true).await;
+
+        let bunker = Self {
+            keys,
+            pool,
+            secret,
+            subscription_id: SubscriptionId::generate(),
+            clients: Arc::new(Mutex::new(HashSet::new())),
+        };
+        bunker.subscribe().await?;
+        Ok(bunker)
+    }
+
+    async fn subscribe(&self) -> Result<(), Error> {
+        // Use a dedicated subscription: the pool one is shared with the clients in the same process
+        let filter = Filter::new()
+            .pubkey(self.keys.public_key())
+            .kind(Kind::NostrConnect)
+            .since(self.pool.clock().now());
+        self.pool
+            .send_msg(
+                ClientMessage::new_req(self.subscription_id.clone(), vec![filter]),
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Get the `bunker://` URI to share with the clients
+    pub async fn uri(&self) -> NostrConnectURI {
+        NostrConnectURI::Bunker {
+            signer_public_key: self.keys.public_key(),
+            relays: self.pool.relays().await.into_keys().collect(),
+            secret: self.secret.clone(),
+        }
+    }
+
+    /// Approve a client: its requests will be served without secret
+    pub async fn allow_client(&self, public_key: XOnlyPublicKey) {
+        self.clients.lock().await.insert(public_key);
+    }
+
+    /// Connect to the client that shared the `nostrconnect://` URI
+    ///
+    /// The client is approved without secret.
+    pub async fn connect_client(&self, uri: &NostrConnectURI) -> Result<(), Error> {
+        let (public_key, relays) = match uri {
+            NostrConnectURI::Client {
+                public_key, relays, ..
+            } => (public_key, relays),
+            NostrConnectURI::Bunker { .. } => return Err(Error::UnexpectedURI),
+        };
+
+        for url in relays.iter() {
+            self.pool
+                .add_relay(url.clone(), None, RelayOptions::default())
+                .await?;
+        }
+        self.pool.connect(true).await;
+        self.subscribe().await?;
+
+        self.clients.lock().await.insert(*public_key);
+
+        let msg = NostrConnectMessage::request(NostrConnectRequest::Connect {
+            public_key: self.keys.public_key(),
+            secret: None,
+        });
+        self.send_msg(*public_key, msg).await
+    }
+
+    /// Serve the requests of the clients until the [`Bunker`] is shut down
+    pub async fn serve(&self) -> Result<(), Error> {
+        let mut notifications = self.pool.notifications();
+        let mut handled: VecDeque<EventId> = VecDeque::new();
+        loop {
+            match notifications.recv().await {
+                Ok(RelayPoolNotification::Message(
+                    _,
+                    RelayMessage::Event {
+                        subscription_id,
+                        event,
+                    },
+                )) if subscription_id == self.subscription_id
+                    && event.kind == Kind::NostrConnect =>
+                {
+                    if handled.contains(&event.id) {
+                        continue;
+                    }
+                    if handled.len() >= MAX_HANDLED_EVENTS {
+                        handled.pop_front();
+                    }
+                    handled.push_back(event.id);
+
+                    if let Err(e) = self.handle_event(&event).await {
+                        log::error!("Impossible to handle NIP46 event {}: {e}", event.id);
+                    }
+                }
+                Ok(RelayPoolNotification::Shutdown) | Err(RecvError::Closed) => break,
+                Ok(_) => (),
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Bunker lagged: {skipped} notifications skipped");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_event(&self, event: &Event) -> Result<(), Error> {
+        // Responses are only the acks of `connect_client`
+        if let NostrConnectMessage::Request { id, req } =
+            NostrConnectMessage::from_event(&self.keys, event)?
+        {
+            let msg = match self.handle_request(event.pubkey, req).await {
+                Ok(result) => NostrConnectMessage::response(id, Some(result), None),
+                Err(e) => NostrConnectMessage::response(id, None, Some(e.to_string())),
+            };
+            self.send_msg(event.pubkey, msg).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_request(
+        &self,
+        client: XOnlyPublicKey,
+        req: NostrConnectRequest,
+    ) -> Result<ResponseResult, Error> {
+        if let NostrConnectRequest::Connect { public_key, secret } = req {
+            if public_key != self.keys.public_key() {
+                return Err(Error::PublicKeyMismatch);
+            }
+            let mut clients = self.clients.lock().await;
+            match &self.secret {
+                Some(expected) => match secret {
+                    Some(secret) if constant_time_eq(secret.as_bytes(), expected.as_bytes()) => {
+                        clients.insert(client);
+                    }
+                    _ => return Err(Error::InvalidSecret),
+                },
+                // Never authorize unknown clients implicitly
+                None if !clients.contains(&client) => return Err(Error::Unauthorized),
+                None => (),
+            }
+            return Ok(ResponseResult::Ack);
+        }
+
+        if !self.clients.lock().await.contains(&client) {
+            return Err(Error::Unauthorized);
+        }
+
+        match req {
+            NostrConnectRequest::Connect { .. } => Ok(ResponseResult::Ack),
+            NostrConnectRequest::GetPublicKey => {
+                Ok(ResponseResult::GetPublicKey(self.keys.public_key()))
+            }
+            NostrConnectRequest::SignEvent(unsigned) => {
+                // Compute the id again: never sign an id chosen by the client
+                let event: Event =
+                    EventBuilder::new(unsigned.kind, unsigned.content, &unsigned.tags)
+                        .custom_created_at(unsigned.created_at)
+                        .to_event(&self.keys)?;
+                Ok(ResponseResult::SignEvent(Box::new(event)))
+            }
+            NostrConnectRequest::Nip04Encrypt { public_key, text } => {
+                Ok(ResponseResult::Nip04Encrypt(nip04::encrypt(
+                    &self.keys.secret_key()?,
+                    &public_key,
+                    text,
+                )?))
+            }
+            NostrConnectRequest::Nip04Decrypt {
+                public_key,
+                ciphertext,
+            } => Ok(ResponseResult::Nip04Decrypt(nip04::decrypt(
+                &self.keys.secret_key()?,
+                &public_key,
+                ciphertext,
+            )?)),
+            NostrConnectRequest::Nip44Encrypt { public_key, text } => {
+                Ok(ResponseResult::Nip44Encrypt(nip44::encrypt(
+                    &self.keys.secret_key()?,
+                    &public_key,
+                    text,
+                    nip44::Version::default(),
+                )?))
+            }
+            NostrConnectRequest::Nip44Decrypt {
+                public_key,
+                ciphertext,
+            } => Ok(ResponseResult::Nip44Decrypt(nip44::decrypt(
+                &self.keys.secret_key()?,
+                &public_key,
+                ciphertext,
+            )?)),
+            NostrConnectRequest::Ping => Ok(ResponseResult::Pong),
+        }
+    }
+
+    async fn send_msg(
+        &self,
+        receiver: XOnlyPublicKey,
+        msg: NostrConnectMessage,
+    ) -> Result<(), Error> {
+        let event: Event =
+            EventBuilder::nostr_connect(&self.keys, receiver, msg)?.to_event(&self.keys)?;
+        self.pool
+            .send_msg(ClientMessage::new_event(event), false)
+            .await?;
+        Ok(())
+    }
+
+    /// Disconnect from the relays and stop serving
+    pub async fn shutdown(self) -> Result<(), Error> {
+        Ok(self.pool.shutdown().await?)
+    }
+}
+
+/// Compare in constant time
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
@@ -5,9 +5,12 @@
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use nostr::key::XOnlyPublicKey;
+#[cfg(feature = "nip59")]
+use nostr::nips::nip59::UnwrappedGift;
 use nostr::url::Url;
 use nostr::{ChannelId, ClientMessage, Contact, Event, EventId, Filter, Keys, Metadata, Tag};
 use tokio::sync::broadcast;
@@ -16,6 +19,7 @@ use super::{Error, Options};
 use crate::client::Entity;
 use crate::relay::pool::RelayPoolNotification;
 use crate::relay::{Relay, RelayOptions};
+use crate::signer::Signer;
 use crate::RUNTIME;
 
 #[derive(Debug, Clone)]
@@ -42,11 +46,40 @@ impl Client {
         }
     }
 
-    /// Get current [`Keys`]
+    pub fn new_with_signer<S>(signer: S) -> Self
+    where
+        S: Signer + 'static,
+    {
+        Self {
+            client: super::Client::new_with_signer(signer),
+        }
+    }
+
+    pub fn new_with_signer_and_opts<S>(signer: S, opts: Options) -> Self
+    where
+        S: Signer + 'static,
+    {
+        Self {
+            client: super::Client::new_with_signer_and_opts(signer, opts),
+        }
+    }
+
+    #[deprecated = "Use `signer` or `public_key` methods"]
     pub fn keys(&self) -> Keys {
+        #[allow(deprecated)]
         self.client.keys()
     }
 
+    /// Get [`Signer`]
+    pub fn signer(&self) -> Arc<dyn Signer> {
+        self.client.signer()
+    }
+
+    /// Get the public key of the [`Signer`]
+    pub fn public_key(&self) -> Result<XOnlyPublicKey, Error> {
+        RUNTIME.block_on(async { self.client.public_key().await })
+    }
+
     pub fn notifications(&self) -> broadcast::Receiver<RelayPoolNotification> {
         self.client.notifications()
     }
@@ -219,6 +252,40 @@ impl Client {
         RUNTIME.block_on(async { self.client.send_direct_msg(receiver, msg).await })
     }
 
+    #[cfg(feature = "nip44")]
+    pub fn send_direct_msg_nip44<S>(
+        &self,
+        receiver: XOnlyPublicKey,
+        msg: S,
+    ) -> Result<EventId, Error>
+    where
+        S: Into<String>,
+    {
+        RUNTIME.block_on(async { self.client.send_direct_msg_nip44(receiver, msg).await })
+    }
+
+    #[cfg(feature = "nip59")]
+    pub fn send_private_msg<S>(
+        &self,
+        receiver: XOnlyPublicKey,
+        message: S,
+        reply_to: Option<EventId>,
+    ) -> Result<EventId, Error>
+    where
+        S: Into<String>,
+    {
+        RUNTIME.block_on(async {
+            self.client
+                .send_private_msg(receiver, message, reply_to)
+                .await
+        })
+    }
+
+    #[cfg(feature = "nip59")]
+    pub fn unwrap_gift_wrap(&self, gift_wrap: &Event) -> Result<UnwrappedGift, Error> {
+        RUNTIME.block_on(async { self.client.unwrap_gift_wrap(gift_wrap).await })
+    }
+
     pub fn repost_event(
         &self,
         event_id: EventId,
@@ -8,14 +8,19 @@ use std::net::SocketAddr;
 #[cfg(feature = "sqlite")]
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use nostr::event::builder::Error as EventBuilderError;
 use nostr::key::XOnlyPublicKey;
+#[cfg(feature = "nip13")]
+use nostr::nips::nip13::{CancellationToken, PowOptions};
+#[cfg(feature = "nip59")]
+use nostr::nips::nip59::{self, UnwrappedGift};
 use nostr::url::Url;
 use nostr::{
     ChannelId, ClientMessage, Contact, Entity, Event, EventBuilder, EventId, Filter, Keys, Kind,
-    Metadata, Tag,
+    Metadata, Tag, UnsignedEvent,
 };
 #[cfg(feature = "sqlite")]
 use nostr_sdk_sqlite::Store;
@@ -27,6 +32,8 @@ mod options;
 
 pub use self::options::Options;
 use crate::relay::pool::{Error as RelayPoolError, RelayPool, RelayPoolNotification};
+use crate::signer::{Error as SignerError, Signer};
+use crate::thread;
 use crate::{Relay, RelayOptions};
 
 /// [`Client`] error
@@ -50,13 +57,25 @@ pub enum Error {
     /// Hex error
     #[error("hex decoding error: {0}")]
     Hex(#[from] nostr::hashes::hex::Error),
+    /// Thread error
+    #[error("thread error: {0}")]
+    Thread(#[from] thread::Error),
+    /// Signer error
+    #[error("signer error: {0}")]
+    Signer(#[from] SignerError),
+    /// NIP59 error
+    #[cfg(feature = "nip59")]
+    #[error("NIP59 error: {0}")]
+    NIP59(#[from] nip59::Error),
 }
 
 /// Nostr client
 #[derive(Debug, Clone)]
 pub struct Client {
     pool: RelayPool,
-    keys: Keys,
+    signer: Arc<dyn Signer>,
+    /// Only for the deprecated [`Client::keys`]
+    keys: Option<Keys>,
     opts: Options,
 }
 
@@ -86,8 +105,42 @@ impl Client {
     /// ```
     pub fn new_with_opts(keys: &Keys, opts: Options) -> Self {
         Self {
-            pool: RelayPool::new(),
-            keys: keys.clone(),
+            pool: RelayPool::new_with_opts(opts.get_pool_opts()),
+            signer: Arc::new(keys.clone()),
+            keys: Some(keys.clone()),
+            opts,
+        }
+    }
+
+    /// Create a new [`Client`] with a custom [`Signer`]
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use nostr_sdk::prelude::*;
+    ///
+    /// // Read-only client
+    /// let public_key = XOnlyPublicKey::from_bech32(
+    ///     "npub14f8usejl26twx0dhuxjh9cas7keav9vr0v8nvtwtrjqx3vycc76qqh9nsy",
+    /// )
+    /// .unwrap();
+    /// let client = Client::new_with_signer(Keys::from_public_key(public_key));
+    /// ```
+    pub fn new_with_signer<S>(signer: S) -> Self
+    where
+        S: Signer + 'static,
+    {
+        Self::new_with_signer_and_opts(signer, Options::default())
+    }
+
+    /// Create a new [`Client`] with a custom [`Signer`] and [`Options`]
+    pub fn new_with_signer_and_opts<S>(signer: S, opts: Options) -> Self
+    where
+        S: Signer + 'static,
+    {
+        Self {
+            pool: RelayPool::new_with_opts(opts.get_pool_opts()),
+            signer: Arc::new(signer),
+            keys: None,
             opts,
         }
     }
@@ -108,8 +161,9 @@ impl Client {
         P: AsRef<Path>,
     {
         Ok(Self {
-            pool: RelayPool::new_with_store(path)?,
-            keys: keys.clone(),
+            pool: RelayPool::new_with_store_and_opts(path, opts.get_pool_opts())?,
+            signer: Arc::new(keys.clone()),
+            keys: Some(keys.clone()),
             opts,
         })
     }
@@ -121,8 +175,25 @@ impl Client {
     }
 
     /// Get current [`Keys`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the [`Client`] was created with a custom [`Signer`].
+    #[deprecated(since = "0.19.0", note = "Use `signer` or `public_key` methods")]
     pub fn keys(&self) -> Keys {
-        self.keys.clone()
+        self.keys
+            .clone()
+            .expect("Client created with a custom signer: use `signer` or `public_key` methods")
+    }
+
+    /// Get [`Signer`]
+    pub fn signer(&self) -> Arc<dyn Signer> {
+        self.signer.clone()
+    }
+
+    /// Get the public key of the [`Signer`]
+    pub async fn public_key(&self) -> Result<XOnlyPublicKey, Error> {
+        Ok(self.signer.get_public_key().await?)
     }
 
     /// Get [`Store`]
@@ -442,17 +513,20 @@ impl Client {
     }
 
     async fn send_event_builder(&self, builder: EventBuilder) -> Result<EventId, Error> {
+        let public_key: XOnlyPublicKey = self.signer.get_public_key().await?;
         #[cfg(feature = "nip13")]
-        let event: Event = {
+        let unsigned: UnsignedEvent = {
             let difficulty: u8 = self.opts.get_difficulty();
             if difficulty > 0 {
-                builder.to_pow_event(&self.keys, difficulty)?
+                self.pow_event(builder, public_key, difficulty).await?
             } else {
-                builder.to_event(&self.keys)?
+                builder.to_unsigned_event_with_clock(public_key, self.opts.get_clock().as_ref())
             }
         };
         #[cfg(not(feature = "nip13"))]
-        let event: Event = builder.to_event(&self.keys)?;
+        let unsigned: UnsignedEvent =
+            builder.to_unsigned_event_with_clock(public_key, self.opts.get_clock().as_ref());
+        let event: Event = self.signer.sign_event(unsigned).await?;
         self.send_event(event).await
     }
 
@@ -517,6 +591,8 @@ impl Client {
 
     /// Publish POW text note
     ///
+    /// The proof of work runs on a blocking thread, using all the available CPU cores.
+    ///
     /// <https://github.com/nostr-protocol/nips/blob/master/13.md>
     ///
     /// # Example
@@ -543,11 +619,43 @@ impl Client {
     where
         S: Into<String>,
     {
-        let event: Event =
-            EventBuilder::new_text_note(content, tags).to_pow_event(&self.keys, difficulty)?;
+        let builder: EventBuilder = EventBuilder::new_text_note(content, tags);
+        let public_key: XOnlyPublicKey = self.signer.get_public_key().await?;
+        let unsigned: UnsignedEvent = self.pow_event(builder, public_key, difficulty).await?;
+        let event: Event = self.signer.sign_event(unsigned).await?;
         self.send_event(event).await
     }
 
+    /// Mine the POW [`UnsignedEvent`] on a blocking thread, to not block the async runtime
+    ///
+    /// The mining is cancelled if the returned future is dropped.
+    #[cfg(feature = "nip13")]
+    async fn pow_event(
+        &self,
+        builder: EventBuilder,
+        public_key: XOnlyPublicKey,
+        difficulty: u8,
+    ) -> Result<UnsignedEvent, Error> {
+        struct CancelOnDrop(CancellationToken);
+
+        impl Drop for CancelOnDrop {
+            fn drop(&mut self) {
+                self.0.cancel();
+            }
+        }
+
+        let token = CancellationToken::new();
+        let _guard = CancelOnDrop(token.clone());
+        let opts = PowOptions::new().cancellation_token(token);
+        let clock = self.opts.get_clock();
+        tokio::task::spawn_blocking(move || {
+            builder.to_unsigned_pow_event_with_clock(public_key, difficulty, &opts, clock.as_ref())
+        })
+        .await
+        .map_err(|_| thread::Error::JoinError)?
+        .map_err(Error::from)
+    }
+
     /// Add recommended relay
     ///
     /// <https://github.com/nostr-protocol/nips/blob/master/01.md>
@@ -605,7 +713,7 @@ impl Client {
         let mut contact_list: Vec<Contact> = Vec::new();
 
         let filter = Filter::new()
-            .authors(vec![self.keys.public_key()])
+            .authors(vec![self.signer.get_public_key().await?])
             .kind(Kind::ContactList)
             .limit(1);
         let events: Vec<Event> = self.get_events_of(vec![filter], timeout).await?;
@@ -658,10 +766,120 @@ impl Client {
     where
         S: Into<String>,
     {
-        let builder = EventBuilder::new_encrypted_direct_msg(&self.keys, receiver, msg)?;
+        let msg: String = msg.into();
+        let content: String = self.signer.nip04_encrypt(receiver, &msg).await?;
+        let builder = EventBuilder::new(
+            Kind::EncryptedDirectMessage,
+            content,
+            &[Tag::PubKey(receiver, None)],
+        );
         self.send_event_builder(builder).await
     }
 
+    /// Send encrypted direct message, using NIP44 encryption
+    ///
+    /// **Warning:** the NIP44 payload is sent in a [`Kind::EncryptedDirectMessage`] (kind 4) event,
+    /// so the clients supporting only NIP04 can't decrypt it. Use it only with receivers known to support NIP44.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/44.md>
+    #[cfg(feature = "nip44")]
+    pub async fn send_direct_msg_nip44<S>(
+        &self,
+        receiver: XOnlyPublicKey,
+        msg: S,
+    ) -> Result<EventId, Error>
+    where
+        S: Into<String>,
+    {
+        let msg: String = msg.into();
+        let content: String = self.signer.nip44_encrypt(receiver, &msg).await?;
+        let builder = EventBuilder::new(
+            Kind::EncryptedDirectMessage,
+            content,
+            &[Tag::PubKey(receiver, None)],
+        );
+        self.send_event_builder(builder).await
+    }
+
+    /// Send private direct message
+    ///
+    /// The message is gift wrapped for the `receiver` and for the signer,
+    /// so it can be read also from the other devices of the sender.
+    ///
+    /// Return the [`EventId`] of the gift wrap sent to the `receiver`.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/17.md>
+    #[cfg(feature = "nip59")]
+    pub async fn send_private_msg<S>(
+        &self,
+        receiver: XOnlyPublicKey,
+        message: S,
+        reply_to: Option<EventId>,
+    ) -> Result<EventId, Error>
+    where
+        S: Into<String>,
+    {
+        let public_key: XOnlyPublicKey = self.signer.get_public_key().await?;
+        let rumor: UnsignedEvent = EventBuilder::private_msg_rumor(receiver, message, reply_to)
+            .to_unsigned_event_with_clock(public_key, self.opts.get_clock().as_ref());
+
+        let own_gift_wrap: Event = self.gift_wrap(public_key, rumor.clone()).await?;
+        let gift_wrap: Event = self.gift_wrap(receiver, rumor).await?;
+
+        self.send_event(own_gift_wrap).await?;
+        self.send_event(gift_wrap).await
+    }
+
+    /// Seal `rumor` with the signer and gift wrap it for `receiver`
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/59.md>
+    #[cfg(feature = "nip59")]
+    pub async fn gift_wrap(
+        &self,
+        receiver: XOnlyPublicKey,
+        rumor: UnsignedEvent,
+    ) -> Result<Event, Error> {
+        let public_key: XOnlyPublicKey = self.signer.get_public_key().await?;
+        if rumor.pubkey != public_key {
+            return Err(nip59::Error::SenderMismatch.into());
+        }
+
+        let content: String = self
+            .signer
+            .nip44_encrypt(receiver, &rumor.as_json())
+            .await?;
+        let now = self.opts.get_clock().now();
+        let seal: UnsignedEvent = EventBuilder::new(Kind::Seal, content, &[])
+            .custom_created_at(nip59::randomize_timestamp(now))
+            .to_unsigned_event(public_key);
+        let seal: Event = self.signer.sign_event(seal).await?;
+
+        Ok(EventBuilder::gift_wrap_from_seal(&receiver, &seal, now)?)
+    }
+
+    /// Unwrap a gift wrap received by the signer
+    ///
+    /// The seal signature and the rumor id are verified.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/59.md>
+    #[cfg(feature = "nip59")]
+    pub async fn unwrap_gift_wrap(&self, gift_wrap: &Event) -> Result<UnwrappedGift, Error> {
+        if gift_wrap.kind != Kind::GiftWrap {
+            return Err(nip59::Error::NotGiftWrap.into());
+        }
+
+        let seal: String = self
+            .signer
+            .nip44_decrypt(gift_wrap.pubkey, &gift_wrap.content)
+            .await?;
+        let seal: Event = nip59::parse_seal(seal)?;
+        let rumor: String = self
+            .signer
+            .nip44_decrypt(seal.pubkey, &seal.content)
+            .await?;
+        Ok(UnwrappedGift::from_seal(&seal, rumor)?)
+    }
+
     /// Repost event
     pub async fn repost_event(
         &self,
@@ -6,6 +6,10 @@ use std::sync::atomic::AtomicU8;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use nostr::types::time::{Clock, SystemClock};
+
+use crate::relay::pool::RelayPoolOptions;
+
 /// Options
 #[derive(Debug, Clone)]
 pub struct Options {
@@ -16,6 +20,11 @@ pub struct Options {
     /// POW difficulty (for all events)
     #[cfg(feature = "nip13")]
     difficulty: Arc<AtomicU8>,
+    /// Min POW difficulty of the received events
+    #[cfg(feature = "nip13")]
+    min_pow: u8,
+    /// Clock used for the `created_at` of the new events and for the expiration checks
+    clock: Arc<dyn Clock>,
 }
 
 impl Default for Options {
@@ -25,6 +34,9 @@ impl Default for Options {
             wait_for_send: Arc::new(AtomicBool::new(false)),
             #[cfg(feature = "nip13")]
             difficulty: Arc::new(AtomicU8::new(0)),
+            #[cfg(feature = "nip13")]
+            min_pow: 0,
+            clock: Arc::new(SystemClock),
         }
     }
 }
@@ -79,4 +91,33 @@ impl Options {
             .difficulty
             .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |_| Some(difficulty));
     }
+
+    /// Discard the received events with a POW difficulty lower than `difficulty` (default: 0)
+    ///
+    /// The difficulty is the target committed in the `nonce` tag, if the event id meets it.
+    ///
+    /// Read once, when the `Client` is built.
+    #[cfg(feature = "nip13")]
+    pub fn min_pow(self, difficulty: u8) -> Self {
+        Self {
+            min_pow: difficulty,
+            ..self
+        }
+    }
+
+    /// Set the [`Clock`] used for the `created_at` of the new events and for the expiration checks
+    pub fn clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub(crate) fn get_clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    pub(crate) fn get_pool_opts(&self) -> RelayPoolOptions {
+        let opts = RelayPoolOptions::new().clock(self.get_clock());
+        #[cfg(feature = "nip13")]
+        let opts = opts.min_pow(self.min_pow);
+        opts
+    }
 }
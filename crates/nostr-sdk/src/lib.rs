@@ -19,17 +19,25 @@ use tokio::runtime::Runtime;
 pub use nostr;
 pub use nostr::Result;
 
+#[cfg(feature = "nip46")]
+pub mod bunker;
 pub mod client;
 pub mod prelude;
 pub mod relay;
+pub mod signer;
 pub mod subscription;
 mod thread;
 
+#[cfg(feature = "nip46")]
+pub use self::bunker::Bunker;
 #[cfg(feature = "blocking")]
 pub use self::client::blocking;
 pub use self::client::{Client, Options};
-pub use self::relay::pool::{RelayPool, RelayPoolNotification};
+pub use self::relay::pool::{RelayPool, RelayPoolNotification, RelayPoolOptions};
 pub use self::relay::{Relay, RelayOptions, RelayStatus};
+#[cfg(feature = "nip46")]
+pub use self::signer::nip46::Nip46Signer;
+pub use self::signer::Signer;
 
 #[cfg(feature = "blocking")]
 static RUNTIME: Lazy<Runtime> = Lazy::new(|| Runtime::new().expect("Can't start Tokio runtime"));
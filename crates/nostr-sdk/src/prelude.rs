@@ -3,6 +3,10 @@
 
 //! Prelude
 
+// Some names (ex. `Error`) are ambiguous: import them from their module
+#![allow(unknown_lints)]
+#![allow(ambiguous_glob_reexports)]
+
 // External crates
 pub use nostr::prelude::*;
 
@@ -5,7 +5,7 @@
 
 use std::fmt;
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -106,6 +106,10 @@ pub struct RelayOptions {
     read: Arc<AtomicBool>,
     /// Allow/disallow write actions
     write: Arc<AtomicBool>,
+    /// Max number of filters per REQ (`0` means no limit)
+    max_filters: Arc<AtomicUsize>,
+    /// Max number of values per filter field (`0` means no limit)
+    max_values_per_filter: Arc<AtomicUsize>,
 }
 
 impl Default for RelayOptions {
@@ -120,6 +124,8 @@ impl RelayOptions {
         Self {
             read: Arc::new(AtomicBool::new(read)),
             write: Arc::new(AtomicBool::new(write)),
+            max_filters: Arc::new(AtomicUsize::new(0)),
+            max_values_per_filter: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -146,6 +152,49 @@ impl RelayOptions {
             .write
             .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |_| Some(write));
     }
+
+    /// Get max number of filters per REQ
+    pub fn max_filters(&self) -> Option<usize> {
+        match self.max_filters.load(Ordering::SeqCst) {
+            0 => None,
+            max => Some(max),
+        }
+    }
+
+    /// Set max number of filters per REQ
+    ///
+    /// The filters exceeding the limit are sent in other REQs.
+    pub fn set_max_filters(&self, max: Option<usize>) {
+        let _ = self
+            .max_filters
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |_| {
+                Some(max.unwrap_or_default())
+            });
+    }
+
+    /// Get max number of values per filter field (ex. `authors`)
+    pub fn max_values_per_filter(&self) -> Option<usize> {
+        match self.max_values_per_filter.load(Ordering::SeqCst) {
+            0 => None,
+            max => Some(max),
+        }
+    }
+
+    /// Set max number of values per filter field (ex. `authors`)
+    ///
+    /// The filters exceeding the limit are split in more filters.
+    pub fn set_max_values_per_filter(&self, max: Option<usize>) {
+        let _ = self
+            .max_values_per_filter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |_| {
+                Some(max.unwrap_or_default())
+            });
+    }
+
+    /// Split the filters in batches, according to the limits
+    fn batch_filters(&self, filters: Vec<Filter>) -> Vec<Vec<Filter>> {
+        Filter::batch(filters, self.max_values_per_filter(), self.max_filters())
+    }
 }
 
 /// Relay
@@ -385,7 +434,8 @@ impl Relay {
                             let data: Vec<u8> = msg.into_data();
 
                             match String::from_utf8(data) {
-                                Ok(data) => match RelayMessage::from_json(&data) {
+                                // The events are verified in batch by the pool
+                                Ok(data) => match RelayMessage::from_json_unverified(&data) {
                                     Ok(msg) => {
                                         log::trace!("Received message to {}: {:?}", relay.url, msg);
                                         if let Err(err) = relay
@@ -516,6 +566,8 @@ impl Relay {
     }
 
     /// Subscribe
+    ///
+    /// Filters exceeding the relay limits are sent in more REQs: return the id of the first one.
     pub async fn subscribe(&self, wait: bool) -> Result<SubscriptionId, Error> {
         if !self.opts.read() {
             return Err(Error::ReadDisabled);
@@ -528,13 +580,26 @@ impl Relay {
             return Err(Error::FiltersEmpty);
         }
 
-        let channel = subscription.get_channel(&self.url());
-        let channel_id = channel.id();
+        // Filters exceeding the relay limits are subscribed with more REQs
+        let batches: Vec<Vec<Filter>> = self.opts.batch_filters(filters);
+
+        let mut channel = subscription.get_channel(&self.url());
+        let old_ids: Vec<SubscriptionId> = channel.ids();
+        channel.set_batches(batches.len());
+        let ids: Vec<SubscriptionId> = channel.ids();
+        subscription.add_channel(&self.url(), channel.clone());
 
-        self.send_msg(ClientMessage::new_req(channel_id.clone(), filters), wait)
-            .await?;
+        // Close the REQs of the batches no more used
+        for id in old_ids.into_iter().skip(ids.len()) {
+            self.send_msg(ClientMessage::close(id), wait).await?;
+        }
 
-        Ok(channel_id)
+        for (id, filters) in ids.into_iter().zip(batches) {
+            self.send_msg(ClientMessage::new_req(id, filters), wait)
+                .await?;
+        }
+
+        Ok(channel.id())
     }
 
     /// Unsubscribe
@@ -545,13 +610,17 @@ impl Relay {
 
         let mut subscription = SUBSCRIPTION.lock().await;
         if let Some(channel) = subscription.remove_channel(&self.url()) {
-            self.send_msg(ClientMessage::close(channel.id()), wait)
-                .await?;
+            for id in channel.ids().into_iter() {
+                self.send_msg(ClientMessage::close(id), wait).await?;
+            }
         }
         Ok(())
     }
 
     /// Get events of filters with custom callback
+    ///
+    /// Filters exceeding the relay limits are requested in more REQs, all sent at once:
+    /// `timeout` applies to the whole request. The REQs are closed on return.
     pub async fn get_events_of_with_callback<F>(
         &self,
         filters: Vec<Filter>,
@@ -565,116 +634,107 @@ impl Relay {
             return Err(Error::ReadDisabled);
         }
 
-        let id = SubscriptionId::generate();
-
-        self.send_msg(ClientMessage::new_req(id.clone(), filters), false)
-            .await?;
+        let batches: Vec<(SubscriptionId, Vec<Filter>)> = self
+            .opts
+            .batch_filters(filters)
+            .into_iter()
+            .map(|filters| (SubscriptionId::generate(), filters))
+            .collect();
+        let mut pending: Vec<SubscriptionId> = batches.iter().map(|(id, _)| id.clone()).collect();
 
+        // Listen before sending the REQs, to not miss any event
         let mut notifications = self.notification_sender.subscribe();
-        let recv = async {
-            while let Ok(notification) = notifications.recv().await {
-                if let RelayPoolNotification::Message(_, msg) = notification {
-                    match msg {
-                        RelayMessage::Event {
-                            subscription_id,
-                            event,
-                        } => {
-                            if subscription_id.eq(&id) {
-                                callback(*event).await;
+
+        let mut res: Result<(), Error> = Ok(());
+        let mut sent: Vec<SubscriptionId> = Vec::with_capacity(pending.len());
+        for (id, filters) in batches.into_iter() {
+            match self
+                .send_msg(ClientMessage::new_req(id.clone(), filters), false)
+                .await
+            {
+                Ok(()) => sent.push(id),
+                Err(e) => {
+                    res = Err(e);
+                    break;
+                }
+            }
+        }
+
+        if res.is_ok() {
+            let recv = async {
+                while !pending.is_empty() {
+                    match notifications.recv().await {
+                        Ok(RelayPoolNotification::Message(_, msg)) => match msg {
+                            RelayMessage::Event {
+                                subscription_id,
+                                event,
+                            } => {
+                                if pending.contains(&subscription_id) {
+                                    callback(*event).await;
+                                }
                             }
-                        }
-                        RelayMessage::EndOfStoredEvents(subscription_id) => {
-                            if subscription_id.eq(&id) {
-                                break;
+                            RelayMessage::EndOfStoredEvents(subscription_id) => {
+                                pending.retain(|id| id != &subscription_id);
                             }
-                        }
-                        _ => log::debug!("Receive unhandled message {msg:?} on get_events_of"),
-                    };
+                            _ => log::debug!("Receive unhandled message {msg:?} on get_events_of"),
+                        },
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => (),
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
                 }
-            }
-        };
+            };
 
-        if let Some(timeout) = timeout {
-            if tokio::time::timeout(timeout, recv).await.is_err() {
-                return Err(Error::Timeout);
+            if let Some(timeout) = timeout {
+                if tokio::time::timeout(timeout, recv).await.is_err() {
+                    res = Err(Error::Timeout);
+                }
+            } else {
+                recv.await;
             }
-        } else {
-            recv.await;
         }
 
         // Unsubscribe
-        self.send_msg(ClientMessage::close(id), false).await?;
+        for id in sent.into_iter() {
+            if let Err(e) = self.send_msg(ClientMessage::close(id), false).await {
+                log::error!("Impossible to close subscription with {}: {e}", self.url());
+            }
+        }
 
-        Ok(())
+        res
     }
 
     /// Get events of filters
+    ///
+    /// On timeout, return the events received until then.
     pub async fn get_events_of(
         &self,
         filters: Vec<Filter>,
         timeout: Option<Duration>,
     ) -> Result<Vec<Event>, Error> {
         let events: Mutex<Vec<Event>> = Mutex::new(Vec::new());
-        self.get_events_of_with_callback(filters, timeout, |event| async {
-            let mut events = events.lock().await;
-            events.push(event);
-        })
-        .await?;
+        match self
+            .get_events_of_with_callback(filters, timeout, |event| async {
+                let mut events = events.lock().await;
+                events.push(event);
+            })
+            .await
+        {
+            Ok(()) => (),
+            Err(Error::Timeout) => log::warn!("Timeout while getting events from {}", self.url()),
+            Err(e) => return Err(e),
+        }
         Ok(events.into_inner())
     }
 
     /// Request events of filter. All events will be sent to notification listener
     pub fn req_events_of(&self, filters: Vec<Filter>, timeout: Option<Duration>) {
-        if !self.opts.read() {
-            log::error!("{}", Error::ReadDisabled);
-        }
-
         let relay = self.clone();
         thread::spawn(async move {
-            let id = SubscriptionId::generate();
-
-            // Subscribe
             if let Err(e) = relay
-                .send_msg(ClientMessage::new_req(id.clone(), filters), false)
+                .get_events_of_with_callback(filters, timeout, |_| async {})
                 .await
             {
-                log::error!(
-                    "Impossible to send REQ to {}: {}",
-                    relay.url(),
-                    e.to_string()
-                );
-            };
-
-            let mut notifications = relay.notification_sender.subscribe();
-            let recv = async {
-                while let Ok(notification) = notifications.recv().await {
-                    if let RelayPoolNotification::Message(
-                        _,
-                        RelayMessage::EndOfStoredEvents(subscription_id),
-                    ) = notification
-                    {
-                        if subscription_id.eq(&id) {
-                            break;
-                        }
-                    }
-                }
-            };
-
-            if let Some(timeout) = timeout {
-                if tokio::time::timeout(timeout, recv).await.is_err() {
-                    log::error!("{}", Error::Timeout);
-                }
-            } else {
-                recv.await;
-            }
-
-            // Unsubscribe
-            if let Err(e) = relay.send_msg(ClientMessage::close(id), false).await {
-                log::error!(
-                    "Impossible to close subscription with {}: {}",
-                    relay.url(),
-                    e.to_string()
-                );
+                log::error!("Impossible to request events from {}: {e}", relay.url());
             }
         });
     }
@@ -10,6 +10,7 @@ use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
+use nostr::types::time::{Clock, SystemClock};
 use nostr::url::Url;
 use nostr::{ClientMessage, Event, EventId, Filter, RelayMessage};
 #[cfg(feature = "sqlite")]
@@ -78,20 +79,80 @@ pub enum RelayPoolNotification {
     Shutdown,
 }
 
+/// [`RelayPool`] options
+#[derive(Debug, Clone)]
+pub struct RelayPoolOptions {
+    /// Clock used to check the expiration of the received events
+    clock: Arc<dyn Clock>,
+    /// Min POW difficulty of the received events
+    #[cfg(feature = "nip13")]
+    min_pow: u8,
+    /// Interval between two purges of the expired events from the store (NIP-40)
+    #[cfg(feature = "sqlite")]
+    purge_interval: Duration,
+}
+
+impl Default for RelayPoolOptions {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            #[cfg(feature = "nip13")]
+            min_pow: 0,
+            #[cfg(feature = "sqlite")]
+            purge_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RelayPoolOptions {
+    /// New default [`RelayPoolOptions`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`Clock`]
+    pub fn clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Discard the received events with a POW difficulty lower than `difficulty`
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/13.md>
+    #[cfg(feature = "nip13")]
+    pub fn min_pow(self, difficulty: u8) -> Self {
+        Self {
+            min_pow: difficulty,
+            ..self
+        }
+    }
+
+    /// Set the interval between two purges of the expired events from the store (NIP-40, min 1 sec)
+    #[cfg(feature = "sqlite")]
+    pub fn purge_interval(self, interval: Duration) -> Self {
+        Self {
+            purge_interval: std::cmp::max(interval, Duration::from_secs(1)),
+            ..self
+        }
+    }
+}
+
 struct RelayPoolTask {
     receiver: Receiver<RelayPoolMessage>,
     notification_sender: broadcast::Sender<RelayPoolNotification>,
     events: VecDeque<EventId>,
     #[cfg(feature = "sqlite")]
     store: Option<Store>,
+    opts: RelayPoolOptions,
 }
 
 const MAX_EVENTS: usize = 100000;
+const MAX_BATCH_SIZE: usize = 1024;
 
 impl RelayPoolTask {
     pub fn new(
         pool_task_receiver: Receiver<RelayPoolMessage>,
         notification_sender: broadcast::Sender<RelayPoolNotification>,
+        opts: RelayPoolOptions,
     ) -> Self {
         Self {
             receiver: pool_task_receiver,
@@ -99,6 +160,7 @@ impl RelayPoolTask {
             notification_sender,
             #[cfg(feature = "sqlite")]
             store: None,
+            opts,
         }
     }
 
@@ -107,34 +169,94 @@ impl RelayPoolTask {
         pool_task_receiver: Receiver<RelayPoolMessage>,
         notification_sender: broadcast::Sender<RelayPoolNotification>,
         store: Option<Store>,
+        opts: RelayPoolOptions,
     ) -> Self {
         Self {
             receiver: pool_task_receiver,
             events: VecDeque::new(),
             notification_sender,
             store,
+            opts,
         }
     }
 
     pub async fn run(&mut self) {
         log::debug!("RelayPoolTask Thread Started");
-        while let Some(msg) = self.receiver.recv().await {
-            match msg {
-                RelayPoolMessage::ReceivedMsg { relay_url, msg } => {
-                    let _ = self
-                        .notification_sender
-                        .send(RelayPoolNotification::Message(
-                            relay_url.clone(),
-                            msg.clone(),
-                        ));
-
-                    if let RelayMessage::Event {
-                        subscription_id: _,
-                        event,
-                    } = msg
-                    {
-                        // Verifies if the event is valid
-                        if event.verify().is_ok() {
+        #[cfg(feature = "sqlite")]
+        let mut purge = time::interval(self.opts.purge_interval);
+        loop {
+            #[cfg(feature = "sqlite")]
+            let msg = tokio::select! {
+                msg = self.receiver.recv() => msg,
+                _ = purge.tick() => {
+                    self.purge_expired_events();
+                    continue;
+                }
+            };
+            #[cfg(not(feature = "sqlite"))]
+            let msg = self.receiver.recv().await;
+
+            let msg = match msg {
+                Some(msg) => msg,
+                None => break,
+            };
+
+            // Collect the already queued messages, so the events can be verified in batch
+            let mut msgs: Vec<RelayPoolMessage> = vec![msg];
+            while msgs.len() < MAX_BATCH_SIZE {
+                match self.receiver.try_recv() {
+                    Ok(msg) => msgs.push(msg),
+                    Err(_) => break,
+                }
+            }
+
+            // Discard the events with not enough POW (NIP-13) before the batch verification.
+            // The id is not verified yet: a forged id may pass this check,
+            // but the event is then discarded by `verify_events`.
+            #[cfg(feature = "nip13")]
+            if self.opts.min_pow > 0 {
+                let min_pow: u8 = self.opts.min_pow;
+                msgs.retain(|msg| match msg {
+                    RelayPoolMessage::ReceivedMsg {
+                        relay_url,
+                        msg: RelayMessage::Event { event, .. },
+                    } => {
+                        let ok: bool = event.check_pow(min_pow);
+                        if !ok {
+                            log::debug!(
+                                "Event {} with not enough POW received from {relay_url}",
+                                event.id
+                            );
+                        }
+                        ok
+                    }
+                    _ => true,
+                });
+            }
+
+            for msg in verify_events(msgs).await.into_iter() {
+                match msg {
+                    RelayPoolMessage::ReceivedMsg { relay_url, msg } => {
+                        if let RelayMessage::Event { event, .. } = &msg {
+                            // Discard expired events (NIP-40)
+                            if event.is_expired_at(self.opts.clock.now()) {
+                                log::debug!("Expired event {} received from {relay_url}", event.id);
+                                continue;
+                            }
+                        }
+
+                        let _ = self
+                            .notification_sender
+                            .send(RelayPoolNotification::Message(
+                                relay_url.clone(),
+                                msg.clone(),
+                            ));
+
+                        if let RelayMessage::Event {
+                            subscription_id: _,
+                            event,
+                        } = msg
+                        {
                             // Adds only new events
                             if !self.events.contains(&event.id) {
                                 self.add_event(event.id);
@@ -155,25 +277,35 @@ impl RelayPoolTask {
                             }
                         }
                     }
-                }
-                RelayPoolMessage::EventSent(event) => {
-                    self.add_event(event.id);
-                }
-                RelayPoolMessage::Shutdown => {
-                    if let Err(e) = self
-                        .notification_sender
-                        .send(RelayPoolNotification::Shutdown)
-                    {
-                        log::error!("Impossible to send shutdown notification: {}", e);
+                    RelayPoolMessage::EventSent(event) => {
+                        self.add_event(event.id);
+                    }
+                    RelayPoolMessage::Shutdown => {
+                        if let Err(e) = self
+                            .notification_sender
+                            .send(RelayPoolNotification::Shutdown)
+                        {
+                            log::error!("Impossible to send shutdown notification: {}", e);
+                        }
+                        log::debug!("Exited from RelayPoolTask thread");
+                        self.receiver.close();
+                        return;
                     }
-                    log::debug!("Exited from RelayPoolTask thread");
-                    self.receiver.close();
-                    break;
                 }
             }
         }
     }
 
+    #[cfg(feature = "sqlite")]
+    fn purge_expired_events(&self) {
+        if let Some(store) = &self.store {
+            match store.delete_expired_events() {
+                Ok(deleted) => log::trace!("Purged {deleted} expired events from store"),
+                Err(e) => log::error!("Impossible to purge expired events from store: {e}"),
+            }
+        }
+    }
+
     fn add_event(&mut self, event_id: EventId) {
         while self.events.len() >= MAX_EVENTS {
             self.events.pop_front();
@@ -182,12 +314,73 @@ impl RelayPoolTask {
     }
 }
 
+/// Verify the events received from relays, discarding the invalid ones
+///
+/// The verification is executed on a blocking thread, to not stall the pool task.
+async fn verify_events(msgs: Vec<RelayPoolMessage>) -> Vec<RelayPoolMessage> {
+    let events: Vec<Event> = msgs
+        .iter()
+        .filter_map(|msg| match msg {
+            RelayPoolMessage::ReceivedMsg {
+                msg: RelayMessage::Event { event, .. },
+                ..
+            } => Some(event.as_ref().clone()),
+            _ => None,
+        })
+        .collect();
+
+    if events.is_empty() {
+        return msgs;
+    }
+
+    let len: usize = events.len();
+    let mut results = match tokio::task::spawn_blocking(move || Event::verify_batch(&events)).await
+    {
+        Ok(results) => results.into_iter(),
+        Err(e) => {
+            // Unverified events can't be trusted: keep only the other messages
+            log::error!("Impossible to verify {len} events, discarding them: {e}");
+            return msgs
+                .into_iter()
+                .filter(|msg| {
+                    !matches!(
+                        msg,
+                        RelayPoolMessage::ReceivedMsg {
+                            msg: RelayMessage::Event { .. },
+                            ..
+                        }
+                    )
+                })
+                .collect();
+        }
+    };
+
+    msgs.into_iter()
+        .filter(|msg| match msg {
+            RelayPoolMessage::ReceivedMsg {
+                relay_url,
+                msg: RelayMessage::Event { event, .. },
+            } => match results.next() {
+                Some(Ok(())) => true,
+                Some(Err(e)) => {
+                    log::warn!("Invalid event {} received from {relay_url}: {e}", event.id);
+                    false
+                }
+                // `Event::verify_batch` returns one result per event
+                None => false,
+            },
+            _ => true,
+        })
+        .collect()
+}
+
 /// Relay Pool
 #[derive(Debug, Clone)]
 pub struct RelayPool {
     relays: Arc<Mutex<HashMap<Url, Relay>>>,
     pool_task_sender: Sender<RelayPoolMessage>,
     notification_sender: broadcast::Sender<RelayPoolNotification>,
+    clock: Arc<dyn Clock>,
     #[cfg(feature = "sqlite")]
     store: Option<Store>,
 }
@@ -201,11 +394,17 @@ impl Default for RelayPool {
 impl RelayPool {
     /// Create new `RelayPool`
     pub fn new() -> Self {
+        Self::new_with_opts(RelayPoolOptions::default())
+    }
+
+    /// Create new `RelayPool` with [`RelayPoolOptions`]
+    pub fn new_with_opts(opts: RelayPoolOptions) -> Self {
         let (notification_sender, _) = broadcast::channel(1024);
         let (pool_task_sender, pool_task_receiver) = mpsc::channel(1024);
 
+        let clock: Arc<dyn Clock> = opts.clock.clone();
         let mut relay_pool_task =
-            RelayPoolTask::new(pool_task_receiver, notification_sender.clone());
+            RelayPoolTask::new(pool_task_receiver, notification_sender.clone(), opts);
 
         thread::spawn(async move { relay_pool_task.run().await });
 
@@ -213,26 +412,38 @@ impl RelayPool {
             relays: Arc::new(Mutex::new(HashMap::new())),
             pool_task_sender,
             notification_sender,
+            clock,
             #[cfg(feature = "sqlite")]
             store: None,
         }
     }
 
-    /// Create new `RelayPool`
+    /// Create new `RelayPool` with [`Store`]
     #[cfg(feature = "sqlite")]
     pub fn new_with_store<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::new_with_store_and_opts(path, RelayPoolOptions::default())
+    }
+
+    /// Create new `RelayPool` with [`Store`] and [`RelayPoolOptions`]
+    #[cfg(feature = "sqlite")]
+    pub fn new_with_store_and_opts<P>(path: P, opts: RelayPoolOptions) -> Result<Self, Error>
     where
         P: AsRef<Path>,
     {
         let (notification_sender, _) = broadcast::channel(1024);
         let (pool_task_sender, pool_task_receiver) = mpsc::channel(1024);
 
-        let store = Some(Store::open(path)?);
+        let clock: Arc<dyn Clock> = opts.clock.clone();
+        let store = Some(Store::open_with_clock(path, clock.clone())?);
 
         let mut relay_pool_task = RelayPoolTask::new_with_store(
             pool_task_receiver,
             notification_sender.clone(),
             store.clone(),
+            opts,
         );
 
         thread::spawn(async move { relay_pool_task.run().await });
@@ -241,11 +452,17 @@ impl RelayPool {
             relays: Arc::new(Mutex::new(HashMap::new())),
             pool_task_sender,
             notification_sender,
+            clock,
             #[cfg(feature = "sqlite")]
             store,
         })
     }
 
+    /// Get [`Clock`]
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
     /// Get new notification listener
     pub fn notifications(&self) -> broadcast::Receiver<RelayPoolNotification> {
         self.notification_sender.subscribe()
@@ -363,12 +580,14 @@ impl RelayPool {
     }
 
     /// Subscribe to filters
+    ///
+    /// The filters are compacted with [`Filter::compact`] and split according to the [`RelayOptions`] of each relay.
     pub async fn subscribe(&self, filters: Vec<Filter>, wait: bool) {
         let relays = self.relays().await;
 
         {
             let mut subscription = SUBSCRIPTION.lock().await;
-            subscription.update_filters(filters.clone());
+            subscription.update_filters(Filter::compact(filters));
         }
 
         for relay in relays.values() {
@@ -389,11 +608,14 @@ impl RelayPool {
     }
 
     /// Get events of filters
+    ///
+    /// The filters are compacted with [`Filter::compact`] and split according to the [`RelayOptions`] of each relay.
     pub async fn get_events_of(
         &self,
         filters: Vec<Filter>,
         timeout: Option<Duration>,
     ) -> Result<Vec<Event>, Error> {
+        let filters: Vec<Filter> = Filter::compact(filters);
         let events: Arc<Mutex<Vec<Event>>> = Arc::new(Mutex::new(Vec::new()));
         let mut handles = Vec::new();
         let relays = self.relays().await;
@@ -422,6 +644,7 @@ impl RelayPool {
 
     /// Request events of filter. All events will be sent to notification listener
     pub async fn req_events_of(&self, filters: Vec<Filter>, timeout: Option<Duration>) {
+        let filters: Vec<Filter> = Filter::compact(filters);
         let relays = self.relays().await;
         for relay in relays.values() {
             relay.req_events_of(filters.clone(), timeout);
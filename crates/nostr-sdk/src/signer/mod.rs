@@ -0,0 +1,193 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Signer
+
+use std::fmt;
+
+use async_trait::async_trait;
+use nostr::event::unsigned;
+use nostr::key::{self, XOnlyPublicKey};
+#[cfg(feature = "nip04")]
+use nostr::nips::nip04;
+#[cfg(feature = "nip44")]
+use nostr::nips::nip44;
+use nostr::{Event, Keys, UnsignedEvent};
+
+#[cfg(feature = "nip46")]
+pub mod nip46;
+
+/// [`Signer`] error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Keys error
+    #[error(transparent)]
+    Keys(#[from] key::Error),
+    /// Unsigned event error
+    #[error(transparent)]
+    Unsigned(#[from] unsigned::Error),
+    /// NIP04 error
+    #[cfg(feature = "nip04")]
+    #[error(transparent)]
+    NIP04(#[from] nip04::Error),
+    /// NIP44 error
+    #[cfg(feature = "nip44")]
+    #[error(transparent)]
+    NIP44(#[from] nip44::Error),
+    /// NIP46 error
+    #[cfg(feature = "nip46")]
+    #[error(transparent)]
+    NIP46(#[from] nip46::Error),
+    /// Error of a custom [`Signer`] implementation
+    #[error("{0}")]
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+    /// Operation not supported by the [`Signer`]
+    #[error("{0} not supported by the signer")]
+    Unsupported(&'static str),
+}
+
+impl Error {
+    /// New error of a custom [`Signer`] implementation
+    pub fn backend<E>(error: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self::Backend(Box::new(error))
+    }
+}
+
+/// Signer
+///
+/// Holds the keys used by the [`Client`](crate::Client) to sign events and to encrypt and decrypt messages.
+/// Implemented for [`Keys`]: implement it to plug in remote signers or a custom key custody.
+/// The NIP04 and NIP44 methods return [`Error::Unsupported`] if not implemented.
+///
+/// # Example
+/// ```rust
+/// use nostr_sdk::prelude::*;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let keys = Keys::generate();
+/// let unsigned = EventBuilder::new_text_note("hello", &[]).to_unsigned_event(keys.public_key());
+/// let event = keys.sign_event(unsigned.clone()).await.unwrap();
+/// assert!(event.verify().is_ok());
+///
+/// // Events of another author or without secret key can't be signed
+/// assert!(Keys::generate().sign_event(unsigned.clone()).await.is_err());
+/// let read_only = Keys::from_public_key(keys.public_key());
+/// assert!(read_only.sign_event(unsigned).await.is_err());
+/// # }
+/// ```
+#[async_trait]
+pub trait Signer: fmt::Debug + Send + Sync {
+    /// Get the public key of the signer
+    async fn get_public_key(&self) -> Result<XOnlyPublicKey, Error>;
+
+    /// Sign an [`UnsignedEvent`]
+    async fn sign_event(&self, unsigned: UnsignedEvent) -> Result<Event, Error>;
+
+    /// Encrypt `content` for `public_key`
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/04.md>
+    #[cfg(feature = "nip04")]
+    async fn nip04_encrypt(
+        &self,
+        _public_key: XOnlyPublicKey,
+        _content: &str,
+    ) -> Result<String, Error> {
+        Err(Error::Unsupported("NIP04"))
+    }
+
+    /// Decrypt `encrypted_content` received from `public_key`
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/04.md>
+    #[cfg(feature = "nip04")]
+    async fn nip04_decrypt(
+        &self,
+        _public_key: XOnlyPublicKey,
+        _encrypted_content: &str,
+    ) -> Result<String, Error> {
+        Err(Error::Unsupported("NIP04"))
+    }
+
+    /// Encrypt `content` for `public_key`, using the latest NIP44 version
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/44.md>
+    #[cfg(feature = "nip44")]
+    async fn nip44_encrypt(
+        &self,
+        _public_key: XOnlyPublicKey,
+        _content: &str,
+    ) -> Result<String, Error> {
+        Err(Error::Unsupported("NIP44"))
+    }
+
+    /// Decrypt `payload` received from `public_key`
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/44.md>
+    #[cfg(feature = "nip44")]
+    async fn nip44_decrypt(
+        &self,
+        _public_key: XOnlyPublicKey,
+        _payload: &str,
+    ) -> Result<String, Error> {
+        Err(Error::Unsupported("NIP44"))
+    }
+}
+
+#[async_trait]
+impl Signer for Keys {
+    async fn get_public_key(&self) -> Result<XOnlyPublicKey, Error> {
+        Ok(self.public_key())
+    }
+
+    async fn sign_event(&self, unsigned: UnsignedEvent) -> Result<Event, Error> {
+        Ok(unsigned.sign(self)?)
+    }
+
+    #[cfg(feature = "nip04")]
+    async fn nip04_encrypt(
+        &self,
+        public_key: XOnlyPublicKey,
+        content: &str,
+    ) -> Result<String, Error> {
+        Ok(nip04::encrypt(&self.secret_key()?, &public_key, content)?)
+    }
+
+    #[cfg(feature = "nip04")]
+    async fn nip04_decrypt(
+        &self,
+        public_key: XOnlyPublicKey,
+        encrypted_content: &str,
+    ) -> Result<String, Error> {
+        Ok(nip04::decrypt(
+            &self.secret_key()?,
+            &public_key,
+            encrypted_content,
+        )?)
+    }
+
+    #[cfg(feature = "nip44")]
+    async fn nip44_encrypt(
+        &self,
+        public_key: XOnlyPublicKey,
+        content: &str,
+    ) -> Result<String, Error> {
+        Ok(nip44::encrypt(
+            &self.secret_key()?,
+            &public_key,
+            content,
+            nip44::Version::default(),
+        )?)
+    }
+
+    #[cfg(feature = "nip44")]
+    async fn nip44_decrypt(
+        &self,
+        public_key: XOnlyPublicKey,
+        payload: &str,
+    ) -> Result<String, Error> {
+        Ok(nip44::decrypt(&self.secret_key()?, &public_key, payload)?)
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP46 remote signer
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/46.md>
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use nostr::event::builder::Error as EventBuilderError;
+use nostr::key::XOnlyPublicKey;
+use nostr::nips::nip46::{
+    self, NostrConnectMessage, NostrConnectRequest, NostrConnectURI, ResponseResult,
+};
+use nostr::{
+    ClientMessage, Event, EventBuilder, Filter, Keys, Kind, SubscriptionId, UnsignedEvent,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::time;
+
+use super::{Error as SignerError, Signer};
+use crate::relay::pool::{
+    Error as RelayPoolError, RelayPool, RelayPoolNotification, RelayPoolOptions,
+};
+use crate::RelayOptions;
+
+/// [`Nip46Signer`] error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// NIP46 error
+    #[error(transparent)]
+    NIP46(#[from] nip46::Error),
+    /// Event builder error
+    #[error(transparent)]
+    EventBuilder(#[from] EventBuilderError),
+    /// Relay pool error
+    #[error(transparent)]
+    RelayPool(#[from] RelayPoolError),
+    /// The `nostrconnect://` URI was not generated with the app keys
+    #[error("the URI public key doesn't match the app keys")]
+    PublicKeyMismatch,
+    /// The remote signer replied with an unexpected result
+    #[error("unexpected result")]
+    UnexpectedResult,
+    /// The remote signer didn't reply in time
+    #[error("timeout")]
+    Timeout,
+    /// The relay pool was shut down
+    #[error("relay pool shut down")]
+    Shutdown,
+}
+
+/// NIP46 remote signer
+///
+/// Talks to a remote signer (bunker) through a dedicated [`RelayPool`],
+/// so the secret key of the user never reaches the app.
+#[derive(Debug, Clone)]
+pub struct Nip46Signer {
+    app_keys: Keys,
+    signer_public_key: XOnlyPublicKey,
+    user_public_key: XOnlyPublicKey,
+    pool: RelayPool,
+    subscription_id: SubscriptionId,
+    timeout: Duration,
+}
+
+impl Nip46Signer {
+    /// Connect to the remote signer
+    ///
+    /// With a `bunker://` URI, the app sends the `connect` request to the remote signer.
+    /// With a `nostrconnect://` URI (generated with the `app_keys`), the app waits for the remote signer
+    /// to send the `connect` request.
+    ///
+    /// `timeout` applies to every request sent to the remote signer.
+    pub async fn new(
+        uri: &NostrConnectURI,
+        app_keys: Keys,
+        timeout: Duration,
+    ) -> Result<Self, Error> {
+        Self::new_with_opts(uri, app_keys, timeout, RelayPoolOptions::default()).await
+    }
+
+    /// Connect to the remote signer, with [`RelayPoolOptions`]
+    pub async fn new_with_opts(
+        uri: &NostrConnectURI,
+        app_keys: Keys,
+        timeout: Duration,
+        opts: RelayPoolOptions,
+    ) -> Result<Self, Error> {
+        let pool = RelayPool::new_with_opts(opts);
+        for url in uri.relays().into_iter() {
+            pool.add_relay(url, None, RelayOptions::default()).await?;
+        }
+        pool.connect(true).await;
+
+        let subscription_id = SubscriptionId::generate();
+        let mut notifications = pool.notifications();
+        subscribe(&pool, &subscription_id, &app_keys).await?;
+
+        let signer_public_key: XOnlyPublicKey = match uri {
+            NostrConnectURI::Bunker {
+                signer_public_key, ..
+            } => *signer_public_key,
+            NostrConnectURI::Client { public_key, .. } => {
+                if *public_key != app_keys.public_key() {
+                    return Err(Error::PublicKeyMismatch);
+                }
+                time::timeout(
+                    timeout,
+                    wait_for_connect(&pool, &app_keys, &mut notifications),
+                )
+                .await
+                .map_err(|_| Error::Timeout)??
+            }
+        };
+
+        let mut signer = Self {
+            app_keys,
+            signer_public_key,
+            user_public_key: signer_public_key,
+            pool,
+            subscription_id,
+            timeout,
+        };
+
+        if let NostrConnectURI::Bunker { secret, .. } = uri {
+            let req = NostrConnectRequest::Connect {
+                public_key: signer_public_key,
+                secret: secret.clone(),
+            };
+            signer.send_request(req).await?;
+        }
+
+        match signer
+            .send_request(NostrConnectRequest::GetPublicKey)
+            .await?
+        {
+            ResponseResult::GetPublicKey(public_key) => signer.user_public_key = public_key,
+            _ => return Err(Error::UnexpectedResult),
+        }
+
+        Ok(signer)
+    }
+
+    /// Get the public key of the remote signer
+    ///
+    /// May differ from the public key of the user.
+    pub fn signer_public_key(&self) -> XOnlyPublicKey {
+        self.signer_public_key
+    }
+
+    /// Check that the remote signer is reachable
+    pub async fn ping(&self) -> Result<(), Error> {
+        match self.send_request(NostrConnectRequest::Ping).await? {
+            ResponseResult::Pong => Ok(()),
+            _ => Err(Error::UnexpectedResult),
+        }
+    }
+
+    /// Send a request and wait for the response of the remote signer
+    pub async fn send_request(&self, req: NostrConnectRequest) -> Result<ResponseResult, Error> {
+        let method = req.method();
+        let msg = NostrConnectMessage::request(req);
+        let id: String = msg.id().to_string();
+
+        // Subscribe again before each request: the subscription is not restored on reconnection
+        let mut notifications = self.pool.notifications();
+        subscribe(&self.pool, &self.subscription_id, &self.app_keys).await?;
+
+        let event: Event =
+            EventBuilder::nostr_connect(&self.app_keys, self.signer_public_key, msg)?
+                .to_event(&self.app_keys)?;
+        self.pool
+            .send_msg(ClientMessage::new_event(event), false)
+            .await?;
+
+        time::timeout(self.timeout, async {
+            loop {
+                let notification = match notifications.recv().await {
+                    Ok(notification) => notification,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("NIP46 signer lagged: {skipped} notifications skipped");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return Err(Error::Shutdown),
+                };
+
+                if let RelayPoolNotification::Event(_, event) = notification {
+                    if event.kind != Kind::NostrConnect || event.pubkey != self.signer_public_key {
+                        continue;
+                    }
+
+                    match NostrConnectMessage::from_event(&self.app_keys, &event) {
+                        Ok(msg) if msg.id() == id => return Ok(msg.to_response(method)?),
+                        Ok(_) => (),
+                        Err(e) => log::error!("Impossible to decrypt NIP46 message: {e}"),
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| Error::Timeout)?
+    }
+
+    /// Disconnect from the relays of the remote signer
+    pub async fn shutdown(self) -> Result<(), Error> {
+        Ok(self.pool.shutdown().await?)
+    }
+}
+
+async fn subscribe(
+    pool: &RelayPool,
+    subscription_id: &SubscriptionId,
+    app_keys: &Keys,
+) -> Result<(), Error> {
+    // Use a dedicated subscription: the pool one is shared with the client
+    let filter = Filter::new()
+        .pubkey(app_keys.public_key())
+        .kind(Kind::NostrConnect)
+        .since(pool.clock().now());
+    pool.send_msg(
+        ClientMessage::new_req(subscription_id.clone(), vec![filter]),
+        false,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Wait for the `connect` request of the remote signer and acknowledge it
+async fn wait_for_connect(
+    pool: &RelayPool,
+    app_keys: &Keys,
+    notifications: &mut Receiver<RelayPoolNotification>,
+) -> Result<XOnlyPublicKey, Error> {
+    loop {
+        let notification = match notifications.recv().await {
+            Ok(notification) => notification,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("NIP46 signer lagged: {skipped} notifications skipped");
+                continue;
+            }
+            Err(RecvError::Closed) => return Err(Error::Shutdown),
+        };
+
+        if let RelayPoolNotification::Event(_, event) = notification {
+            if event.kind != Kind::NostrConnect {
+                continue;
+            }
+
+            if let Ok(NostrConnectMessage::Request {
+                id,
+                req: NostrConnectRequest::Connect { .. },
+            }) = NostrConnectMessage::from_event(app_keys, &event)
+            {
+                let msg = NostrConnectMessage::response(id, Some(ResponseResult::Ack), None);
+                let ack: Event =
+                    EventBuilder::nostr_connect(app_keys, event.pubkey, msg)?.to_event(app_keys)?;
+                pool.send_msg(ClientMessage::new_event(ack), false).await?;
+                return Ok(event.pubkey);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for Nip46Signer {
+    async fn get_public_key(&self) -> Result<XOnlyPublicKey, SignerError> {
+        Ok(self.user_public_key)
+    }
+
+    async fn sign_event(&self, unsigned: UnsignedEvent) -> Result<Event, SignerError> {
+        let id = unsigned.id;
+        match self
+            .send_request(NostrConnectRequest::SignEvent(unsigned))
+            .await?
+        {
+            // The id commits to the whole content: check that the remote signer signed what we asked
+            ResponseResult::SignEvent(event) if event.id == id => Ok(*event),
+            _ => Err(Error::UnexpectedResult.into()),
+        }
+    }
+
+    #[cfg(feature = "nip04")]
+    async fn nip04_encrypt(
+        &self,
+        public_key: XOnlyPublicKey,
+        content: &str,
+    ) -> Result<String, SignerError> {
+        let req = NostrConnectRequest::Nip04Encrypt {
+            public_key,
+            text: content.to_string(),
+        };
+        match self.send_request(req).await? {
+            ResponseResult::Nip04Encrypt(ciphertext) => Ok(ciphertext),
+            _ => Err(Error::UnexpectedResult.into()),
+        }
+    }
+
+    #[cfg(feature = "nip04")]
+    async fn nip04_decrypt(
+        &self,
+        public_key: XOnlyPublicKey,
+        encrypted_content: &str,
+    ) -> Result<String, SignerError> {
+        let req = NostrConnectRequest::Nip04Decrypt {
+            public_key,
+            ciphertext: encrypted_content.to_string(),
+        };
+        match self.send_request(req).await? {
+            ResponseResult::Nip04Decrypt(content) => Ok(content),
+            _ => Err(Error::UnexpectedResult.into()),
+        }
+    }
+
+    async fn nip44_encrypt(
+        &self,
+        public_key: XOnlyPublicKey,
+        content: &str,
+    ) -> Result<String, SignerError> {
+        let req = NostrConnectRequest::Nip44Encrypt {
+            public_key,
+            text: content.to_string(),
+        };
+        match self.send_request(req).await? {
+            ResponseResult::Nip44Encrypt(payload) => Ok(payload),
+            _ => Err(Error::UnexpectedResult.into()),
+        }
+    }
+
+    async fn nip44_decrypt(
+        &self,
+        public_key: XOnlyPublicKey,
+        payload: &str,
+    ) -> Result<String, SignerError> {
+        let req = NostrConnectRequest::Nip44Decrypt {
+            public_key,
+            ciphertext: payload.to_string(),
+        };
+        match self.send_request(req).await? {
+            ResponseResult::Nip44Decrypt(content) => Ok(content),
+            _ => Err(Error::UnexpectedResult.into()),
+        }
+    }
+}
@@ -61,6 +61,7 @@ impl Subscription {
 #[derive(Debug, Clone)]
 pub struct Channel {
     id: SubscriptionId,
+    batches: usize,
     relay_url: Url,
 }
 
@@ -69,6 +70,7 @@ impl Channel {
     pub fn new(relay_url: Url) -> Self {
         Self {
             id: SubscriptionId::generate(),
+            batches: 1,
             relay_url,
         }
     }
@@ -78,6 +80,23 @@ impl Channel {
         self.id.clone()
     }
 
+    /// Get the ids of the channel REQs, one per batch of filters
+    ///
+    /// The first one is the channel id.
+    pub fn ids(&self) -> Vec<SubscriptionId> {
+        (0..self.batches)
+            .map(|i| match i {
+                0 => self.id(),
+                i => SubscriptionId::new(format!("{}:{i}", self.id.to_string())),
+            })
+            .collect()
+    }
+
+    /// Set the number of batches of filters
+    pub fn set_batches(&mut self, batches: usize) {
+        self.batches = std::cmp::max(batches, 1);
+    }
+
     /// Get channel relay url
     pub fn relay_url(&self) -> Url {
         self.relay_url.clone()
@@ -0,0 +1,90 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Compare the serialization of events and messages
+//! with the previous [`serde_json::Value`] based implementation
+
+use bitcoin_hashes::sha256::Hash as Sha256Hash;
+use bitcoin_hashes::Hash;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nostr::prelude::*;
+use serde_json::{json, Value};
+
+fn sample_event() -> Event {
+    let keys = Keys::generate();
+    let tags: Vec<Tag> = (0..10)
+        .map(|_| Tag::PubKey(Keys::generate().public_key(), None))
+        .chain([Tag::Hashtag(String::from("nostr"))])
+        .collect();
+    EventBuilder::new_text_note(
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit, \"sed\" do eiusmod tempor 🦀",
+        &tags,
+    )
+    .to_event(&keys)
+    .unwrap()
+}
+
+fn legacy_event_id(event: &Event) -> EventId {
+    let json: Value = json!([
+        0,
+        event.pubkey,
+        event.created_at,
+        event.kind,
+        event.tags,
+        event.content
+    ]);
+    EventId::from(Sha256Hash::hash(json.to_string().as_bytes()))
+}
+
+fn legacy_relay_message_from_json(msg: &str) -> Option<(SubscriptionId, Event)> {
+    let value: Value = serde_json::from_str(msg).ok()?;
+    let v = value.as_array()?;
+    if v.len() != 3 || v[0] != "EVENT" {
+        return None;
+    }
+    let subscription_id: SubscriptionId = serde_json::from_value(v[1].clone()).ok()?;
+    let event = Event::from_json(v[2].to_string()).ok()?;
+    Some((subscription_id, event))
+}
+
+fn event_id(c: &mut Criterion) {
+    let event = sample_event();
+    let mut group = c.benchmark_group("event_id");
+    group.bench_function("value", |b| b.iter(|| legacy_event_id(black_box(&event))));
+    group.bench_function("direct", |b| {
+        b.iter(|| {
+            EventId::new(
+                black_box(&event.pubkey),
+                black_box(event.created_at),
+                black_box(&event.kind),
+                black_box(&event.tags),
+                black_box(&event.content),
+            )
+        })
+    });
+    group.finish();
+}
+
+fn event_as_json(c: &mut Criterion) {
+    let event = sample_event();
+    let mut group = c.benchmark_group("event_as_json");
+    group.bench_function("value", |b| b.iter(|| json!(black_box(&event)).to_string()));
+    group.bench_function("direct", |b| b.iter(|| black_box(&event).as_json()));
+    group.finish();
+}
+
+fn relay_message_from_json(c: &mut Criterion) {
+    let msg: String =
+        RelayMessage::new_event(SubscriptionId::new("bench"), sample_event()).as_json();
+    let mut group = c.benchmark_group("relay_message_from_json");
+    group.bench_function("value", |b| {
+        b.iter(|| legacy_relay_message_from_json(black_box(&msg)))
+    });
+    group.bench_function("direct", |b| {
+        b.iter(|| RelayMessage::from_json(black_box(&msg)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, event_id, event_as_json, relay_message_from_json);
+criterion_main!(benches);
@@ -1,7 +1,7 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
-use nostr::nips::nip06::{FromMnemonic, GenerateMnemonic};
+use nostr::nips::nip06::{FromMnemonic, GenerateMnemonic, MnemonicAccount};
 use nostr::nips::nip19::ToBech32;
 use nostr::{Keys, Result};
 
@@ -15,5 +15,13 @@ fn main() -> Result<()> {
     let keys = Keys::from_mnemonic(MNEMONIC_PHRASE, Some("mypassphrase"))?;
     println!("{}", keys.secret_key()?.to_bech32()?);
 
+    for account in MnemonicAccount::derive_accounts(MNEMONIC_PHRASE, None, 3)? {
+        println!(
+            "Account {}: {}",
+            account.account(),
+            account.keys().public_key().to_bech32()?
+        );
+    }
+
     Ok(())
 }
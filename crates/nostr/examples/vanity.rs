@@ -1,6 +1,9 @@
 // Copyright (c) 2022-2023 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use std::time::Duration;
+
+use nostr::key::vanity::{self, VanityOptions, VanityPattern};
 use nostr::prelude::*;
 
 fn main() -> Result<()> {
@@ -8,5 +11,28 @@ fn main() -> Result<()> {
     let keys = Keys::vanity(vec!["0000", "yuk", "yuk0"], true, num_cores)?;
     println!("Secret key: {}", keys.secret_key()?.to_bech32()?);
     println!("Public key: {}", keys.public_key().to_bech32()?);
+
+    let patterns = vec![
+        VanityPattern::suffix("sats"),
+        VanityPattern::contains("yuk"),
+    ];
+    println!(
+        "Expected attempts: {:.0}",
+        vanity::expected_attempts(&patterns, true)?
+    );
+    let opts = VanityOptions::new()
+        .threads(num_cores)
+        .timeout(Duration::from_secs(60))
+        .progress(Duration::from_secs(1), |progress| {
+            println!(
+                "{} keys in {:?} ({:.0} keys/s)",
+                progress.attempts,
+                progress.elapsed,
+                progress.rate()
+            )
+        });
+    let keys = Keys::vanity_with_opts(patterns, true, &opts)?;
+    println!("Public key: {}", keys.public_key().to_bech32()?);
+
     Ok(())
 }
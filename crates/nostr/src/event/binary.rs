@@ -0,0 +1,239 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Binary encoding
+//!
+//! Compact and versioned binary encoding of [`Event`].
+//!
+//! Version `1` layout:
+//!
+//! | Field        | Encoding                                                   |
+//! | ------------ | ---------------------------------------------------------- |
+//! | `version`    | 1 byte (`0x01`)                                            |
+//! | `id`         | 32 bytes                                                   |
+//! | `pubkey`     | 32 bytes (x-only public key)                               |
+//! | `sig`        | 64 bytes (schnorr signature)                               |
+//! | `created_at` | varint                                                     |
+//! | `kind`       | varint                                                     |
+//! | `tags`       | varint number of tags, for each tag: varint number of values, for each value: string |
+//! | `content`    | string                                                     |
+//! | `ots`        | 1 byte (`0x00` if missing, `0x01` if present) + string     |
+//!
+//! Varints are unsigned LEB128 and strings are UTF-8 bytes prefixed by their varint length.
+
+use std::string::FromUtf8Error;
+
+use secp256k1::schnorr::Signature;
+use secp256k1::XOnlyPublicKey;
+
+use super::{id, tag, Event, EventId, Kind, Tag};
+use crate::util::bytes::{write_varbytes, write_varint, Reader, ReaderError};
+use crate::Timestamp;
+
+/// Current version of the binary encoding
+pub const VERSION: u8 = 1;
+
+/// Binary encoding error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Unsupported version
+    #[error("unsupported version: {0}")]
+    UnsupportedVersion(u8),
+    /// Unexpected end of data
+    #[error("unexpected end of data")]
+    UnexpectedEof,
+    /// Varint overflow
+    #[error("varint overflow")]
+    VarintOverflow,
+    /// Trailing bytes
+    #[error("trailing bytes")]
+    TrailingBytes,
+    /// Invalid UTF-8 string
+    #[error(transparent)]
+    Utf8(#[from] FromUtf8Error),
+    /// EventId error
+    #[error(transparent)]
+    EventId(#[from] id::Error),
+    /// Secp256k1 error
+    #[error(transparent)]
+    Secp256k1(#[from] secp256k1::Error),
+    /// Tag error
+    #[error(transparent)]
+    Tag(#[from] tag::Error),
+}
+
+impl ReaderError for Error {
+    fn unexpected_eof() -> Self {
+        Self::UnexpectedEof
+    }
+
+    fn varint_overflow() -> Self {
+        Self::VarintOverflow
+    }
+
+    fn trailing_bytes() -> Self {
+        Self::TrailingBytes
+    }
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    write_varbytes(buf, s.as_bytes());
+}
+
+fn read_string(reader: &mut Reader<Error>) -> Result<String, Error> {
+    Ok(String::from_utf8(reader.read_varbytes()?.to_vec())?)
+}
+
+impl Event {
+    /// Encode [`Event`] in the compact binary format
+    ///
+    /// See [`crate::event::binary`] for the layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf: Vec<u8> = Vec::with_capacity(160 + self.content.len());
+        buf.push(VERSION);
+        buf.extend_from_slice(self.id.as_bytes());
+        buf.extend_from_slice(&self.pubkey.serialize());
+        buf.extend_from_slice(self.sig.as_ref());
+        write_varint(&mut buf, self.created_at.as_u64());
+        write_varint(&mut buf, self.kind.as_u64());
+        write_varint(&mut buf, self.tags.len() as u64);
+        for tag in self.tags.iter() {
+            let values: Vec<String> = tag.as_vec();
+            write_varint(&mut buf, values.len() as u64);
+            for value in values.iter() {
+                write_str(&mut buf, value);
+            }
+        }
+        write_str(&mut buf, &self.content);
+        match &self.ots {
+            Some(ots) => {
+                buf.push(1);
+                write_str(&mut buf, ots);
+            }
+            None => buf.push(0),
+        }
+        buf
+    }
+
+    /// Decode [`Event`] from the compact binary format
+    ///
+    /// The event is not verified: call [`Event::verify`] if the data is untrusted.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader: Reader<Error> = Reader::new(bytes);
+
+        let version: u8 = reader.read_u8()?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let id = EventId::from_slice(reader.read(32)?)?;
+        let pubkey = XOnlyPublicKey::from_slice(reader.read(32)?)?;
+        let sig = Signature::from_slice(reader.read(64)?)?;
+        let created_at = Timestamp::from(reader.read_varint()?);
+        let kind = Kind::from(reader.read_varint()?);
+
+        let tags_len: usize = reader.read_len()?;
+        let mut tags: Vec<Tag> = Vec::with_capacity(tags_len);
+        for _ in 0..tags_len {
+            let values_len: usize = reader.read_len()?;
+            let mut values: Vec<String> = Vec::with_capacity(values_len);
+            for _ in 0..values_len {
+                values.push(read_string(&mut reader)?);
+            }
+            tags.push(Tag::parse(values)?);
+        }
+
+        let content: String = read_string(&mut reader)?;
+        let ots: Option<String> = match reader.read_u8()? {
+            0 => None,
+            _ => Some(read_string(&mut reader)?),
+        };
+
+        reader.finish()?;
+
+        Ok(Self {
+            id,
+            pubkey,
+            created_at,
+            kind,
+            tags,
+            content,
+            sig,
+            ots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventBuilder, Keys};
+
+    #[test]
+    fn test_varint() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value);
+            let mut reader: Reader<Error> = Reader::new(&buf);
+            assert_eq!(reader.read_varint().unwrap(), value);
+            assert!(reader.finish().is_ok());
+        }
+
+        let mut reader: Reader<Error> = Reader::new(&[0xff; 11]);
+        assert!(matches!(reader.read_varint(), Err(Error::VarintOverflow)));
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let keys = Keys::generate();
+        let mut event: Event = EventBuilder::new_text_note(
+            "Hello 🦀",
+            &[
+                Tag::Hashtag(String::from("nostr")),
+                Tag::PubKey(
+                    keys.public_key(),
+                    Some(String::from("wss://relay.damus.io")),
+                ),
+                Tag::Generic(tag::TagKind::Custom(String::from("x")), Vec::new()),
+            ],
+        )
+        .to_event(&keys)
+        .unwrap();
+
+        let bytes: Vec<u8> = event.to_bytes();
+        assert!(bytes.len() < event.as_json().len());
+        let decoded = Event::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, event);
+        assert_eq!(decoded.as_json(), event.as_json());
+        decoded.verify().unwrap();
+
+        event.ots = Some(String::from("ots"));
+        assert_eq!(Event::from_bytes(&event.to_bytes()).unwrap(), event);
+    }
+
+    #[test]
+    fn test_binary_invalid() {
+        let keys = Keys::generate();
+        let event: Event = EventBuilder::new_text_note("Hello", &[])
+            .to_event(&keys)
+            .unwrap();
+        let mut bytes: Vec<u8> = event.to_bytes();
+
+        assert!(matches!(
+            Event::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::UnexpectedEof)
+        ));
+
+        bytes.push(0);
+        assert!(matches!(
+            Event::from_bytes(&bytes),
+            Err(Error::TrailingBytes)
+        ));
+
+        bytes[0] = 2;
+        assert!(matches!(
+            Event::from_bytes(&bytes),
+            Err(Error::UnsupportedVersion(2))
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Borrowed Event
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use bitcoin_hashes::hex::FromHex;
+use bitcoin_hashes::sha256::Hash as Sha256Hash;
+use secp256k1::schnorr::Signature;
+use secp256k1::XOnlyPublicKey;
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use super::{tag, Error, Event, EventId, Kind, Tag};
+use crate::Timestamp;
+
+/// String borrowed from the input buffer when it doesn't contain escape sequences
+///
+/// `#[serde(borrow)]` doesn't apply to the elements of collections.
+struct CowStr<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for CowStr<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CowStrVisitor;
+
+        impl<'de> Visitor<'de> for CowStrVisitor {
+            type Value = CowStr<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(CowStr(Cow::Borrowed(v)))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(CowStr(Cow::Owned(v.to_string())))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(CowStr(Cow::Owned(v)))
+            }
+        }
+
+        deserializer.deserialize_str(CowStrVisitor)
+    }
+}
+
+/// Tag borrowed from the input buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagRef<'a>(Vec<Cow<'a, str>>);
+
+impl<'de> Deserialize<'de> for TagRef<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let elements: Vec<CowStr<'de>> = Vec::deserialize(deserializer)?;
+        Ok(Self(elements.into_iter().map(|e| e.0).collect()))
+    }
+}
+
+impl<'a> TagRef<'a> {
+    /// Get tag kind (first element)
+    pub fn kind(&self) -> Option<&str> {
+        self.0.first().map(|k| k.as_ref())
+    }
+
+    /// Get tag content (second element)
+    pub fn content(&self) -> Option<&str> {
+        self.0.get(1).map(|c| c.as_ref())
+    }
+
+    /// Get tag elements
+    pub fn as_slice(&self) -> &[Cow<'a, str>] {
+        &self.0
+    }
+
+    /// Parse owned [`Tag`]
+    pub fn to_tag(&self) -> Result<Tag, tag::Error> {
+        Tag::parse(self.0.clone())
+    }
+}
+
+/// [`Event`] borrowed from the input buffer
+///
+/// Tags are parsed only on request and the event is not verified until converted to [`Event`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventRef<'a> {
+    /// Id (hex)
+    pub id: &'a str,
+    /// Author (hex)
+    pub pubkey: &'a str,
+    /// Timestamp (seconds)
+    pub created_at: Timestamp,
+    /// Kind
+    pub kind: Kind,
+    #[serde(borrow)]
+    tags: &'a RawValue,
+    /// Content
+    #[serde(borrow)]
+    pub content: Cow<'a, str>,
+    /// Signature (hex)
+    pub sig: &'a str,
+    /// OpenTimestamps Attestations
+    #[serde(borrow, default)]
+    pub ots: Option<Cow<'a, str>>,
+}
+
+impl<'a> EventRef<'a> {
+    /// Deserialize [`EventRef`] from JSON string
+    pub fn from_json(json: &'a str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Get tags JSON
+    pub fn raw_tags(&self) -> &'a str {
+        self.tags.get()
+    }
+
+    /// Parse tags
+    pub fn tags(&self) -> Result<Vec<TagRef<'a>>, Error> {
+        let tags: &'a str = self.tags.get();
+        Ok(serde_json::from_str(tags)?)
+    }
+
+    /// Convert to owned [`Event`] and verify it
+    pub fn to_event(&self) -> Result<Event, Error> {
+        let tags: Vec<Tag> = self
+            .tags()?
+            .iter()
+            .map(|t| t.to_tag())
+            .collect::<Result<_, _>>()?;
+        let event = Event {
+            id: EventId::from(Sha256Hash::from_hex(self.id)?),
+            pubkey: XOnlyPublicKey::from_str(self.pubkey)?,
+            created_at: self.created_at,
+            kind: self.kind,
+            tags,
+            content: self.content.to_string(),
+            sig: Signature::from_str(self.sig)?,
+            ots: self.ots.as_ref().map(|ots| ots.to_string()),
+        };
+        event.verify()?;
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventBuilder, Keys};
+
+    #[test]
+    fn test_event_ref() {
+        let keys = Keys::generate();
+        let event: Event = EventBuilder::new_text_note(
+            "Hello \"nostr\"",
+            &[
+                Tag::Hashtag(String::from("nostr")),
+                Tag::PubKey(keys.public_key(), None),
+            ],
+        )
+        .to_event(&keys)
+        .unwrap();
+        let json: String = event.as_json();
+
+        let event_ref = EventRef::from_json(&json).unwrap();
+        assert_eq!(event_ref.id, event.id.to_hex());
+        assert_eq!(event_ref.pubkey, event.pubkey.to_string());
+        assert_eq!(event_ref.kind, Kind::TextNote);
+        assert!(matches!(event_ref.content, Cow::Owned(_)));
+        assert_eq!(event_ref.content, event.content);
+
+        let tags = event_ref.tags().unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].kind(), Some("t"));
+        assert_eq!(tags[0].content(), Some("nostr"));
+        assert!(matches!(tags[0].as_slice()[1], Cow::Borrowed(_)));
+        assert_eq!(tags[1].to_tag().unwrap(), event.tags[1]);
+
+        assert_eq!(event_ref.to_event().unwrap(), event);
+
+        let forged: String = json.replace("Hello", "Bye");
+        let event_ref = EventRef::from_json(&forged).unwrap();
+        assert!(matches!(event_ref.to_event(), Err(Error::InvalidId)));
+    }
+}
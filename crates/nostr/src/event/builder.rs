@@ -3,20 +3,27 @@
 
 //! Event builder
 
-use secp256k1::{KeyPair, Message, XOnlyPublicKey};
+use secp256k1::XOnlyPublicKey;
 use serde_json::{json, Value};
 use url::Url;
 
 pub use super::kind::Kind;
 pub use super::tag::{Marker, Tag, TagKind};
-use super::{Event, EventId};
+use super::{Event, EventId, UnsignedEvent};
 use crate::key::{self, Keys};
 #[cfg(feature = "nip04")]
 use crate::nips::nip04;
 #[cfg(feature = "nip13")]
 use crate::nips::nip13;
-use crate::types::{ChannelId, Contact, Metadata, Timestamp};
-use crate::SECP256K1;
+#[cfg(feature = "nip27")]
+use crate::nips::nip27;
+#[cfg(feature = "nip44")]
+use crate::nips::nip44;
+#[cfg(feature = "nip46")]
+use crate::nips::nip46::NostrConnectMessage;
+#[cfg(feature = "nip59")]
+use crate::nips::nip59;
+use crate::types::{ChannelId, Clock, Contact, Metadata, SystemClock, Timestamp};
 
 /// [`EventBuilder`] error
 #[derive(Debug, thiserror::Error)]
@@ -30,10 +37,25 @@ pub enum Error {
     /// JSON error
     #[error(transparent)]
     Json(#[from] serde_json::Error),
+    /// Unsigned event error
+    #[error(transparent)]
+    Unsigned(#[from] super::unsigned::Error),
     /// NIP04 error
     #[cfg(feature = "nip04")]
     #[error(transparent)]
     NIP04(#[from] nip04::Error),
+    /// NIP13 error
+    #[cfg(feature = "nip13")]
+    #[error(transparent)]
+    NIP13(#[from] nip13::PowError),
+    /// NIP44 error
+    #[cfg(feature = "nip44")]
+    #[error(transparent)]
+    NIP44(#[from] nip44::Error),
+    /// NIP59 error
+    #[cfg(feature = "nip59")]
+    #[error(transparent)]
+    NIP59(#[from] nip59::Error),
 }
 
 /// [`Event`] builder
@@ -42,6 +64,7 @@ pub struct EventBuilder {
     kind: Kind,
     tags: Vec<Tag>,
     content: String,
+    created_at: Option<Timestamp>,
 }
 
 impl EventBuilder {
@@ -54,78 +77,156 @@ impl EventBuilder {
             kind,
             tags: tags.to_vec(),
             content: content.into(),
+            created_at: None,
         }
     }
 
+    /// Set a custom `created_at` [`Timestamp`], instead of the current one
+    ///
+    /// Useful to create reproducible events, for example with the time of a [`Clock`](crate::types::Clock).
+    pub fn custom_created_at(self, created_at: Timestamp) -> Self {
+        Self {
+            created_at: Some(created_at),
+            ..self
+        }
+    }
+
+    /// Set the expiration [`Timestamp`] of the event
+    ///
+    /// Replace the previous `expiration` tag, if any.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/40.md>
+    pub fn expiration(mut self, timestamp: Timestamp) -> Self {
+        self.tags.retain(|tag| !matches!(tag, Tag::Expiration(_)));
+        self.tags.push(Tag::Expiration(timestamp));
+        self
+    }
+
     /// Build [`Event`]
     pub fn to_event(self, keys: &Keys) -> Result<Event, Error> {
-        let keypair: &KeyPair = &keys.key_pair()?;
+        self.to_event_with_clock(keys, &SystemClock)
+    }
+
+    /// Build [`Event`], taking the `created_at` from the [`Clock`] if not set with [`EventBuilder::custom_created_at`]
+    pub fn to_event_with_clock(self, keys: &Keys, clock: &dyn Clock) -> Result<Event, Error> {
         let pubkey: XOnlyPublicKey = keys.public_key();
-        let created_at: Timestamp = Timestamp::now();
+        Ok(self
+            .to_unsigned_event_with_clock(pubkey, clock)
+            .sign(keys)?)
+    }
 
-        let id = EventId::new(&pubkey, created_at, &self.kind, &self.tags, &self.content);
-        let message = Message::from_slice(id.as_bytes())?;
+    /// Build [`UnsignedEvent`]
+    ///
+    /// The returned event can be signed later with [`UnsignedEvent::sign`]
+    /// or [`UnsignedEvent::add_signature`] (ex. by an external signer).
+    pub fn to_unsigned_event(self, pubkey: XOnlyPublicKey) -> UnsignedEvent {
+        self.to_unsigned_event_with_clock(pubkey, &SystemClock)
+    }
 
-        Ok(Event {
+    /// Build [`UnsignedEvent`], taking the `created_at` from the [`Clock`] if not set with [`EventBuilder::custom_created_at`]
+    pub fn to_unsigned_event_with_clock(
+        self,
+        pubkey: XOnlyPublicKey,
+        clock: &dyn Clock,
+    ) -> UnsignedEvent {
+        let created_at: Timestamp = self.created_at.unwrap_or_else(|| clock.now());
+        let id = EventId::new(&pubkey, created_at, &self.kind, &self.tags, &self.content);
+        UnsignedEvent {
             id,
             pubkey,
             created_at,
             kind: self.kind,
             tags: self.tags,
             content: self.content,
-            sig: SECP256K1.sign_schnorr(&message, keypair),
-            ots: None,
-        })
+        }
     }
 
     /// Build POW [`Event`]
+    ///
+    /// The proof of work runs on all the available CPU cores:
+    /// use [`EventBuilder::to_pow_event_with_opts`] to customize it.
     #[cfg(feature = "nip13")]
     pub fn to_pow_event(self, keys: &Keys, difficulty: u8) -> Result<Event, Error> {
+        self.to_pow_event_with_opts(keys, difficulty, &nip13::PowOptions::default())
+    }
+
+    /// Build POW [`Event`] with [`PowOptions`](nip13::PowOptions)
+    #[cfg(feature = "nip13")]
+    pub fn to_pow_event_with_opts(
+        self,
+        keys: &Keys,
+        difficulty: u8,
+        opts: &nip13::PowOptions,
+    ) -> Result<Event, Error> {
+        let pubkey: XOnlyPublicKey = keys.public_key();
+        Ok(self
+            .to_unsigned_pow_event(pubkey, difficulty, opts)?
+            .sign(keys)?)
+    }
+
+    /// Build POW [`UnsignedEvent`]
+    #[cfg(feature = "nip13")]
+    pub fn to_unsigned_pow_event(
+        self,
+        pubkey: XOnlyPublicKey,
+        difficulty: u8,
+        opts: &nip13::PowOptions,
+    ) -> Result<UnsignedEvent, Error> {
+        self.to_unsigned_pow_event_with_clock(pubkey, difficulty, opts, &SystemClock)
+    }
+
+    /// Build POW [`UnsignedEvent`], taking the `created_at` from the [`Clock`] if not set with [`EventBuilder::custom_created_at`]
+    #[cfg(feature = "nip13")]
+    pub fn to_unsigned_pow_event_with_clock(
+        self,
+        pubkey: XOnlyPublicKey,
+        difficulty: u8,
+        opts: &nip13::PowOptions,
+        clock: &dyn Clock,
+    ) -> Result<UnsignedEvent, Error> {
         #[cfg(target_arch = "wasm32")]
         use instant::Instant;
         #[cfg(not(target_arch = "wasm32"))]
         use std::time::Instant;
 
-        let mut nonce: u128 = 0;
-        let mut tags: Vec<Tag> = self.tags;
+        let created_at: Timestamp = self.created_at.unwrap_or_else(|| clock.now());
 
-        let pubkey = keys.public_key();
+        // The event id is the hash of `[0,<pubkey>,<created_at>,<kind>,[<tags>,["nonce","<nonce>","<difficulty>"]],<content>]`:
+        // serialize everything except the nonce only once.
+        let mut prefix: String = serde_json::to_string(&(0, pubkey, created_at, self.kind))?;
+        prefix.pop();
+        prefix.push_str(",[");
+        for tag in self.tags.iter() {
+            prefix.push_str(&serde_json::to_string(tag)?);
+            prefix.push(',');
+        }
+        prefix.push_str("[\"nonce\",\"");
+        let suffix: String = format!(
+            "\",\"{difficulty}\"]],{}]",
+            serde_json::to_string(&self.content)?
+        );
 
         let now = Instant::now();
+        let (nonce, hash) = nip13::mine(prefix.as_bytes(), suffix.as_bytes(), difficulty, opts)?;
 
-        loop {
-            nonce += 1;
-
-            tags.push(Tag::POW { nonce, difficulty });
-
-            let created_at: Timestamp = Timestamp::now();
-            let id = EventId::new(&pubkey, created_at, &self.kind, &tags, &self.content);
-
-            if nip13::get_leading_zero_bits(id.inner()) >= difficulty {
-                log::debug!(
-                    "{} iterations in {} ms. Avg rate {} hashes/second",
-                    nonce,
-                    now.elapsed().as_millis(),
-                    nonce * 1000 / std::cmp::max(1, now.elapsed().as_millis())
-                );
-
-                let keypair: &KeyPair = &keys.key_pair()?;
-                let message = Message::from_slice(id.as_bytes())?;
-
-                return Ok(Event {
-                    id,
-                    pubkey,
-                    created_at,
-                    kind: self.kind,
-                    tags,
-                    content: self.content,
-                    sig: SECP256K1.sign_schnorr(&message, keypair),
-                    ots: None,
-                });
-            }
+        log::debug!(
+            "POW with difficulty {} found in {} ms (nonce {})",
+            difficulty,
+            now.elapsed().as_millis(),
+            nonce
+        );
 
-            tags.pop();
-        }
+        let mut tags: Vec<Tag> = self.tags;
+        tags.push(Tag::POW { nonce, difficulty });
+
+        Ok(UnsignedEvent {
+            id: EventId::from(hash),
+            pubkey,
+            created_at,
+            kind: self.kind,
+            tags,
+            content: self.content,
+        })
     }
 }
 
@@ -175,6 +276,67 @@ impl EventBuilder {
         Self::new(Kind::TextNote, content, tags)
     }
 
+    /// Text note with the `p`, `e`, `a` and `t` tags of the `nostr:` URIs and hashtags found in the content
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/27.md>
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use nostr::EventBuilder;
+    ///
+    /// let builder = EventBuilder::new_text_note_with_references(
+    ///     "Hello nostr:npub180cvv07tjdrrgpa0j7j7tmnyl2yr6yr7l8j4s3evf6u64th6gkwsyjh6w6 #nostr",
+    ///     &[],
+    /// );
+    /// ```
+    #[cfg(feature = "nip27")]
+    pub fn new_text_note_with_references<S>(content: S, tags: &[Tag]) -> Self
+    where
+        S: Into<String>,
+    {
+        let content: String = content.into();
+        let mut tags: Vec<Tag> = tags.to_vec();
+        for tag in nip27::extract_tags(&content).into_iter() {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Self::new_text_note(content, &tags)
+    }
+
+    /// Reply to an event
+    ///
+    /// The `e` tags are marked as `root` and `reply` and the `p` tags include the author and
+    /// all the public keys tagged in the replied event.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/10.md>
+    #[cfg(feature = "nip10")]
+    pub fn reply_to<S>(event: &Event, content: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let thread = event.thread();
+
+        let mut tags: Vec<Tag> = Vec::new();
+        match thread.root {
+            Some(root) => {
+                tags.push(Tag::Event(root.id, root.relay_url, Some(Marker::Root)));
+                tags.push(Tag::Event(event.id, None, Some(Marker::Reply)));
+            }
+            None => tags.push(Tag::Event(event.id, None, Some(Marker::Root))),
+        }
+
+        let mut pubkeys: Vec<XOnlyPublicKey> = vec![event.pubkey];
+        for pubkey in thread.pubkeys.into_iter() {
+            if !pubkeys.contains(&pubkey) {
+                pubkeys.push(pubkey);
+            }
+        }
+        tags.extend(pubkeys.into_iter().map(|pk| Tag::PubKey(pk, None)));
+
+        Self::new_text_note(content, &tags)
+    }
+
     /// Long-form text note (generally referred to as "articles" or "blog posts").
     ///
     /// <https://github.com/nostr-protocol/nips/blob/master/23.md>
@@ -234,6 +396,131 @@ impl EventBuilder {
         ))
     }
 
+    /// Create encrypted direct msg event, using NIP44 encryption
+    ///
+    /// **Warning:** the NIP44 payload is sent in a [`Kind::EncryptedDirectMessage`] (kind 4) event,
+    /// so the clients supporting only NIP04 can't decrypt it. Use it only with receivers known to support NIP44.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/44.md>
+    #[cfg(feature = "nip44")]
+    pub fn new_encrypted_direct_msg_nip44<S>(
+        sender_keys: &Keys,
+        receiver_pubkey: XOnlyPublicKey,
+        content: S,
+    ) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let msg = nip44::encrypt(
+            &sender_keys.secret_key()?,
+            &receiver_pubkey,
+            content.into(),
+            nip44::Version::default(),
+        )?;
+
+        Ok(Self::new(
+            Kind::EncryptedDirectMessage,
+            msg,
+            &[Tag::PubKey(receiver_pubkey, None)],
+        ))
+    }
+
+    /// Private direct message rumor
+    ///
+    /// Must be gift wrapped (ex. with [`EventBuilder::gift_wrap`]) for the receiver and for the sender,
+    /// never signed and published as is.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/17.md>
+    #[cfg(feature = "nip59")]
+    pub fn private_msg_rumor<S>(
+        receiver_pubkey: XOnlyPublicKey,
+        message: S,
+        reply_to: Option<EventId>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut tags: Vec<Tag> = vec![Tag::PubKey(receiver_pubkey, None)];
+        if let Some(event_id) = reply_to {
+            tags.push(Tag::Event(event_id, None, None));
+        }
+        Self::new(Kind::PrivateDirectMessage, message, &tags)
+    }
+
+    /// Seal: `rumor` encrypted for `receiver_pubkey`, to sign with the `sender_keys`
+    ///
+    /// The `created_at` is randomized in the past of `timestamp` (ex. the time of a [`Clock`](crate::types::Clock)).
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/59.md>
+    #[cfg(feature = "nip59")]
+    pub fn seal(
+        sender_keys: &Keys,
+        receiver_pubkey: &XOnlyPublicKey,
+        rumor: UnsignedEvent,
+        timestamp: Timestamp,
+    ) -> Result<Self, Error> {
+        let content: String = nip44::encrypt(
+            &sender_keys.secret_key()?,
+            receiver_pubkey,
+            rumor.as_json(),
+            nip44::Version::default(),
+        )?;
+        Ok(Self::new(Kind::Seal, content, &[])
+            .custom_created_at(nip59::randomize_timestamp(timestamp)))
+    }
+
+    /// Gift wrap `seal` for `receiver_pubkey`, signing with random ephemeral keys
+    ///
+    /// The `created_at` is randomized in the past of `timestamp` (ex. the time of a [`Clock`](crate::types::Clock)).
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/59.md>
+    #[cfg(feature = "nip59")]
+    pub fn gift_wrap_from_seal(
+        receiver_pubkey: &XOnlyPublicKey,
+        seal: &Event,
+        timestamp: Timestamp,
+    ) -> Result<Event, Error> {
+        if seal.kind != Kind::Seal {
+            return Err(nip59::Error::NotSeal.into());
+        }
+
+        let ephemeral_keys = Keys::generate();
+        let content: String = nip44::encrypt(
+            &ephemeral_keys.secret_key()?,
+            receiver_pubkey,
+            seal.as_json(),
+            nip44::Version::default(),
+        )?;
+        Self::new(
+            Kind::GiftWrap,
+            content,
+            &[Tag::PubKey(*receiver_pubkey, None)],
+        )
+        .custom_created_at(nip59::randomize_timestamp(timestamp))
+        .to_event(&ephemeral_keys)
+    }
+
+    /// Seal `rumor` with the `sender_keys` and gift wrap it for `receiver_pubkey`
+    ///
+    /// The `created_at` of the seal and of the gift wrap are randomized in the past of `timestamp`.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/59.md>
+    #[cfg(feature = "nip59")]
+    pub fn gift_wrap(
+        sender_keys: &Keys,
+        receiver_pubkey: &XOnlyPublicKey,
+        rumor: UnsignedEvent,
+        timestamp: Timestamp,
+    ) -> Result<Event, Error> {
+        if rumor.pubkey != sender_keys.public_key() {
+            return Err(nip59::Error::SenderMismatch.into());
+        }
+
+        let seal: Event =
+            Self::seal(sender_keys, receiver_pubkey, rumor, timestamp)?.to_event(sender_keys)?;
+        Self::gift_wrap_from_seal(receiver_pubkey, &seal, timestamp)
+    }
+
     /// Repost event
     pub fn repost(event_id: EventId, public_key: XOnlyPublicKey) -> Self {
         Self::new(
@@ -372,6 +659,25 @@ impl EventBuilder {
         )
     }
 
+    /// Create Nostr Connect event
+    ///
+    /// The [`NostrConnectMessage`] is encrypted for `receiver_pubkey` with NIP04.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/46.md>
+    #[cfg(feature = "nip46")]
+    pub fn nostr_connect(
+        sender_keys: &Keys,
+        receiver_pubkey: XOnlyPublicKey,
+        msg: NostrConnectMessage,
+    ) -> Result<Self, Error> {
+        let content = nip04::encrypt(&sender_keys.secret_key()?, &receiver_pubkey, msg.as_json())?;
+        Ok(Self::new(
+            Kind::NostrConnect,
+            content,
+            &[Tag::PubKey(receiver_pubkey, None)],
+        ))
+    }
+
     /// Create report event
     ///
     /// <https://github.com/nostr-protocol/nips/blob/master/56.md>
@@ -389,7 +695,83 @@ mod tests {
 
     use secp256k1::SecretKey;
 
-    use crate::{Event, EventBuilder, Keys, Result};
+    #[cfg(feature = "nip13")]
+    use crate::nips::nip13;
+    #[cfg(feature = "nip44")]
+    use crate::nips::nip44;
+    use crate::types::{Clock, FixedClock};
+    use crate::{Event, EventBuilder, Keys, Result, Tag, Timestamp};
+
+    #[test]
+    fn test_custom_created_at() -> Result<()> {
+        let keys = Keys::new(SecretKey::from_str(
+            "6b911fd37cdf5c81d4c0adb1ab7fa822ed253ab0ad9aa18d77257c88b29b718e",
+        )?);
+        let clock = FixedClock::new(Timestamp::from(1_680_000_000));
+
+        let build = || {
+            EventBuilder::new_text_note("hello", &[])
+                .custom_created_at(clock.now())
+                .to_unsigned_event(keys.public_key())
+                .sign_deterministic(&keys)
+        };
+        let event = build()?;
+        assert_eq!(event.created_at, Timestamp::from(1_680_000_000));
+        assert_eq!(event.as_json(), build()?.as_json());
+        event.verify()?;
+
+        clock.set(Timestamp::from(1_690_000_000));
+        assert_eq!(build()?.created_at, Timestamp::from(1_690_000_000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_event_with_clock() -> Result<()> {
+        let keys = Keys::generate();
+        let clock = FixedClock::new(Timestamp::from(1_680_000_000));
+
+        let event = EventBuilder::new_text_note("hello", &[]).to_event_with_clock(&keys, &clock)?;
+        assert_eq!(event.created_at, Timestamp::from(1_680_000_000));
+
+        // `custom_created_at` takes precedence over the clock
+        let event = EventBuilder::new_text_note("hello", &[])
+            .custom_created_at(Timestamp::from(1_690_000_000))
+            .to_event_with_clock(&keys, &clock)?;
+        assert_eq!(event.created_at, Timestamp::from(1_690_000_000));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "nip13")]
+    fn test_pow_event_custom_created_at() -> Result<()> {
+        let keys = Keys::generate();
+        let created_at = Timestamp::from(1_680_000_000);
+        let event = EventBuilder::new(crate::Kind::TextNote, "pow", &[])
+            .custom_created_at(created_at)
+            .to_pow_event(&keys, 8)?;
+        assert_eq!(event.created_at, created_at);
+        event.verify()?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "nip13")]
+    fn test_pow_event_with_opts() -> Result<()> {
+        let keys = Keys::generate();
+        let opts = nip13::PowOptions::new().threads(2);
+        let event =
+            EventBuilder::new_text_note("\"escaped\" 🦀\n", &[Tag::Hashtag(String::from("nostr"))])
+                .to_pow_event_with_opts(&keys, 10, &opts)?;
+        event.verify()?;
+        assert!(nip13::get_leading_zero_bits(event.id.inner()) >= 10);
+        assert!(matches!(
+            event.tags.last(),
+            Some(Tag::POW { difficulty: 10, .. })
+        ));
+        Ok(())
+    }
 
     #[test]
     fn round_trip() -> Result<()> {
@@ -427,4 +809,30 @@ mod tests {
 
         Ok(event.verify()?)
     }
+
+    #[test]
+    #[cfg(feature = "nip44")]
+    fn test_encrypted_direct_msg_nip44() -> Result<()> {
+        let sender_keys = Keys::generate();
+        let receiver_keys = Keys::generate();
+
+        let content = "Venus, the Bringer of Peace";
+        let event = EventBuilder::new_encrypted_direct_msg_nip44(
+            &sender_keys,
+            receiver_keys.public_key(),
+            content,
+        )?
+        .to_event(&sender_keys)?;
+        event.verify()?;
+
+        assert_eq!(
+            nip44::decrypt(
+                &receiver_keys.secret_key()?,
+                &sender_keys.public_key(),
+                &event.content
+            )?,
+            content
+        );
+        Ok(())
+    }
 }
@@ -10,7 +10,6 @@ use bitcoin_hashes::sha256::Hash as Sha256Hash;
 use bitcoin_hashes::Hash;
 use secp256k1::XOnlyPublicKey;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
 
 use super::{Kind, Tag};
 #[cfg(feature = "nip19")]
@@ -45,9 +44,12 @@ impl EventId {
         tags: &[Tag],
         content: &str,
     ) -> Self {
-        let json: Value = json!([0, pubkey, created_at, kind, tags, content]);
-        let event_str: String = json.to_string();
-        Self(Sha256Hash::hash(event_str.as_bytes()))
+        // Serialize the commitment `[0, <pubkey>, <created_at>, <kind>, <tags>, <content>]`
+        // directly into the hash engine, without intermediate buffers
+        let mut engine = Sha256Hash::engine();
+        // Writing into the hash engine never fails
+        let _ = serde_json::to_writer(&mut engine, &(0, pubkey, created_at, kind, tags, content));
+        Self(Sha256Hash::from_engine(engine))
     }
 
     /// [`EventId`] hex string
@@ -29,6 +29,10 @@ pub enum Kind {
     Repost,
     /// Reaction (NIP25)
     Reaction,
+    /// Seal (NIP59)
+    Seal,
+    /// Private Direct Message (NIP17)
+    PrivateDirectMessage,
     /// Channel Creation (NIP28)
     ChannelCreation,
     /// Channel Metadata (NIP28)
@@ -49,6 +53,10 @@ pub enum Kind {
     PublicChatReserved48,
     /// Public Chat Reserved (NIP28)
     PublicChatReserved49,
+    /// OpenTimestamps Attestations (NIP03)
+    OpenTimestamps,
+    /// Gift Wrap (NIP59)
+    GiftWrap,
     /// Reporting (NIP56)
     Reporting,
     /// Zap Request (NIP57)
@@ -57,6 +65,8 @@ pub enum Kind {
     Zap,
     /// Client Authentication (NIP42)
     Authentication,
+    /// Nostr Connect (NIP46)
+    NostrConnect,
     /// Long-form Text Note (NIP23)
     LongFormTextNote,
     /// Relay List Metadata (NIP65)
@@ -81,6 +91,28 @@ impl Kind {
     pub fn as_u64(&self) -> u64 {
         (*self).into()
     }
+
+    /// Check if [`Kind`] is replaceable
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/01.md>
+    pub fn is_replaceable(&self) -> bool {
+        let kind: u64 = self.as_u64();
+        kind == 0 || kind == 3 || (10_000..20_000).contains(&kind)
+    }
+
+    /// Check if [`Kind`] is ephemeral
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/01.md>
+    pub fn is_ephemeral(&self) -> bool {
+        (20_000..30_000).contains(&self.as_u64())
+    }
+
+    /// Check if [`Kind`] is parameterized replaceable
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/01.md>
+    pub fn is_parameterized_replaceable(&self) -> bool {
+        (30_000..40_000).contains(&self.as_u64())
+    }
 }
 
 impl From<u64> for Kind {
@@ -94,6 +126,8 @@ impl From<u64> for Kind {
             5 => Self::EventDeletion,
             6 => Self::Repost,
             7 => Self::Reaction,
+            13 => Self::Seal,
+            14 => Self::PrivateDirectMessage,
             40 => Self::ChannelCreation,
             41 => Self::ChannelMetadata,
             42 => Self::ChannelMessage,
@@ -104,11 +138,14 @@ impl From<u64> for Kind {
             47 => Self::PublicChatReserved47,
             48 => Self::PublicChatReserved48,
             49 => Self::PublicChatReserved49,
+            1040 => Self::OpenTimestamps,
+            1059 => Self::GiftWrap,
             1984 => Self::Reporting,
             9734 => Self::ZapRequest,
             9735 => Self::Zap,
             10002 => Self::RelayList,
             22242 => Self::Authentication,
+            24133 => Self::NostrConnect,
             30023 => Self::LongFormTextNote,
             x if (10_000..20_000).contains(&x) => Self::Replaceable(x as u16),
             x if (20_000..30_000).contains(&x) => Self::Ephemeral(x as u16),
@@ -129,6 +166,8 @@ impl From<Kind> for u64 {
             Kind::EventDeletion => 5,
             Kind::Repost => 6,
             Kind::Reaction => 7,
+            Kind::Seal => 13,
+            Kind::PrivateDirectMessage => 14,
             Kind::ChannelCreation => 40,
             Kind::ChannelMetadata => 41,
             Kind::ChannelMessage => 42,
@@ -139,11 +178,14 @@ impl From<Kind> for u64 {
             Kind::PublicChatReserved47 => 47,
             Kind::PublicChatReserved48 => 48,
             Kind::PublicChatReserved49 => 49,
+            Kind::OpenTimestamps => 1040,
+            Kind::GiftWrap => 1059,
             Kind::Reporting => 1984,
             Kind::ZapRequest => 9734,
             Kind::Zap => 9735,
             Kind::RelayList => 10002,
             Kind::Authentication => 22242,
+            Kind::NostrConnect => 24133,
             Kind::LongFormTextNote => 30023,
             Kind::Replaceable(u) => u as u64,
             Kind::Ephemeral(u) => u as u64,
@@ -213,6 +255,25 @@ mod tests {
         assert_eq!(Kind::TextNote, Kind::Custom(1));
     }
 
+    #[test]
+    fn test_kind_classification() {
+        assert!(Kind::Metadata.is_replaceable());
+        assert!(Kind::ContactList.is_replaceable());
+        assert!(Kind::RelayList.is_replaceable());
+        assert!(Kind::Custom(19999).is_replaceable());
+        assert!(!Kind::TextNote.is_replaceable());
+        assert!(!Kind::Custom(20000).is_replaceable());
+
+        assert!(Kind::Authentication.is_ephemeral());
+        assert!(Kind::Ephemeral(20100).is_ephemeral());
+        assert!(!Kind::Custom(30000).is_ephemeral());
+
+        assert!(Kind::LongFormTextNote.is_parameterized_replaceable());
+        assert!(Kind::Custom(39999).is_parameterized_replaceable());
+        assert!(!Kind::Custom(40000).is_parameterized_replaceable());
+        assert!(!Kind::Metadata.is_parameterized_replaceable());
+    }
+
     #[test]
     fn test_not_equal_kind() {
         assert_ne!(Kind::Custom(20100), Kind::Custom(2000));
@@ -8,22 +8,37 @@ use std::str::FromStr;
 
 use secp256k1::schnorr::Signature;
 use secp256k1::{Message, XOnlyPublicKey};
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
 
+pub mod binary;
+pub mod borrowed;
 pub mod builder;
 pub mod id;
 pub mod kind;
 pub mod tag;
+pub mod unsigned;
 
+pub use self::borrowed::{EventRef, TagRef};
 pub use self::builder::EventBuilder;
 pub use self::id::EventId;
 pub use self::kind::Kind;
 pub use self::tag::{Marker, Tag, TagKind};
-use crate::{Timestamp, SECP256K1};
+pub use self::unsigned::UnsignedEvent;
+#[cfg(feature = "nip13")]
+use crate::nips::nip13;
+use crate::{Coordinate, Timestamp, SECP256K1};
+
+/// Min number of events verified by each thread in [`Event::verify_batch`]
+#[cfg(feature = "parallel")]
+const MIN_BATCH_SIZE_PER_THREAD: usize = 64;
 
 /// [`Event`] error
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    /// Invalid event id
+    #[error("invalid event id")]
+    InvalidId,
     /// Invalid signature
     #[error("invalid signature")]
     InvalidSignature,
@@ -36,13 +51,16 @@ pub enum Error {
     /// Hex decoding error
     #[error(transparent)]
     Hex(#[from] bitcoin_hashes::hex::Error),
+    /// Tag error
+    #[error(transparent)]
+    Tag(#[from] tag::Error),
     /// OpenTimestamps error
     #[error(transparent)]
     OpenTimestamps(#[from] nostr_ots::Error),
 }
 
 /// [`Event`] struct
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize)]
 pub struct Event {
     /// Id
     pub id: EventId,
@@ -59,13 +77,41 @@ pub struct Event {
     /// Signature
     pub sig: Signature,
     /// OpenTimestamps Attestations
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub ots: Option<String>,
 }
 
+impl Serialize for Event {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Keys in lexicographical order
+        let len: usize = if self.ots.is_some() { 8 } else { 7 };
+        let mut state = serializer.serialize_struct("Event", len)?;
+        state.serialize_field("content", &self.content)?;
+        state.serialize_field("created_at", &self.created_at)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("kind", &self.kind)?;
+        match &self.ots {
+            Some(ots) => state.serialize_field("ots", ots)?,
+            None => state.skip_field("ots")?,
+        }
+        state.serialize_field("pubkey", &self.pubkey)?;
+        state.serialize_field("sig", &self.sig)?;
+        state.serialize_field("tags", &self.tags)?;
+        state.end()
+    }
+}
+
 impl Event {
-    /// Verify event
+    /// Verify both [`EventId`] and [`Signature`]
     pub fn verify(&self) -> Result<(), Error> {
+        self.verify_id()?;
+        self.verify_signature()
+    }
+
+    /// Verify if the [`EventId`] has been computed correctly
+    pub fn verify_id(&self) -> Result<(), Error> {
         let id = EventId::new(
             &self.pubkey,
             self.created_at,
@@ -73,12 +119,58 @@ impl Event {
             &self.tags,
             &self.content,
         );
-        let message = Message::from_slice(id.as_bytes())?;
+        if id == self.id {
+            Ok(())
+        } else {
+            Err(Error::InvalidId)
+        }
+    }
+
+    /// Verify event [`Signature`]
+    pub fn verify_signature(&self) -> Result<(), Error> {
+        let message = Message::from_slice(self.id.as_bytes())?;
         SECP256K1
             .verify_schnorr(&self.sig, &message, &self.pubkey)
             .map_err(|_| Error::InvalidSignature)
     }
 
+    /// Verify a batch of events
+    ///
+    /// Return the verification result of each [`Event`], in the same order of `events`.
+    ///
+    /// With the `parallel` feature enabled, the events are verified on all the available CPU cores.
+    pub fn verify_batch(events: &[Event]) -> Vec<Result<(), Error>> {
+        #[cfg(feature = "parallel")]
+        {
+            let threads: usize = std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1);
+            if threads > 1 && events.len() >= MIN_BATCH_SIZE_PER_THREAD * 2 {
+                let chunk_size: usize = std::cmp::max(
+                    (events.len() + threads - 1) / threads,
+                    MIN_BATCH_SIZE_PER_THREAD,
+                );
+                return std::thread::scope(|s| {
+                    let handles: Vec<_> = events
+                        .chunks(chunk_size)
+                        .map(|chunk| {
+                            s.spawn(move || chunk.iter().map(Event::verify).collect::<Vec<_>>())
+                        })
+                        .collect();
+                    handles
+                        .into_iter()
+                        .flat_map(|handle| match handle.join() {
+                            Ok(results) => results,
+                            Err(e) => std::panic::resume_unwind(e),
+                        })
+                        .collect()
+                });
+            }
+        }
+
+        events.iter().map(Event::verify).collect()
+    }
+
     /// New event from json string
     pub fn from_json<S>(json: S) -> Result<Self, Error>
     where
@@ -91,7 +183,82 @@ impl Event {
 
     /// Get event as json string
     pub fn as_json(&self) -> String {
-        serde_json::json!(self).to_string()
+        // Serializing an event never fails
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Get the [`Coordinate`] of a replaceable or parameterized replaceable event
+    ///
+    /// Return `None` for the other kinds.
+    pub fn coordinate(&self) -> Option<Coordinate> {
+        if self.kind.is_replaceable() {
+            Some(Coordinate::new(self.kind, self.pubkey))
+        } else if self.kind.is_parameterized_replaceable() {
+            let identifier: &str = self
+                .tags
+                .iter()
+                .find_map(|tag| match tag {
+                    Tag::Identifier(identifier) => Some(identifier.as_str()),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            Some(Coordinate::new(self.kind, self.pubkey).identifier(identifier))
+        } else {
+            None
+        }
+    }
+
+    /// Get the expiration [`Timestamp`] of the event, if any
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/40.md>
+    pub fn expiration(&self) -> Option<Timestamp> {
+        self.tags.iter().find_map(|tag| match tag {
+            Tag::Expiration(timestamp) => Some(*timestamp),
+            _ => None,
+        })
+    }
+
+    /// Check if the event is expired
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/40.md>
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Timestamp::now())
+    }
+
+    /// Check if the event is expired at `now` (ex. the time of a [`Clock`](crate::types::Clock))
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/40.md>
+    pub fn is_expired_at(&self, now: Timestamp) -> bool {
+        match self.expiration() {
+            Some(expiration) => expiration <= now,
+            None => false,
+        }
+    }
+
+    /// Get the proof of work difficulty
+    ///
+    /// Return the target committed in the `nonce` tag if the id meets it, otherwise `0`:
+    /// an event that reached a higher difficulty by chance is not worth more than its target.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/13.md>
+    #[cfg(feature = "nip13")]
+    pub fn pow_difficulty(&self) -> u8 {
+        self.tags
+            .iter()
+            .find_map(|tag| match tag {
+                Tag::POW { difficulty, .. } => Some(*difficulty),
+                _ => None,
+            })
+            .filter(|target| nip13::get_leading_zero_bits(self.id.inner()) >= *target)
+            .unwrap_or(0)
+    }
+
+    /// Check if the event has a proof of work difficulty of at least `min`
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/13.md>
+    #[cfg(feature = "nip13")]
+    pub fn check_pow(&self, min: u8) -> bool {
+        min == 0 || self.pow_difficulty() >= min
     }
 
     /// Timestamp this event with OpenTimestamps, according to NIP-03
@@ -138,6 +305,11 @@ impl Event {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use bitcoin_hashes::sha256::Hash as Sha256Hash;
+    use bitcoin_hashes::Hash;
+
     use super::*;
 
     use crate::Keys;
@@ -150,6 +322,38 @@ mod tests {
         assert_eq!(ev_ser.as_json(), sample_event);
     }
 
+    #[test]
+    fn test_canonical_serialization() {
+        let keys = Keys::generate();
+        let mut event: Event = EventBuilder::new(
+            Kind::TextNote,
+            "Tab\t, quote \", emoji 🦀 and \u{7f}",
+            &[Tag::Hashtag(String::from("nostr"))],
+        )
+        .to_event(&keys)
+        .unwrap();
+
+        // Same commitment of the `serde_json::Value` based implementation
+        let commitment: String = serde_json::json!([
+            0,
+            event.pubkey,
+            event.created_at,
+            event.kind,
+            event.tags,
+            event.content
+        ])
+        .to_string();
+        assert_eq!(
+            event.id,
+            EventId::from(Sha256Hash::hash(commitment.as_bytes()))
+        );
+
+        assert_eq!(event.as_json(), serde_json::json!(event).to_string());
+        event.ots = Some(String::from("ots"));
+        assert_eq!(event.as_json(), serde_json::json!(event).to_string());
+        assert_eq!(Event::from_json(event.as_json()).unwrap(), event);
+    }
+
     #[test]
     fn test_custom_kind() {
         let keys = Keys::generate();
@@ -164,4 +368,143 @@ mod tests {
         assert_eq!(Kind::Custom(123), e.kind);
         assert_eq!(Kind::Custom(123), deserialized.kind);
     }
+
+    #[test]
+    fn test_verify_batch() {
+        let keys = Keys::generate();
+        let mut events: Vec<Event> = (0..300)
+            .map(|i| {
+                EventBuilder::new_text_note(format!("note {i}"), &[])
+                    .to_event(&keys)
+                    .unwrap()
+            })
+            .collect();
+        events[42].content = String::from("tampered");
+        events[250].sig = events[0].sig;
+
+        let results = Event::verify_batch(&events);
+        assert_eq!(results.len(), events.len());
+        for (i, res) in results.into_iter().enumerate() {
+            match i {
+                42 => assert!(matches!(res, Err(Error::InvalidId))),
+                250 => assert!(matches!(res, Err(Error::InvalidSignature))),
+                _ => assert!(res.is_ok()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_expiration() {
+        let keys = Keys::generate();
+
+        let event: Event = EventBuilder::new_text_note("my content", &[])
+            .to_event(&keys)
+            .unwrap();
+        assert_eq!(event.expiration(), None);
+        assert!(!event.is_expired());
+
+        let expiration = Timestamp::now() - Duration::from_secs(60);
+        let event: Event = EventBuilder::new_text_note("my content", &[])
+            .expiration(expiration)
+            .to_event(&keys)
+            .unwrap();
+        assert_eq!(event.expiration(), Some(expiration));
+        assert!(event.is_expired());
+
+        let event: Event = EventBuilder::new_text_note("my content", &[])
+            .expiration(Timestamp::now() + Duration::from_secs(60))
+            .to_event(&keys)
+            .unwrap();
+        assert!(!event.is_expired());
+    }
+
+    #[test]
+    fn test_coordinate() {
+        let keys = Keys::generate();
+
+        let event: Event = EventBuilder::new_text_note("my content", &[])
+            .to_event(&keys)
+            .unwrap();
+        assert_eq!(event.coordinate(), None);
+
+        let event: Event = EventBuilder::new(Kind::RelayList, "", &[])
+            .to_event(&keys)
+            .unwrap();
+        assert_eq!(
+            event.coordinate(),
+            Some(Coordinate::new(Kind::RelayList, keys.public_key()))
+        );
+
+        let event: Event = EventBuilder::long_form_text_note(
+            "my content",
+            &[Tag::Identifier(String::from("ipsum"))],
+        )
+        .to_event(&keys)
+        .unwrap();
+        assert_eq!(
+            event.coordinate(),
+            Some(Coordinate::new(Kind::LongFormTextNote, keys.public_key()).identifier("ipsum"))
+        );
+    }
+
+    #[test]
+    fn test_event_with_forged_id() {
+        // Valid event, but with the id of another event
+        let sample_event = r#"{"content":"uRuvYr585B80L6rSJiHocw==?iv=oh6LVqdsYYol3JfFnXTbPA==","created_at":1640839235,"id":"378f145897eea948952674269945e88612420db35791784abf0616b4fed56ef7","kind":4,"pubkey":"f86c44a2de95d9149b51c6a29afeabba264c18e2fa7c49de93424a0c56947785","sig":"a5d9290ef9659083c490b303eb7ee41356d8778ff19f2f91776c8dc4443388a64ffcf336e61af4c25c05ac3ae952d1ced889ed655b67790891222aaa15b99fdd","tags":[["p","13adc511de7e1cfcf1c6b7f6365fb5a03442d7bcacf565ea57fa7770912c023d"]]}"#;
+        assert!(matches!(
+            Event::from_json(sample_event).unwrap_err(),
+            Error::InvalidId
+        ));
+    }
+
+    #[test]
+    fn test_event_with_invalid_signature() {
+        let keys = Keys::generate();
+        let mut event: Event = EventBuilder::new_text_note("my content", &[])
+            .to_event(&keys)
+            .unwrap();
+        let other: Event = EventBuilder::new_text_note("other content", &[])
+            .to_event(&keys)
+            .unwrap();
+        event.sig = other.sig;
+
+        assert!(event.verify_id().is_ok());
+        assert!(matches!(
+            event.verify_signature().unwrap_err(),
+            Error::InvalidSignature
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "nip13")]
+    fn test_pow_difficulty() {
+        let keys = Keys::generate();
+
+        let event = EventBuilder::new_text_note("pow", &[])
+            .to_pow_event(&keys, 8)
+            .unwrap();
+        assert_eq!(event.pow_difficulty(), 8);
+        assert!(event.check_pow(8));
+        assert!(!event.check_pow(9));
+
+        let event = EventBuilder::new_text_note("no pow", &[])
+            .to_event(&keys)
+            .unwrap();
+        assert_eq!(event.pow_difficulty(), 0);
+        assert!(event.check_pow(0));
+        assert!(!event.check_pow(1));
+
+        // Committed target not met
+        let event = EventBuilder::new_text_note(
+            "fake pow",
+            &[Tag::POW {
+                nonce: 1,
+                difficulty: 255,
+            }],
+        )
+        .to_event(&keys)
+        .unwrap();
+        assert_eq!(event.pow_difficulty(), 0);
+        assert!(!event.check_pow(1));
+    }
 }
@@ -0,0 +1,179 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Unsigned Event
+
+use secp256k1::schnorr::Signature;
+use secp256k1::{KeyPair, Message, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{Event, EventId, Keys, Kind, Tag, Timestamp, SECP256K1};
+
+/// [`UnsignedEvent`] error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Key error
+    #[error(transparent)]
+    Key(#[from] crate::key::Error),
+    /// Error serializing or deserializing JSON data
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Secp256k1 error
+    #[error(transparent)]
+    Secp256k1(#[from] secp256k1::Error),
+    /// Event error
+    #[error(transparent)]
+    Event(#[from] super::Error),
+    /// The keys don't match the public key of the [`UnsignedEvent`]
+    #[error("public key mismatch")]
+    PublicKeyMismatch,
+}
+
+/// [`UnsignedEvent`] struct
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsignedEvent {
+    /// Id
+    pub id: EventId,
+    /// Author
+    pub pubkey: XOnlyPublicKey,
+    /// Timestamp (seconds)
+    pub created_at: Timestamp,
+    /// Kind
+    pub kind: Kind,
+    /// Vector of [`Tag`]
+    pub tags: Vec<Tag>,
+    /// Content
+    pub content: String,
+}
+
+impl UnsignedEvent {
+    /// New unsigned event from json string
+    pub fn from_json<S>(json: S) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        Ok(serde_json::from_str(&json.into())?)
+    }
+
+    /// Get unsigned event as json string
+    pub fn as_json(&self) -> String {
+        serde_json::json!(self).to_string()
+    }
+
+    /// Sign an [`UnsignedEvent`]
+    ///
+    /// Return [`Error::PublicKeyMismatch`] if the `keys` are not the ones of the event author.
+    pub fn sign(self, keys: &Keys) -> Result<Event, Error> {
+        self.check_public_key(keys)?;
+        let keypair: &KeyPair = &keys.key_pair()?;
+        let message = Message::from_slice(self.id.as_bytes())?;
+        let sig: Signature = SECP256K1.sign_schnorr(&message, keypair);
+        Ok(self.into_event(sig))
+    }
+
+    /// Sign an [`UnsignedEvent`] without auxiliary randomness
+    ///
+    /// The same event signed with the same keys always has the same signature:
+    /// useful for golden tests and reproducible builds.
+    /// Prefer [`UnsignedEvent::sign`] otherwise, since the auxiliary randomness
+    /// protects against side-channel attacks.
+    pub fn sign_deterministic(self, keys: &Keys) -> Result<Event, Error> {
+        self.check_public_key(keys)?;
+        let keypair: &KeyPair = &keys.key_pair()?;
+        let message = Message::from_slice(self.id.as_bytes())?;
+        let sig: Signature = SECP256K1.sign_schnorr_no_aux_rand(&message, keypair);
+        Ok(self.into_event(sig))
+    }
+
+    fn check_public_key(&self, keys: &Keys) -> Result<(), Error> {
+        if keys.public_key() == self.pubkey {
+            Ok(())
+        } else {
+            Err(Error::PublicKeyMismatch)
+        }
+    }
+
+    fn into_event(self, sig: Signature) -> Event {
+        Event {
+            id: self.id,
+            pubkey: self.pubkey,
+            created_at: self.created_at,
+            kind: self.kind,
+            tags: self.tags,
+            content: self.content,
+            sig,
+            ots: None,
+        }
+    }
+
+    /// Add signature to [`UnsignedEvent`]
+    ///
+    /// The signature is verified before building the [`Event`].
+    pub fn add_signature(self, sig: Signature) -> Result<Event, Error> {
+        let event: Event = self.into_event(sig);
+        event.verify()?;
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use secp256k1::SecretKey;
+
+    use super::*;
+    use crate::{EventBuilder, Result};
+
+    #[test]
+    fn test_sign_unsigned_event() -> Result<()> {
+        let keys = Keys::new(SecretKey::from_str(
+            "6b911fd37cdf5c81d4c0adb1ab7fa822ed253ab0ad9aa18d77257c88b29b718e",
+        )?);
+
+        let unsigned =
+            EventBuilder::new_text_note("hello", &[]).to_unsigned_event(keys.public_key());
+        let unsigned = UnsignedEvent::from_json(unsigned.as_json())?;
+        let event = unsigned.clone().sign(&keys)?;
+        event.verify()?;
+
+        assert_eq!(event.id, unsigned.id);
+        assert_eq!(event.content, "hello");
+
+        // Keys of another author
+        let other_keys = Keys::generate();
+        assert!(matches!(
+            unsigned.clone().sign(&other_keys),
+            Err(Error::PublicKeyMismatch)
+        ));
+        assert!(matches!(
+            unsigned.sign_deterministic(&other_keys),
+            Err(Error::PublicKeyMismatch)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_signature() -> Result<()> {
+        let keys = Keys::new(SecretKey::from_str(
+            "6b911fd37cdf5c81d4c0adb1ab7fa822ed253ab0ad9aa18d77257c88b29b718e",
+        )?);
+        let other_keys = Keys::generate();
+
+        let unsigned =
+            EventBuilder::new_text_note("hello", &[]).to_unsigned_event(keys.public_key());
+
+        // Signature made on "another device"
+        let message = Message::from_slice(unsigned.id.as_bytes())?;
+        let sig = SECP256K1.sign_schnorr(&message, &keys.key_pair()?);
+        let event = unsigned.clone().add_signature(sig)?;
+        assert_eq!(event.id, unsigned.id);
+
+        // Signature made with the wrong keys
+        let sig = SECP256K1.sign_schnorr(&message, &other_keys.key_pair()?);
+        assert!(unsigned.add_signature(sig).is_err());
+
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@
 //!
 //! This module defines the [`Keys`] structure.
 
+use std::fmt;
 #[cfg(feature = "nip19")]
 use std::str::FromStr;
 
@@ -63,13 +64,37 @@ pub trait FromPkStr: Sized {
 }
 
 /// Keys
-#[derive(Debug, Clone, Eq, PartialEq)]
+///
+/// The secret material is never printed by [`Debug`](fmt::Debug) and is zeroized on drop.
+#[derive(Clone, Eq, PartialEq)]
 pub struct Keys {
     public_key: XOnlyPublicKey,
     key_pair: Option<KeyPair>,
     secret_key: Option<SecretKey>,
 }
 
+impl fmt::Debug for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keys")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Keys {
+    fn drop(&mut self) {
+        // The copies returned by `Keys::secret_key` and `Keys::key_pair` are not wiped
+        if let Some(secret_key) = self.secret_key.as_mut() {
+            // SAFETY: `SecretKey` is a plain byte array without `Drop` and it's never used again
+            unsafe { zeroize::zeroize_flat_type(secret_key) };
+        }
+        if let Some(key_pair) = self.key_pair.as_mut() {
+            // SAFETY: `KeyPair` is a plain byte array without `Drop` and it's never used again
+            unsafe { zeroize::zeroize_flat_type(key_pair) };
+        }
+    }
+}
+
 impl Keys {
     /// Initialize from secret key.
     pub fn new(secret_key: SecretKey) -> Self {
@@ -154,3 +179,18 @@ impl FromPkStr for Keys {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let keys = Keys::generate();
+        let secret_key: String = keys.secret_key().unwrap().display_secret().to_string();
+        let debug: String = format!("{keys:?}");
+        assert!(debug.starts_with("Keys { public_key: "));
+        assert!(!debug.contains(&secret_key));
+        assert!(!format!("{keys:#?}").contains(&secret_key));
+    }
+}
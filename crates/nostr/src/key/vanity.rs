@@ -3,27 +3,56 @@
 
 //! Vanity
 
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{sync_channel, RecvError};
-use std::sync::Arc;
-use std::thread;
+use std::sync::mpsc::RecvError;
 
 use secp256k1::rand::rngs::OsRng;
 use secp256k1::SecretKey;
 
 use super::Keys;
 use crate::nips::nip19::{ToBech32, PREFIX_BECH32_PUBLIC_KEY};
+use crate::util::search;
+pub use crate::util::{CancellationToken, SearchError, SearchOptions, SearchProgress};
 use crate::SECP256K1;
 
 const BECH32_CHARS: &str = "023456789acdefghjklmnpqrstuvwxyz";
 const HEX_CHARS: &str = "0123456789abcdef";
 
+/// Length of the bech32 public key after `npub1` (52 chars of data and 6 of checksum)
+const BECH32_DATA_LEN: usize = 58;
+/// Index of the last data char of the bech32 public key: it holds only 1 bit of the key (and 4 bits of padding)
+const BECH32_LAST_DATA_INDEX: usize = 51;
+/// Possible values of the last data char of the bech32 public key
+const BECH32_LAST_DATA_CHARS: &str = "qs";
+/// Length of the hex public key
+const HEX_LEN: usize = 64;
+
+/// Number of keys generated between two checks of the stop conditions
+const BATCH_SIZE: u64 = 64;
+
 /// [`Keys`] vanity error
 #[derive(Debug, Eq, PartialEq, thiserror::Error)]
 pub enum Error {
     /// Unsupported char
     #[error("Unsupported char: {0}")]
     InvalidChar(char),
+    /// Empty pattern
+    #[error("Empty pattern")]
+    EmptyPattern,
+    /// No patterns
+    #[error("No patterns")]
+    NoPatterns,
+    /// Pattern that no public key can match
+    #[error("Pattern can never match: {0}")]
+    ImpossiblePattern(String),
+    /// Pattern longer than the public key
+    #[error("Pattern too long: {0}")]
+    PatternTooLong(String),
+    /// Search cancelled
+    #[error("Search cancelled")]
+    Cancelled,
+    /// Timeout
+    #[error("Timeout")]
+    Timeout,
     /// RecvError
     #[error(transparent)]
     RecvError(#[from] RecvError),
@@ -32,80 +61,344 @@ pub enum Error {
     JoinHandleError,
 }
 
-impl Keys {
-    /// Generate new vanity public key
-    pub fn vanity<S>(prefixes: Vec<S>, bech32: bool, num_cores: usize) -> Result<Self, Error>
+impl From<SearchError> for Error {
+    fn from(e: SearchError) -> Self {
+        match e {
+            SearchError::Cancelled => Self::Cancelled,
+            SearchError::Timeout => Self::Timeout,
+            SearchError::JoinHandleError => Self::JoinHandleError,
+        }
+    }
+}
+
+/// Where the [`VanityPattern`] must appear in the public key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Position {
+    /// At the beginning (after `npub1` for bech32)
+    Prefix,
+    /// At the end
+    Suffix,
+    /// Anywhere
+    Contains,
+}
+
+/// Vanity pattern
+///
+/// Patterns are case-insensitive: they are matched against the lowercase public key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VanityPattern {
+    value: String,
+    position: Position,
+}
+
+impl VanityPattern {
+    /// New [`VanityPattern`]
+    pub fn new<S>(value: S, position: Position) -> Self
     where
         S: Into<String>,
     {
-        let prefixes: Vec<String> = prefixes.into_iter().map(|p| p.into()).collect();
-
-        if bech32 {
-            for prefix in prefixes.iter() {
-                for c in prefix.chars() {
-                    if !BECH32_CHARS.contains(c) {
-                        return Err(Error::InvalidChar(c));
-                    }
-                }
-            }
+        Self {
+            value: value.into().to_lowercase(),
+            position,
+        }
+    }
+
+    /// Public key starting with `value`
+    pub fn prefix<S>(value: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(value, Position::Prefix)
+    }
+
+    /// Public key ending with `value`
+    pub fn suffix<S>(value: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(value, Position::Suffix)
+    }
+
+    /// Public key containing `value`
+    pub fn contains<S>(value: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(value, Position::Contains)
+    }
+
+    /// Get value
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get [`Position`]
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    fn verify(&self, bech32: bool) -> Result<(), Error> {
+        if self.value.is_empty() {
+            return Err(Error::EmptyPattern);
+        }
+
+        let (chars, len) = if bech32 {
+            (BECH32_CHARS, BECH32_DATA_LEN)
         } else {
-            for prefix in prefixes.iter() {
-                for c in prefix.chars() {
-                    if !HEX_CHARS.contains(c) {
-                        return Err(Error::InvalidChar(c));
-                    }
-                }
+            (HEX_CHARS, HEX_LEN)
+        };
+
+        if self.value.len() > len {
+            return Err(Error::PatternTooLong(self.value.clone()));
+        }
+
+        if let Some(c) = self.value.chars().find(|c| !chars.contains(*c)) {
+            return Err(Error::InvalidChar(c));
+        }
+
+        if bech32 && !self.is_possible_bech32() {
+            return Err(Error::ImpossiblePattern(self.value.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Check if the pattern fits in a bech32 public key in at least one of the allowed positions
+    fn is_possible_bech32(&self) -> bool {
+        let len: usize = self.value.len();
+        let last_start: usize = BECH32_DATA_LEN - len;
+        let starts = match self.position {
+            Position::Prefix => 0..=0,
+            Position::Suffix => last_start..=last_start,
+            Position::Contains => 0..=last_start,
+        };
+        starts.into_iter().any(|start| {
+            match BECH32_LAST_DATA_INDEX
+                .checked_sub(start)
+                .and_then(|i| self.value.chars().nth(i))
+            {
+                Some(c) => BECH32_LAST_DATA_CHARS.contains(c),
+                None => true,
             }
+        })
+    }
+
+    /// Probability for a random public key to match
+    fn probability(&self, bech32: bool) -> f64 {
+        let (alphabet, len) = if bech32 {
+            (BECH32_CHARS.len(), BECH32_DATA_LEN)
+        } else {
+            (HEX_CHARS.len(), HEX_LEN)
+        };
+        let p: f64 = (alphabet as f64).powi(-(self.value.len() as i32));
+        match self.position {
+            Position::Prefix | Position::Suffix => p,
+            // Approximation: the occurrences are not independent
+            Position::Contains => (p * (len - self.value.len() + 1) as f64).min(1.0),
         }
+    }
 
-        let (tx, rx) = sync_channel::<SecretKey>(1);
-        let found = Arc::new(AtomicBool::new(false));
-        let mut handles = vec![];
-
-        for _ in 0..num_cores {
-            let tx = tx.clone();
-            let found = found.clone();
-            let prefixes = prefixes.clone();
-            let handle = thread::spawn(move || {
-                let mut rng = OsRng::default();
-                loop {
-                    if found.load(Ordering::SeqCst) {
-                        break;
-                    }
-
-                    let (secret_key, public_key) = SECP256K1.generate_keypair(&mut rng);
-                    let (xonly_public_key, _) = public_key.x_only_public_key();
-
-                    if bech32 {
-                        let bech32_key = xonly_public_key
-                            .to_bech32()
-                            .expect("Unable to convert key to bech32");
-                        if prefixes.iter().any(|prefix| {
-                            bech32_key.starts_with(&format!("{PREFIX_BECH32_PUBLIC_KEY}1{prefix}"))
-                        }) {
-                            tx.send(secret_key).expect("Unable to send on channel");
-                            let _ = found
-                                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |_| Some(true));
-                            break;
-                        }
-                    } else {
-                        let pubkey = xonly_public_key.to_string();
-                        if prefixes.iter().any(|prefix| pubkey.starts_with(prefix)) {
-                            tx.send(secret_key).expect("Unable to send on channel");
-                            let _ = found
-                                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |_| Some(true));
-                            break;
-                        }
-                    }
-                }
-            });
-            handles.push(handle);
+    fn matches(&self, data: &str) -> bool {
+        match self.position {
+            Position::Prefix => data.starts_with(&self.value),
+            Position::Suffix => data.ends_with(&self.value),
+            Position::Contains => data.contains(&self.value),
         }
+    }
+}
+
+/// Check that there is at least one pattern and that all of them can match
+fn verify_patterns(patterns: &[VanityPattern], bech32: bool) -> Result<(), Error> {
+    if patterns.is_empty() {
+        return Err(Error::NoPatterns);
+    }
+    for pattern in patterns.iter() {
+        pattern.verify(bech32)?;
+    }
+    Ok(())
+}
+
+/// Expected number of keys to generate before one matches any of the `patterns`
+///
+/// Divide by the [`VanityProgress::rate`] to estimate the duration of the search.
+pub fn expected_attempts(patterns: &[VanityPattern], bech32: bool) -> Result<f64, Error> {
+    verify_patterns(patterns, bech32)?;
+    let probability: f64 = patterns
+        .iter()
+        .map(|pattern| pattern.probability(bech32))
+        .sum();
+    Ok(1.0 / probability.min(1.0))
+}
+
+/// Vanity search progress
+pub type VanityProgress = SearchProgress;
 
-        for handle in handles {
-            handle.join().map_err(|_| Error::JoinHandleError)?;
+/// Vanity search options
+pub type VanityOptions = SearchOptions;
+
+/// Generate `BATCH_SIZE` keys, returning the first one matching any of the `patterns`
+fn search_batch(patterns: &[VanityPattern], bech32: bool) -> Option<SecretKey> {
+    let mut rng = OsRng::default();
+    for _ in 0..BATCH_SIZE {
+        let (secret_key, public_key) = SECP256K1.generate_keypair(&mut rng);
+        let (xonly_public_key, _) = public_key.x_only_public_key();
+
+        let found: bool = if bech32 {
+            let bech32_key = xonly_public_key
+                .to_bech32()
+                .expect("Unable to convert key to bech32");
+            let data: &str = &bech32_key[PREFIX_BECH32_PUBLIC_KEY.len() + 1..];
+            patterns.iter().any(|pattern| pattern.matches(data))
+        } else {
+            let pubkey = xonly_public_key.to_string();
+            patterns.iter().any(|pattern| pattern.matches(&pubkey))
+        };
+
+        if found {
+            return Some(secret_key);
         }
+    }
+    None
+}
 
-        Ok(Self::new(rx.recv()?))
+impl Keys {
+    /// Generate new vanity public key, starting with any of the `prefixes`
+    pub fn vanity<S>(prefixes: Vec<S>, bech32: bool, num_cores: usize) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let patterns: Vec<VanityPattern> =
+            prefixes.into_iter().map(VanityPattern::prefix).collect();
+        Self::vanity_with_opts(patterns, bech32, &VanityOptions::new().threads(num_cores))
+    }
+
+    /// Generate new vanity public key, matching any of the `patterns`
+    ///
+    /// The patterns are matched against the bech32 (without `npub1`) or the hex public key.
+    /// The search is split on the threads set in [`VanityOptions`] and can be cancelled or time limited.
+    pub fn vanity_with_opts(
+        patterns: Vec<VanityPattern>,
+        bech32: bool,
+        opts: &VanityOptions,
+    ) -> Result<Self, Error> {
+        verify_patterns(&patterns, bech32)?;
+
+        let secret_key: SecretKey = search::run(opts, BATCH_SIZE, |_, _| {
+            let patterns = &patterns;
+            move || search_batch(patterns, bech32)
+        })?;
+
+        Ok(Self::new(secret_key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_vanity_patterns() {
+        let opts = VanityOptions::new().threads(2);
+
+        let keys = Keys::vanity_with_opts(vec![VanityPattern::suffix("Q")], true, &opts).unwrap();
+        assert!(keys.public_key().to_bech32().unwrap().ends_with('q'));
+
+        let keys =
+            Keys::vanity_with_opts(vec![VanityPattern::contains("ab")], false, &opts).unwrap();
+        assert!(keys.public_key().to_string().contains("ab"));
+
+        let keys = Keys::vanity(vec!["0"], false, 1).unwrap();
+        assert!(keys.public_key().to_string().starts_with('0'));
+
+        assert_eq!(
+            Keys::vanity_with_opts(vec![VanityPattern::prefix("b")], true, &opts).unwrap_err(),
+            Error::InvalidChar('b')
+        );
+        assert_eq!(
+            Keys::vanity_with_opts(vec![VanityPattern::prefix("")], false, &opts).unwrap_err(),
+            Error::EmptyPattern
+        );
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        let opts = VanityOptions::new().threads(2);
+
+        assert_eq!(
+            Keys::vanity_with_opts(Vec::new(), true, &opts).unwrap_err(),
+            Error::NoPatterns
+        );
+        assert_eq!(
+            expected_attempts(&[], false).unwrap_err(),
+            Error::NoPatterns
+        );
+
+        // The last data char of a bech32 public key is `q` or `s`
+        let prefix: String = format!("{}p", "q".repeat(BECH32_LAST_DATA_INDEX));
+        assert_eq!(
+            Keys::vanity_with_opts(vec![VanityPattern::prefix(prefix.clone())], true, &opts)
+                .unwrap_err(),
+            Error::ImpossiblePattern(prefix)
+        );
+        assert_eq!(
+            expected_attempts(&[VanityPattern::suffix("pqqqqqq")], true).unwrap_err(),
+            Error::ImpossiblePattern(String::from("pqqqqqq"))
+        );
+        assert!(expected_attempts(&[VanityPattern::suffix("sqqqqqq")], true).is_ok());
+        assert!(expected_attempts(&[VanityPattern::contains("pqqqqqq")], true).is_ok());
+        let contains: String = "p".repeat(BECH32_DATA_LEN);
+        assert_eq!(
+            expected_attempts(&[VanityPattern::contains(contains.clone())], true).unwrap_err(),
+            Error::ImpossiblePattern(contains)
+        );
+    }
+
+    #[test]
+    fn test_expected_attempts() {
+        assert_eq!(
+            expected_attempts(&[VanityPattern::prefix("00")], false).unwrap(),
+            256.0
+        );
+        assert_eq!(
+            expected_attempts(
+                &[VanityPattern::prefix("0"), VanityPattern::suffix("0")],
+                true
+            )
+            .unwrap(),
+            16.0
+        );
+        let contains = expected_attempts(&[VanityPattern::contains("000")], false).unwrap();
+        assert!(contains > 60.0 && contains < 70.0);
+    }
+
+    #[test]
+    fn test_cancel_and_timeout() {
+        // Practically impossible to find
+        let patterns = vec![VanityPattern::prefix("qqqqqqqqqqqqqqqq")];
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let opts = VanityOptions::new().threads(2).cancellation_token(token);
+        assert_eq!(
+            Keys::vanity_with_opts(patterns.clone(), true, &opts).unwrap_err(),
+            Error::Cancelled
+        );
+
+        let progress: Arc<Mutex<Vec<VanityProgress>>> = Arc::new(Mutex::new(Vec::new()));
+        let p = progress.clone();
+        let opts = VanityOptions::new()
+            .threads(1)
+            .timeout(Duration::from_millis(200))
+            .progress(Duration::from_millis(10), move |prog| {
+                p.lock().unwrap().push(prog)
+            });
+        assert_eq!(
+            Keys::vanity_with_opts(patterns, true, &opts).unwrap_err(),
+            Error::Timeout
+        );
+        assert!(!progress.lock().unwrap().is_empty());
     }
 }
@@ -19,6 +19,7 @@ pub use bitcoin_hashes as hashes;
 pub use secp256k1::{self, SECP256K1};
 #[cfg(feature = "base")]
 pub use url::{self, Url};
+pub use zeroize;
 
 #[cfg(feature = "base")]
 pub mod event;
@@ -29,14 +30,15 @@ pub mod nips;
 pub mod prelude;
 #[cfg(feature = "base")]
 pub mod types;
+pub mod util;
 
 #[cfg(feature = "base")]
-pub use self::event::{Event, EventBuilder, EventId, Kind, Tag};
+pub use self::event::{Event, EventBuilder, EventId, EventRef, Kind, Tag, UnsignedEvent};
 pub use self::key::Keys;
 #[cfg(feature = "base")]
-pub use self::message::{ClientMessage, Filter, RelayMessage, SubscriptionId};
+pub use self::message::{ClientMessage, Filter, RelayMessage, RelayMessageRef, SubscriptionId};
 #[cfg(feature = "base")]
-pub use self::types::{ChannelId, Contact, Entity, Metadata, Profile, Timestamp};
+pub use self::types::{ChannelId, Contact, Coordinate, Entity, Metadata, Profile, Timestamp};
 
 #[allow(deprecated)]
 #[cfg(feature = "base")]
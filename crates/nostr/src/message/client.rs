@@ -5,10 +5,13 @@
 //! Client messages
 
 use serde::de::Error;
+use serde::ser::SerializeSeq;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::{json, Value};
+use serde_json::value::RawValue;
 
-use super::{Filter, MessageHandleError, SubscriptionId};
+use super::{
+    parse_array, parse_element, parse_event, parse_type, Filter, MessageHandleError, SubscriptionId,
+};
 use crate::Event;
 
 /// Messages sent by clients, received by relays
@@ -33,8 +36,39 @@ impl Serialize for ClientMessage {
     where
         S: Serializer,
     {
-        let json_value: Value = self.as_value();
-        json_value.serialize(serializer)
+        match self {
+            Self::Event(event) => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element("EVENT")?;
+                seq.serialize_element(event)?;
+                seq.end()
+            }
+            Self::Req {
+                subscription_id,
+                filters,
+            } => {
+                // Filters are flattened in the message
+                let mut seq = serializer.serialize_seq(Some(2 + filters.len()))?;
+                seq.serialize_element("REQ")?;
+                seq.serialize_element(subscription_id)?;
+                for filter in filters.iter() {
+                    seq.serialize_element(filter)?;
+                }
+                seq.end()
+            }
+            Self::Close(subscription_id) => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element("CLOSE")?;
+                seq.serialize_element(subscription_id)?;
+                seq.end()
+            }
+            Self::Auth(event) => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element("AUTH")?;
+                seq.serialize_element(event)?;
+                seq.end()
+            }
+        }
     }
 }
 
@@ -43,8 +77,8 @@ impl<'de> Deserialize<'de> for ClientMessage {
     where
         D: Deserializer<'de>,
     {
-        let json_value = Value::deserialize(deserializer)?;
-        ClientMessage::from_value(json_value).map_err(Error::custom)
+        let raw: Box<RawValue> = Box::deserialize(deserializer)?;
+        Self::from_raw(raw.get()).map_err(Error::custom)
     }
 }
 
@@ -72,114 +106,72 @@ impl ClientMessage {
         Self::Auth(Box::new(event))
     }
 
-    fn as_value(&self) -> Value {
-        match self {
-            Self::Event(event) => json!(["EVENT", event]),
-            Self::Req {
-                subscription_id,
-                filters,
-            } => {
-                let mut json = json!(["REQ", subscription_id]);
-                let mut filters = json!(filters);
-
-                if let Some(json) = json.as_array_mut() {
-                    if let Some(filters) = filters.as_array_mut() {
-                        json.append(filters);
-                    }
-                }
-
-                json
-            }
-            Self::Close(subscription_id) => json!(["CLOSE", subscription_id]),
-            Self::Auth(event) => json!(["AUTH", event]),
-        }
-    }
-
     /// Serialize [`ClientMessage`] as JSON string
     pub fn as_json(&self) -> String {
-        self.as_value().to_string()
+        // Serializing a client message never fails
+        serde_json::to_string(self).unwrap_or_default()
     }
 
-    fn from_value(msg: Value) -> Result<Self, MessageHandleError> {
-        let v = msg
-            .as_array()
-            .ok_or(MessageHandleError::InvalidMessageFormat)?;
-
-        if v.is_empty() {
-            return Err(MessageHandleError::InvalidMessageFormat);
-        }
-
+    /// Deserialize [`ClientMessage`] from JSON string, without intermediate [`serde_json::Value`]
+    fn from_raw(msg: &str) -> Result<Self, MessageHandleError> {
+        let v: Vec<&RawValue> = parse_array(msg)?;
         let v_len: usize = v.len();
 
-        // Event
-        // ["EVENT", <event JSON>]
-        if v[0] == "EVENT" {
-            if v_len != 2 {
-                return Err(MessageHandleError::InvalidMessageFormat);
+        match parse_type(&v)? {
+            // Event
+            // ["EVENT", <event JSON>]
+            "EVENT" => {
+                if v_len != 2 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                let event: Event = parse_event(v[1])?;
+                Ok(Self::new_event(event))
             }
-            let event = Event::from_json(v[1].to_string())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-            return Ok(Self::new_event(event));
-        }
-
-        // Req
-        // ["REQ", <subscription_id>, <filter JSON>, <filter JSON>...]
-        if v[0] == "REQ" {
-            if v_len == 2 {
-                let subscription_id: SubscriptionId = serde_json::from_value(v[1].clone())
-                    .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-                return Ok(Self::new_req(subscription_id, Vec::new()));
-            } else if v_len == 3 {
-                let subscription_id: SubscriptionId = serde_json::from_value(v[1].clone())
-                    .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-                let filters: Vec<Filter> = serde_json::from_value(Value::Array(v[2..].to_vec()))
-                    .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-                return Ok(Self::new_req(subscription_id, filters));
-            } else {
-                return Err(MessageHandleError::InvalidMessageFormat);
+            // Req
+            // ["REQ", <subscription_id>, <filter JSON>, <filter JSON>...]
+            "REQ" => {
+                if v_len < 2 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                let subscription_id: SubscriptionId = parse_element(v[1])?;
+                let filters: Vec<Filter> = v[2..]
+                    .iter()
+                    .map(|raw| parse_element(raw))
+                    .collect::<Result<_, _>>()?;
+                Ok(Self::new_req(subscription_id, filters))
             }
-        }
-
-        // Close
-        // ["CLOSE", <subscription_id>]
-        if v[0] == "CLOSE" {
-            if v_len != 2 {
-                return Err(MessageHandleError::InvalidMessageFormat);
+            // Close
+            // ["CLOSE", <subscription_id>]
+            "CLOSE" => {
+                if v_len != 2 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                let subscription_id: SubscriptionId = parse_element(v[1])?;
+                Ok(Self::close(subscription_id))
             }
-
-            let subscription_id: SubscriptionId = serde_json::from_value(v[1].clone())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-
-            return Ok(Self::close(subscription_id));
-        }
-
-        // Auth
-        // ["AUTH", <event JSON>]
-        if v[0] == "AUTH" {
-            if v_len != 2 {
-                return Err(MessageHandleError::InvalidMessageFormat);
+            // Auth
+            // ["AUTH", <event JSON>]
+            "AUTH" => {
+                if v_len != 2 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                let event: Event = parse_event(v[1])?;
+                Ok(Self::new_auth(event))
             }
-            let event = Event::from_json(v[1].to_string())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-            return Ok(Self::new_auth(event));
+            _ => Err(MessageHandleError::InvalidMessageFormat),
         }
-
-        Err(MessageHandleError::InvalidMessageFormat)
     }
 
     /// Deserialize [`ClientMessage`] from JSON string
     pub fn from_json<S>(msg: S) -> Result<Self, MessageHandleError>
     where
-        S: Into<String>,
+        S: AsRef<str>,
     {
-        let msg: &str = &msg.into();
+        let msg: &str = msg.as_ref();
 
         log::trace!("{}", msg);
 
-        let value: Value =
-            serde_json::from_str(msg).map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-
-        Self::from_value(value)
+        Self::from_raw(msg)
     }
 }
 
@@ -211,6 +203,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_client_message_from_json() {
+        let pk = XOnlyPublicKey::from_str(
+            "379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe",
+        )
+        .unwrap();
+        let client_req = ClientMessage::new_req(
+            SubscriptionId::new("test"),
+            vec![
+                Filter::new().kind(Kind::EncryptedDirectMessage).limit(10),
+                Filter::new().pubkey(pk),
+            ],
+        );
+        assert_eq!(
+            ClientMessage::from_json(client_req.as_json()).unwrap(),
+            client_req
+        );
+        assert_eq!(
+            serde_json::from_str::<ClientMessage>(&client_req.as_json()).unwrap(),
+            client_req
+        );
+
+        assert_eq!(
+            ClientMessage::from_json(r#"["REQ","test"]"#).unwrap(),
+            ClientMessage::new_req(SubscriptionId::new("test"), Vec::new())
+        );
+        assert_eq!(
+            ClientMessage::from_json(r#"["CLOSE","test"]"#).unwrap(),
+            ClientMessage::close(SubscriptionId::new("test"))
+        );
+
+        assert_eq!(
+            ClientMessage::from_json(r#"{"type":"CLOSE"}"#).unwrap_err(),
+            MessageHandleError::InvalidMessageFormat
+        );
+        assert_eq!(
+            ClientMessage::from_json(r#"["CLOSE"]"#).unwrap_err(),
+            MessageHandleError::InvalidMessageFormat
+        );
+        assert_eq!(
+            ClientMessage::from_json(r#"["REQ","test",{"kinds":"1"}]"#).unwrap_err(),
+            MessageHandleError::JsonDeserializationFailed
+        );
+        assert_eq!(
+            ClientMessage::from_json(r#"["CLOSE","test""#).unwrap_err(),
+            MessageHandleError::JsonDeserializationFailed
+        );
+    }
+
     #[test]
     fn test_client_message_custom_kind() {
         let pk = XOnlyPublicKey::from_str(
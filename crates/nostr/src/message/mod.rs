@@ -3,13 +3,18 @@
 
 //! Messages
 
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use crate::Event;
+
 pub mod client;
 pub mod relay;
 pub mod subscription;
 
 pub use self::client::ClientMessage;
-pub use self::relay::RelayMessage;
-pub use self::subscription::{Filter, SubscriptionId};
+pub use self::relay::{RelayMessage, RelayMessageRef};
+pub use self::subscription::{Alphabet, Filter, SingleLetterTag, SubscriptionId};
 
 #[allow(deprecated)]
 pub use self::subscription::SubscriptionFilter;
@@ -24,3 +29,37 @@ pub enum MessageHandleError {
     #[error("Json deserialization failed")]
     JsonDeserializationFailed,
 }
+
+/// Split a JSON array message in its raw elements, without parsing them
+fn parse_array(msg: &str) -> Result<Vec<&RawValue>, MessageHandleError> {
+    serde_json::from_str(msg).map_err(|e| {
+        if e.is_data() {
+            MessageHandleError::InvalidMessageFormat
+        } else {
+            MessageHandleError::JsonDeserializationFailed
+        }
+    })
+}
+
+/// Get the type of a message (first element of the array)
+fn parse_type<'a>(v: &[&'a RawValue]) -> Result<&'a str, MessageHandleError> {
+    let first: &RawValue = v.first().ok_or(MessageHandleError::InvalidMessageFormat)?;
+    serde_json::from_str(first.get()).map_err(|_| MessageHandleError::InvalidMessageFormat)
+}
+
+/// Deserialize a raw element of a message
+fn parse_element<'a, T>(raw: &'a RawValue) -> Result<T, MessageHandleError>
+where
+    T: Deserialize<'a>,
+{
+    serde_json::from_str(raw.get()).map_err(|_| MessageHandleError::JsonDeserializationFailed)
+}
+
+/// Deserialize and verify a raw [`Event`] element of a message
+fn parse_event(raw: &RawValue) -> Result<Event, MessageHandleError> {
+    let event: Event = parse_element(raw)?;
+    event
+        .verify()
+        .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
+    Ok(event)
+}
@@ -4,12 +4,15 @@
 
 //! Relay messages
 
+use std::borrow::Cow;
+
 use serde::de::Error;
+use serde::ser::SerializeSeq;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::{json, Value};
+use serde_json::value::RawValue;
 
-use super::MessageHandleError;
-use crate::{Event, EventId, SubscriptionId};
+use super::{parse_array, parse_element, parse_event, parse_type, MessageHandleError};
+use crate::{Event, EventId, EventRef, SubscriptionId};
 
 /// Messages sent by relays, received by clients
 #[allow(missing_docs)]
@@ -39,8 +42,49 @@ impl Serialize for RelayMessage {
     where
         S: Serializer,
     {
-        let json_value: Value = self.as_value();
-        json_value.serialize(serializer)
+        match self {
+            Self::Event {
+                event,
+                subscription_id,
+            } => {
+                let mut seq = serializer.serialize_seq(Some(3))?;
+                seq.serialize_element("EVENT")?;
+                seq.serialize_element(subscription_id)?;
+                seq.serialize_element(event)?;
+                seq.end()
+            }
+            Self::Notice { message } => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element("NOTICE")?;
+                seq.serialize_element(message)?;
+                seq.end()
+            }
+            Self::EndOfStoredEvents(subscription_id) => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element("EOSE")?;
+                seq.serialize_element(subscription_id)?;
+                seq.end()
+            }
+            Self::Ok {
+                event_id,
+                status,
+                message,
+            } => {
+                let mut seq = serializer.serialize_seq(Some(4))?;
+                seq.serialize_element("OK")?;
+                seq.serialize_element(event_id)?;
+                seq.serialize_element(status)?;
+                seq.serialize_element(message)?;
+                seq.end()
+            }
+            Self::Auth { challenge } => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element("AUTH")?;
+                seq.serialize_element(challenge)?;
+                seq.end()
+            }
+            Self::Empty => serializer.serialize_unit(),
+        }
     }
 }
 
@@ -49,8 +93,8 @@ impl<'de> Deserialize<'de> for RelayMessage {
     where
         D: Deserializer<'de>,
     {
-        let json_value = Value::deserialize(deserializer)?;
-        RelayMessage::from_value(json_value).map_err(Error::custom)
+        let raw: Box<RawValue> = Box::deserialize(deserializer)?;
+        Self::from_raw(raw.get(), true).map_err(Error::custom)
     }
 }
 
@@ -100,124 +144,197 @@ impl RelayMessage {
         }
     }
 
-    fn as_value(&self) -> Value {
-        match self {
-            Self::Event {
-                event,
-                subscription_id,
-            } => json!(["EVENT", subscription_id, event]),
-            Self::Notice { message } => json!(["NOTICE", message]),
-            Self::EndOfStoredEvents(subscription_id) => {
-                json!(["EOSE", subscription_id])
-            }
-            Self::Ok {
-                event_id,
-                status,
-                message,
-            } => json!(["OK", event_id, status, message]),
-            Self::Auth { challenge } => json!(["AUTH", challenge]),
-            Self::Empty => Value::Null,
-        }
-    }
-
     /// Serialize [`RelayMessage`] as JSON string
     pub fn as_json(&self) -> String {
-        self.as_value().to_string()
+        // Serializing a relay message never fails
+        serde_json::to_string(self).unwrap_or_default()
     }
 
-    /// Deserialize [`RelayMessage`] from [`Value`]
-    fn from_value(msg: Value) -> Result<Self, MessageHandleError> {
-        let v = msg
-            .as_array()
-            .ok_or(MessageHandleError::InvalidMessageFormat)?;
-
-        if v.is_empty() {
-            return Err(MessageHandleError::InvalidMessageFormat);
-        }
-
+    /// Deserialize [`RelayMessage`] from JSON string, without intermediate [`serde_json::Value`]
+    fn from_raw(msg: &str, verify: bool) -> Result<Self, MessageHandleError> {
+        let v: Vec<&RawValue> = parse_array(msg)?;
         let v_len: usize = v.len();
 
-        // Notice
-        // Relay response format: ["NOTICE", <message>]
-        if v[0] == "NOTICE" {
-            if v_len != 2 {
-                return Err(MessageHandleError::InvalidMessageFormat);
+        match parse_type(&v)? {
+            // Notice
+            // Relay response format: ["NOTICE", <message>]
+            "NOTICE" => {
+                if v_len != 2 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                let message: String = parse_element(v[1])?;
+                Ok(Self::Notice { message })
             }
-            let v_notice: String = serde_json::from_value(v[1].clone())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-            return Ok(Self::Notice { message: v_notice });
-        }
-
-        // Event
-        // Relay response format: ["EVENT", <subscription id>, <event JSON>]
-        if v[0] == "EVENT" {
-            if v_len != 3 {
-                return Err(MessageHandleError::InvalidMessageFormat);
+            // Event
+            // Relay response format: ["EVENT", <subscription id>, <event JSON>]
+            "EVENT" => {
+                if v_len != 3 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                let subscription_id: SubscriptionId = parse_element(v[1])?;
+                let event: Event = if verify {
+                    parse_event(v[2])?
+                } else {
+                    parse_element(v[2])?
+                };
+                Ok(Self::new_event(subscription_id, event))
             }
-
-            let subscription_id: SubscriptionId = serde_json::from_value(v[1].clone())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-            let event = Event::from_json(v[2].to_string())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-
-            return Ok(Self::new_event(subscription_id, event));
-        }
-
-        // EOSE (NIP-15)
-        // Relay response format: ["EOSE", <subscription_id>]
-        if v[0] == "EOSE" {
-            if v_len != 2 {
-                return Err(MessageHandleError::InvalidMessageFormat);
+            // EOSE (NIP-15)
+            // Relay response format: ["EOSE", <subscription_id>]
+            "EOSE" => {
+                if v_len != 2 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                let subscription_id: SubscriptionId = parse_element(v[1])?;
+                Ok(Self::new_eose(subscription_id))
             }
-
-            let subscription_id: SubscriptionId = serde_json::from_value(v[1].clone())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
-
-            return Ok(Self::new_eose(subscription_id));
+            // OK (NIP-20)
+            // Relay response format: ["OK", <event_id>, <true|false>, <message>]
+            "OK" => {
+                if v_len != 4 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                let event_id: EventId = parse_element(v[1])?;
+                let status: bool = parse_element(v[2])?;
+                let message: String = parse_element(v[3])?;
+                Ok(Self::new_ok(event_id, status, message))
+            }
+            _ => Err(MessageHandleError::InvalidMessageFormat),
         }
+    }
 
-        // OK (NIP-20)
-        // Relay response format: ["OK", <event_id>, <true|false>, <message>]
-        if v[0] == "OK" {
-            if v_len != 4 {
-                return Err(MessageHandleError::InvalidMessageFormat);
-            }
+    /// Deserialize [`RelayMessage`] as JSON string
+    pub fn from_json<S>(msg: S) -> Result<Self, MessageHandleError>
+    where
+        S: AsRef<str>,
+    {
+        let msg: &str = msg.as_ref();
 
-            let event_id: EventId = serde_json::from_value(v[1].clone())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
+        log::trace!("{}", msg);
 
-            let status: bool = serde_json::from_value(v[2].clone())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
+        Self::from_raw(msg, true)
+    }
 
-            let message: String = serde_json::from_value(v[3].clone())
-                .map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
+    /// Deserialize [`RelayMessage`] as JSON string, without verifying the event
+    ///
+    /// The caller must verify the event (ex. with [`Event::verify_batch`]) before trusting it.
+    pub fn from_json_unverified<S>(msg: S) -> Result<Self, MessageHandleError>
+    where
+        S: AsRef<str>,
+    {
+        let msg: &str = msg.as_ref();
 
-            return Ok(Self::new_ok(event_id, status, message));
-        }
+        log::trace!("{}", msg);
 
-        Err(MessageHandleError::InvalidMessageFormat)
+        Self::from_raw(msg, false)
     }
+}
 
-    /// Deserialize [`RelayMessage`] as JSON string
-    pub fn from_json<S>(msg: S) -> Result<Self, MessageHandleError>
-    where
-        S: Into<String>,
-    {
-        let msg: &str = &msg.into();
+/// [`RelayMessage`] borrowed from the input buffer
+///
+/// Events are not verified until converted to [`RelayMessage`].
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub enum RelayMessageRef<'a> {
+    Event {
+        subscription_id: Cow<'a, str>,
+        event: Box<EventRef<'a>>,
+    },
+    Notice {
+        message: Cow<'a, str>,
+    },
+    EndOfStoredEvents(Cow<'a, str>),
+    Ok {
+        event_id: &'a str,
+        status: bool,
+        message: Cow<'a, str>,
+    },
+}
 
+impl<'a> RelayMessageRef<'a> {
+    /// Deserialize [`RelayMessageRef`] from JSON string
+    pub fn from_json(msg: &'a str) -> Result<Self, MessageHandleError> {
         log::trace!("{}", msg);
 
-        let value: Value =
-            serde_json::from_str(msg).map_err(|_| MessageHandleError::JsonDeserializationFailed)?;
+        let v: Vec<&RawValue> = parse_array(msg)?;
+        let v_len: usize = v.len();
+
+        match parse_type(&v)? {
+            // Relay response format: ["NOTICE", <message>]
+            "NOTICE" => {
+                if v_len != 2 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                Ok(Self::Notice {
+                    message: parse_element(v[1])?,
+                })
+            }
+            // Relay response format: ["EVENT", <subscription id>, <event JSON>]
+            "EVENT" => {
+                if v_len != 3 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                Ok(Self::Event {
+                    subscription_id: parse_element(v[1])?,
+                    event: Box::new(parse_element(v[2])?),
+                })
+            }
+            // Relay response format: ["EOSE", <subscription_id>]
+            "EOSE" => {
+                if v_len != 2 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                Ok(Self::EndOfStoredEvents(parse_element(v[1])?))
+            }
+            // Relay response format: ["OK", <event_id>, <true|false>, <message>]
+            "OK" => {
+                if v_len != 4 {
+                    return Err(MessageHandleError::InvalidMessageFormat);
+                }
+                Ok(Self::Ok {
+                    event_id: parse_element(v[1])?,
+                    status: parse_element(v[2])?,
+                    message: parse_element(v[3])?,
+                })
+            }
+            _ => Err(MessageHandleError::InvalidMessageFormat),
+        }
+    }
 
-        Self::from_value(value)
+    /// Convert to owned [`RelayMessage`], verifying the event
+    pub fn to_relay_message(&self) -> Result<RelayMessage, MessageHandleError> {
+        match self {
+            Self::Event {
+                subscription_id,
+                event,
+            } => Ok(RelayMessage::new_event(
+                SubscriptionId::new(subscription_id.as_ref()),
+                event
+                    .to_event()
+                    .map_err(|_| MessageHandleError::JsonDeserializationFailed)?,
+            )),
+            Self::Notice { message } => Ok(RelayMessage::new_notice(message.as_ref())),
+            Self::EndOfStoredEvents(subscription_id) => Ok(RelayMessage::new_eose(
+                SubscriptionId::new(subscription_id.as_ref()),
+            )),
+            Self::Ok {
+                event_id,
+                status,
+                message,
+            } => Ok(RelayMessage::new_ok(
+                EventId::from_hex(*event_id)
+                    .map_err(|_| MessageHandleError::JsonDeserializationFailed)?,
+                *status,
+                message.as_ref(),
+            )),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Result, Timestamp};
+    use crate::{Kind, Result, Timestamp};
 
     #[test]
     fn test_handle_valid_notice() -> Result<()> {
@@ -289,6 +406,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_handle_unverified_event() -> Result<()> {
+        // Valid event with a forged signature
+        let forged_event_msg = r#"["EVENT", "random_string", {"id":"70b10f70c1318967eddf12527799411b1a9780ad9c43858f5e5fcd45486a13a5","pubkey":"379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe","created_at":1612809991,"kind":1,"tags":[],"content":"test","sig":"273a9cd5d11455590f4359500bccb7a89428262b96b3ea87a756b770964472f8c3e87f5d5e64d8d2e859a71462a3f477b554565c4f2f326cb01dd7620db71503"}]"#;
+
+        assert_eq!(
+            RelayMessage::from_json(forged_event_msg).unwrap_err(),
+            MessageHandleError::JsonDeserializationFailed
+        );
+
+        match RelayMessage::from_json_unverified(forged_event_msg)? {
+            RelayMessage::Event { event, .. } => assert!(event.verify().is_err()),
+            _ => panic!("expected an EVENT message"),
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_handle_valid_eose() -> Result<()> {
         let valid_eose_msg = r#"["EOSE","random-subscription-id"]"#;
@@ -388,4 +523,43 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_relay_message_ref() -> Result<()> {
+        let msg = r#"["EVENT", "random_string", {"id":"70b10f70c1318967eddf12527799411b1a9780ad9c43858f5e5fcd45486a13a5","pubkey":"379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe","created_at":1612809991,"kind":1,"tags":[],"content":"test","sig":"273a9cd5d11455590f4359500bccb7a89428262b96b3ea87a756b770964472f8c3e87f5d5e64d8d2e859a71462a3f477b554565c4f2f326cb01dd7620db71502"}]"#;
+
+        match RelayMessageRef::from_json(msg)? {
+            RelayMessageRef::Event {
+                subscription_id,
+                event,
+            } => {
+                assert_eq!(subscription_id, "random_string");
+                assert_eq!(event.kind, Kind::TextNote);
+                assert_eq!(event.content, "test");
+                assert!(event.tags()?.is_empty());
+            }
+            _ => panic!("expected an EVENT message"),
+        }
+        assert_eq!(
+            RelayMessageRef::from_json(msg)?.to_relay_message()?,
+            RelayMessage::from_json(msg)?
+        );
+
+        let msg = r#"["OK", "b1a649ebe8b435ec71d3784793f3bbf4b93e64e17568a741aecd4c7ddeafce30", true, "pow: difficulty 25>=24"]"#;
+        assert_eq!(
+            RelayMessageRef::from_json(msg)?.to_relay_message()?,
+            RelayMessage::from_json(msg)?
+        );
+
+        assert_eq!(
+            RelayMessageRef::from_json(r#"["EOSE"]"#).unwrap_err(),
+            MessageHandleError::InvalidMessageFormat
+        );
+        assert_eq!(
+            RelayMessageRef::from_json(r#"["EOSE", 404]"#).unwrap_err(),
+            MessageHandleError::JsonDeserializationFailed
+        );
+
+        Ok(())
+    }
 }
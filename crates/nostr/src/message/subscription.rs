@@ -6,14 +6,199 @@
 
 #![allow(missing_docs)]
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
 use bitcoin_hashes::sha256::Hash as Sha256Hash;
 use bitcoin_hashes::Hash;
 use secp256k1::rand::rngs::OsRng;
 use secp256k1::rand::RngCore;
 use secp256k1::XOnlyPublicKey;
+use serde::de::{Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 
-use crate::{EventId, Kind, Timestamp};
+use crate::{Coordinate, Event, EventId, Kind, Tag, Timestamp};
+
+/// [`Alphabet`] error
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    /// Invalid char
+    #[error("invalid char")]
+    InvalidChar,
+}
+
+/// Latin alphabet letter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Alphabet {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+}
+
+impl Alphabet {
+    /// Get [`Alphabet`] as lowercase [`char`]
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::A => 'a',
+            Self::B => 'b',
+            Self::C => 'c',
+            Self::D => 'd',
+            Self::E => 'e',
+            Self::F => 'f',
+            Self::G => 'g',
+            Self::H => 'h',
+            Self::I => 'i',
+            Self::J => 'j',
+            Self::K => 'k',
+            Self::L => 'l',
+            Self::M => 'm',
+            Self::N => 'n',
+            Self::O => 'o',
+            Self::P => 'p',
+            Self::Q => 'q',
+            Self::R => 'r',
+            Self::S => 's',
+            Self::T => 't',
+            Self::U => 'u',
+            Self::V => 'v',
+            Self::W => 'w',
+            Self::X => 'x',
+            Self::Y => 'y',
+            Self::Z => 'z',
+        }
+    }
+}
+
+impl TryFrom<char> for Alphabet {
+    type Error = Error;
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c.to_ascii_lowercase() {
+            'a' => Ok(Self::A),
+            'b' => Ok(Self::B),
+            'c' => Ok(Self::C),
+            'd' => Ok(Self::D),
+            'e' => Ok(Self::E),
+            'f' => Ok(Self::F),
+            'g' => Ok(Self::G),
+            'h' => Ok(Self::H),
+            'i' => Ok(Self::I),
+            'j' => Ok(Self::J),
+            'k' => Ok(Self::K),
+            'l' => Ok(Self::L),
+            'm' => Ok(Self::M),
+            'n' => Ok(Self::N),
+            'o' => Ok(Self::O),
+            'p' => Ok(Self::P),
+            'q' => Ok(Self::Q),
+            'r' => Ok(Self::R),
+            's' => Ok(Self::S),
+            't' => Ok(Self::T),
+            'u' => Ok(Self::U),
+            'v' => Ok(Self::V),
+            'w' => Ok(Self::W),
+            'x' => Ok(Self::X),
+            'y' => Ok(Self::Y),
+            'z' => Ok(Self::Z),
+            _ => Err(Error::InvalidChar),
+        }
+    }
+}
+
+/// Single-letter tag name (ex. `e`, `p`, `d`, `L`)
+///
+/// <https://github.com/nostr-protocol/nips/blob/master/01.md>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SingleLetterTag {
+    /// Letter
+    pub character: Alphabet,
+    /// Uppercase letter
+    pub uppercase: bool,
+}
+
+impl SingleLetterTag {
+    /// Lowercase single-letter tag
+    pub fn lowercase(character: Alphabet) -> Self {
+        Self {
+            character,
+            uppercase: false,
+        }
+    }
+
+    /// Uppercase single-letter tag
+    pub fn uppercase(character: Alphabet) -> Self {
+        Self {
+            character,
+            uppercase: true,
+        }
+    }
+
+    /// Get [`SingleLetterTag`] as [`char`]
+    pub fn as_char(&self) -> char {
+        if self.uppercase {
+            self.character.as_char().to_ascii_uppercase()
+        } else {
+            self.character.as_char()
+        }
+    }
+}
+
+impl From<Alphabet> for SingleLetterTag {
+    fn from(character: Alphabet) -> Self {
+        Self::lowercase(character)
+    }
+}
+
+impl TryFrom<char> for SingleLetterTag {
+    type Error = Error;
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        Ok(Self {
+            character: Alphabet::try_from(c)?,
+            uppercase: c.is_ascii_uppercase(),
+        })
+    }
+}
+
+impl FromStr for SingleLetterTag {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Self::try_from(c),
+            _ => Err(Error::InvalidChar),
+        }
+    }
+}
+
+impl fmt::Display for SingleLetterTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_char())
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct SubscriptionId(String);
@@ -44,34 +229,32 @@ impl ToString for SubscriptionId {
 #[deprecated(since = "0.19.0", note = "Use `Filter` instead.")]
 pub type SubscriptionFilter = Filter;
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
 pub struct Filter {
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub ids: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(flatten, deserialize_with = "deserialize_authors")]
     pub authors: Option<Vec<XOnlyPublicKey>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Prefixes of the authors public keys, serialized in `authors` too
+    #[serde(flatten, deserialize_with = "deserialize_author_prefixes")]
+    pub author_prefixes: Option<Vec<String>>,
     pub kinds: Option<Vec<Kind>>,
     #[serde(rename = "#e")]
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub events: Option<Vec<EventId>>,
     #[serde(rename = "#p")]
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub pubkeys: Option<Vec<XOnlyPublicKey>>,
     #[serde(rename = "#t")]
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub hashtags: Option<Vec<String>>,
     #[serde(rename = "#r")]
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub references: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "#d")]
+    pub identifiers: Option<Vec<String>>,
     pub search: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub since: Option<Timestamp>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub until: Option<Timestamp>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<usize>,
+    /// Single-letter tag queries (`#<letter>`) without a dedicated field
+    #[serde(flatten, deserialize_with = "deserialize_generic_tags")]
+    pub generic_tags: BTreeMap<SingleLetterTag, Vec<String>>,
 }
 
 impl Default for Filter {
@@ -89,11 +272,14 @@ impl Filter {
             pubkeys: None,
             hashtags: None,
             references: None,
+            identifiers: None,
             search: None,
             since: None,
             until: None,
             authors: None,
+            author_prefixes: None,
             limit: None,
+            generic_tags: BTreeMap::new(),
         }
     }
 
@@ -129,6 +315,22 @@ impl Filter {
         }
     }
 
+    /// Set author prefix
+    pub fn author_prefix(self, prefix: impl Into<String>) -> Self {
+        Self {
+            author_prefixes: Some(vec![prefix.into()]),
+            ..self
+        }
+    }
+
+    /// Set author prefixes
+    pub fn author_prefixes(self, prefixes: impl Into<Vec<String>>) -> Self {
+        Self {
+            author_prefixes: Some(prefixes.into()),
+            ..self
+        }
+    }
+
     /// Set kind
     pub fn kind(self, kind: Kind) -> Self {
         Self {
@@ -217,6 +419,131 @@ impl Filter {
         }
     }
 
+    /// Set identifier
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/33.md>
+    pub fn identifier(self, identifier: impl Into<String>) -> Self {
+        Self {
+            identifiers: Some(vec![identifier.into()]),
+            ..self
+        }
+    }
+
+    /// Set identifiers
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/33.md>
+    pub fn identifiers(self, identifiers: impl Into<Vec<String>>) -> Self {
+        Self {
+            identifiers: Some(identifiers.into()),
+            ..self
+        }
+    }
+
+    /// Set [`Coordinate`]
+    ///
+    /// Expanded in `authors`, `kinds` and `#d` (only for parameterized replaceable events).
+    pub fn coordinate(self, coordinate: &Coordinate) -> Self {
+        let filter = self.author(coordinate.pubkey).kind(coordinate.kind);
+        if coordinate.kind.is_parameterized_replaceable() {
+            filter.identifier(coordinate.identifier.clone())
+        } else {
+            filter
+        }
+    }
+
+    /// Set custom single-letter tag values
+    ///
+    /// The values are added to the previous ones of the same tag, if any.
+    /// The `e`, `p`, `t`, `r` and `d` values are stored in their dedicated fields
+    /// (the `e` and `p` values that are not valid ids or public keys are discarded).
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/12.md>
+    pub fn custom_tag<T, S>(mut self, tag: T, values: Vec<S>) -> Self
+    where
+        T: Into<SingleLetterTag>,
+        S: Into<String>,
+    {
+        let tag: SingleLetterTag = tag.into();
+        let values = values.into_iter().map(|v| v.into());
+        match (tag.character, tag.uppercase) {
+            (Alphabet::E, false) => extend_unique(
+                &mut self.events,
+                values.filter_map(|v| EventId::from_hex(v).ok()),
+            ),
+            (Alphabet::P, false) => extend_unique(
+                &mut self.pubkeys,
+                values.filter_map(|v| XOnlyPublicKey::from_str(&v).ok()),
+            ),
+            (Alphabet::T, false) => extend_unique(&mut self.hashtags, values),
+            (Alphabet::R, false) => extend_unique(&mut self.references, values),
+            (Alphabet::D, false) => extend_unique(&mut self.identifiers, values),
+            _ => {
+                let entry = self.generic_tags.entry(tag).or_default();
+                for value in values {
+                    if !entry.contains(&value) {
+                        entry.push(value);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Remove custom single-letter tag values
+    ///
+    /// The `e`, `p`, `t`, `r` and `d` values are removed from their dedicated fields.
+    pub fn remove_custom_tag<T>(mut self, tag: T) -> Self
+    where
+        T: Into<SingleLetterTag>,
+    {
+        let tag: SingleLetterTag = tag.into();
+        match (tag.character, tag.uppercase) {
+            (Alphabet::E, false) => self.events = None,
+            (Alphabet::P, false) => self.pubkeys = None,
+            (Alphabet::T, false) => self.hashtags = None,
+            (Alphabet::R, false) => self.references = None,
+            (Alphabet::D, false) => self.identifiers = None,
+            _ => {
+                self.generic_tags.remove(&tag);
+            }
+        }
+        self
+    }
+
+    /// Get all the single-letter tag queries, both from the dedicated fields and the generic ones
+    pub fn tag_values(&self) -> BTreeMap<SingleLetterTag, Vec<String>> {
+        // `generic_tags` never contains the tags with a dedicated field
+        let mut tags: BTreeMap<SingleLetterTag, Vec<String>> = BTreeMap::new();
+
+        if let Some(events) = &self.events {
+            tags.insert(
+                Alphabet::E.into(),
+                events.iter().map(|id| id.to_hex()).collect(),
+            );
+        }
+        if let Some(pubkeys) = &self.pubkeys {
+            tags.insert(
+                Alphabet::P.into(),
+                pubkeys.iter().map(|p| p.to_string()).collect(),
+            );
+        }
+        if let Some(hashtags) = &self.hashtags {
+            tags.insert(Alphabet::T.into(), hashtags.clone());
+        }
+        if let Some(references) = &self.references {
+            tags.insert(Alphabet::R.into(), references.clone());
+        }
+        if let Some(identifiers) = &self.identifiers {
+            tags.insert(Alphabet::D.into(), identifiers.clone());
+        }
+
+        for (tag, values) in self.generic_tags.iter() {
+            tags.insert(*tag, values.clone());
+        }
+
+        tags
+    }
+
     /// Set search field
     pub fn search<S>(self, value: S) -> Self
     where
@@ -251,4 +578,1014 @@ impl Filter {
             ..self
         }
     }
+
+    /// Check if the [`Event`] match the [`Filter`]
+    ///
+    /// The `ids` and the `author_prefixes` are matched as prefixes of the event id and of the author,
+    /// while `search` and `limit` are ignored. An event match the authors constraint if its author
+    /// is in `authors` or starts with any of the `author_prefixes`.
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/01.md>
+    pub fn match_event(&self, event: &Event) -> bool {
+        if let Some(ids) = &self.ids {
+            let id: String = event.id.to_hex();
+            if !ids.iter().any(|prefix| id.starts_with(prefix.as_str())) {
+                return false;
+            }
+        }
+
+        if (self.authors.is_some() || self.author_prefixes.is_some())
+            && !self.match_author(&event.pubkey)
+        {
+            return false;
+        }
+
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(&event.kind) {
+                return false;
+            }
+        }
+
+        if let Some(since) = self.since {
+            if event.created_at < since {
+                return false;
+            }
+        }
+
+        if let Some(until) = self.until {
+            if event.created_at > until {
+                return false;
+            }
+        }
+
+        let tag_values = self.tag_values();
+        if tag_values.is_empty() {
+            return true;
+        }
+
+        let tags: Vec<Vec<String>> = event.tags.iter().map(Tag::as_vec).collect();
+        tag_values.iter().all(|(name, values)| {
+            tags.iter().any(|tag| {
+                tag.len() > 1
+                    && tag[0].len() == 1
+                    && tag[0].starts_with(name.as_char())
+                    && values.contains(&tag[1])
+            })
+        })
+    }
+
+    /// Check if `author` is in `authors` or starts with any of the `author_prefixes`
+    fn match_author(&self, author: &XOnlyPublicKey) -> bool {
+        if let Some(authors) = &self.authors {
+            if authors.contains(author) {
+                return true;
+            }
+        }
+        match &self.author_prefixes {
+            Some(prefixes) => {
+                let author: String = author.to_string();
+                prefixes
+                    .iter()
+                    .any(|prefix| author.starts_with(prefix.as_str()))
+            }
+            None => false,
+        }
+    }
+
+    /// Check if all the authors matched by `other` are matched also by this [`Filter`]
+    fn covers_authors(&self, other: &Filter) -> bool {
+        if self.authors.is_none() && self.author_prefixes.is_none() {
+            return true;
+        }
+        if other.authors.is_none() && other.author_prefixes.is_none() {
+            return false;
+        }
+
+        let authors_covered: bool = other
+            .authors
+            .iter()
+            .flatten()
+            .all(|author| self.match_author(author));
+        let prefixes_covered: bool = other.author_prefixes.iter().flatten().all(|other_prefix| {
+            self.author_prefixes
+                .iter()
+                .flatten()
+                .any(|prefix| other_prefix.starts_with(prefix.as_str()))
+        });
+        authors_covered && prefixes_covered
+    }
+
+    /// Check if the [`Event`] match at least one of the [`Filter`]s
+    pub fn match_any(filters: &[Filter], event: &Event) -> bool {
+        filters.iter().any(|filter| filter.match_event(event))
+    }
+
+    /// Check if all the events matched by `other` are matched also by this [`Filter`]
+    ///
+    /// The check is conservative: `false` may be returned also for some covered filters.
+    pub fn covers(&self, other: &Filter) -> bool {
+        // The limit is applied per filter: only an equal filter is covered
+        if self.limit.is_some() || other.limit.is_some() {
+            return self == other;
+        }
+
+        if let Some(ids) = &self.ids {
+            match &other.ids {
+                Some(other_ids) => {
+                    if !other_ids
+                        .iter()
+                        .all(|id| ids.iter().any(|prefix| id.starts_with(prefix.as_str())))
+                    {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+
+        if !self.covers_authors(other) || !is_subset(&other.kinds, &self.kinds) {
+            return false;
+        }
+
+        let other_tags = other.tag_values();
+        for (tag, values) in self.tag_values().into_iter() {
+            match other_tags.get(&tag) {
+                Some(other_values) => {
+                    if !other_values.iter().all(|v| values.contains(v)) {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+
+        if let Some(since) = self.since {
+            if other.since.map_or(true, |other_since| other_since < since) {
+                return false;
+            }
+        }
+
+        if let Some(until) = self.until {
+            if other.until.map_or(true, |other_until| other_until > until) {
+                return false;
+            }
+        }
+
+        self.search.is_none() || self.search == other.search
+    }
+
+    /// Merge two [`Filter`]s in a single one, matching the same events of both
+    ///
+    /// Return `None` if the filters are not compatible (ex. they differ in more than one field).
+    pub fn merge(&self, other: &Filter) -> Option<Filter> {
+        if self == other {
+            return Some(self.clone());
+        }
+
+        if self.limit.is_some()
+            || other.limit.is_some()
+            || self.search != other.search
+            || self.since != other.since
+            || self.until != other.until
+        {
+            return None;
+        }
+
+        let mut diff: usize = [
+            self.ids != other.ids,
+            // `authors` and `author_prefixes` are a single constraint
+            self.authors != other.authors || self.author_prefixes != other.author_prefixes,
+            self.kinds != other.kinds,
+            self.events != other.events,
+            self.pubkeys != other.pubkeys,
+            self.hashtags != other.hashtags,
+            self.references != other.references,
+            self.identifiers != other.identifiers,
+        ]
+        .into_iter()
+        .filter(|d| *d)
+        .count();
+        let tags: BTreeSet<&SingleLetterTag> = self
+            .generic_tags
+            .keys()
+            .chain(other.generic_tags.keys())
+            .collect();
+        diff += tags
+            .into_iter()
+            .filter(|tag| self.generic_tags.get(tag) != other.generic_tags.get(tag))
+            .count();
+
+        // Only filters that differ in a single field can be merged
+        if diff > 1 {
+            return None;
+        }
+
+        let mut generic_tags = BTreeMap::new();
+        for (tag, values) in self.generic_tags.iter() {
+            if let Some(other_values) = other.generic_tags.get(tag) {
+                generic_tags.insert(*tag, union(values, other_values));
+            }
+        }
+
+        // No authors constraint in one of the filters means no constraint in the merged one
+        let (authors, author_prefixes) = if (self.authors.is_none()
+            && self.author_prefixes.is_none())
+            || (other.authors.is_none() && other.author_prefixes.is_none())
+        {
+            (None, None)
+        } else {
+            (
+                union_some(&self.authors, &other.authors),
+                union_some(&self.author_prefixes, &other.author_prefixes),
+            )
+        };
+
+        Some(Filter {
+            ids: union_opt(&self.ids, &other.ids),
+            authors,
+            author_prefixes,
+            kinds: union_opt(&self.kinds, &other.kinds),
+            events: union_opt(&self.events, &other.events),
+            pubkeys: union_opt(&self.pubkeys, &other.pubkeys),
+            hashtags: union_opt(&self.hashtags, &other.hashtags),
+            references: union_opt(&self.references, &other.references),
+            identifiers: union_opt(&self.identifiers, &other.identifiers),
+            search: self.search.clone(),
+            since: self.since,
+            until: self.until,
+            limit: None,
+            generic_tags,
+        })
+    }
+
+    /// Remove the redundant [`Filter`]s and merge the compatible ones
+    ///
+    /// The returned filters match the same events of the input ones.
+    pub fn compact(filters: Vec<Filter>) -> Vec<Filter> {
+        let mut compacted: Vec<Filter> = Vec::with_capacity(filters.len());
+        for mut filter in filters.into_iter() {
+            loop {
+                if compacted.iter().any(|f| f.covers(&filter)) {
+                    break;
+                }
+
+                compacted.retain(|f| !filter.covers(f));
+
+                match compacted
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, f)| f.merge(&filter).map(|merged| (i, merged)))
+                {
+                    Some((i, merged)) => {
+                        compacted.remove(i);
+                        filter = merged;
+                    }
+                    None => {
+                        compacted.push(filter);
+                        break;
+                    }
+                }
+            }
+        }
+        compacted
+    }
+
+    /// Split the [`Filter`] so that each field has at most `max_values` values
+    ///
+    /// Note: the `limit`, if any, is applied to each of the returned filters.
+    pub fn split(self, max_values: usize) -> Vec<Filter> {
+        let max_values: usize = std::cmp::max(max_values, 1);
+        let mut filters: Vec<Filter> = vec![self];
+        filters = split_field(filters, max_values, |f| &mut f.ids);
+        filters = split_authors(filters, max_values);
+        filters = split_field(filters, max_values, |f| &mut f.kinds);
+        filters = split_field(filters, max_values, |f| &mut f.events);
+        filters = split_field(filters, max_values, |f| &mut f.pubkeys);
+        filters = split_field(filters, max_values, |f| &mut f.hashtags);
+        filters = split_field(filters, max_values, |f| &mut f.references);
+        filters = split_field(filters, max_values, |f| &mut f.identifiers);
+
+        let tags: Vec<SingleLetterTag> = filters
+            .first()
+            .map(|f| f.generic_tags.keys().copied().collect())
+            .unwrap_or_default();
+        for tag in tags.into_iter() {
+            let mut splitted: Vec<Filter> = Vec::with_capacity(filters.len());
+            for filter in filters.into_iter() {
+                match filter.generic_tags.get(&tag) {
+                    Some(values) if values.len() > max_values => {
+                        for chunk in values.chunks(max_values) {
+                            let mut f = filter.clone();
+                            f.generic_tags.insert(tag, chunk.to_vec());
+                            splitted.push(f);
+                        }
+                    }
+                    _ => splitted.push(filter),
+                }
+            }
+            filters = splitted;
+        }
+
+        filters
+    }
+
+    /// Group the [`Filter`]s in batches to respect the limits of a relay
+    ///
+    /// Each batch has at most `max_filters` filters and each filter at most `max_values` values per field.
+    /// Use [`Filter::compact`] before, to reduce the number of filters.
+    pub fn batch(
+        mut filters: Vec<Filter>,
+        max_values: Option<usize>,
+        max_filters: Option<usize>,
+    ) -> Vec<Vec<Filter>> {
+        if let Some(max_values) = max_values {
+            filters = filters
+                .into_iter()
+                .flat_map(|f| f.split(max_values))
+                .collect();
+        }
+
+        match max_filters {
+            Some(max_filters) => filters
+                .chunks(std::cmp::max(max_filters, 1))
+                .map(|chunk| chunk.to_vec())
+                .collect(),
+            None if filters.is_empty() => Vec::new(),
+            None => vec![filters],
+        }
+    }
+}
+
+fn is_subset<T>(values: &Option<Vec<T>>, of: &Option<Vec<T>>) -> bool
+where
+    T: PartialEq,
+{
+    match (values, of) {
+        (_, None) => true,
+        (Some(values), Some(of)) => values.iter().all(|v| of.contains(v)),
+        (None, Some(_)) => false,
+    }
+}
+
+/// Add the values not already present, setting the field if it was `None`
+fn extend_unique<T, I>(field: &mut Option<Vec<T>>, values: I)
+where
+    T: PartialEq,
+    I: Iterator<Item = T>,
+{
+    let field: &mut Vec<T> = field.get_or_insert_with(Vec::new);
+    for value in values {
+        if !field.contains(&value) {
+            field.push(value);
+        }
+    }
+}
+
+fn union<T>(a: &[T], b: &[T]) -> Vec<T>
+where
+    T: PartialEq + Clone,
+{
+    let mut values: Vec<T> = Vec::with_capacity(a.len() + b.len());
+    for v in a.iter().chain(b.iter()) {
+        if !values.contains(v) {
+            values.push(v.clone());
+        }
+    }
+    values
+}
+
+/// `None` means no constraints, so the union with `None` is `None`
+fn union_opt<T>(a: &Option<Vec<T>>, b: &Option<Vec<T>>) -> Option<Vec<T>>
+where
+    T: PartialEq + Clone,
+{
+    match (a, b) {
+        (Some(a), Some(b)) => Some(union(a, b)),
+        _ => None,
+    }
+}
+
+/// `None` means no values, so the union with `None` is the other field
+fn union_some<T>(a: &Option<Vec<T>>, b: &Option<Vec<T>>) -> Option<Vec<T>>
+where
+    T: PartialEq + Clone,
+{
+    match (a, b) {
+        (Some(a), Some(b)) => Some(union(a, b)),
+        (Some(values), None) | (None, Some(values)) => Some(values.clone()),
+        (None, None) => None,
+    }
+}
+
+fn split_field<T>(
+    filters: Vec<Filter>,
+    max_values: usize,
+    field: fn(&mut Filter) -> &mut Option<Vec<T>>,
+) -> Vec<Filter>
+where
+    T: Clone,
+{
+    let mut splitted: Vec<Filter> = Vec::with_capacity(filters.len());
+    for mut filter in filters.into_iter() {
+        match field(&mut filter).take() {
+            Some(values) if values.len() > max_values => {
+                for chunk in values.chunks(max_values) {
+                    let mut f = filter.clone();
+                    *field(&mut f) = Some(chunk.to_vec());
+                    splitted.push(f);
+                }
+            }
+            values => {
+                *field(&mut filter) = values;
+                splitted.push(filter);
+            }
+        }
+    }
+    splitted
+}
+
+/// Split `authors` and `author_prefixes` together: both are serialized in the `authors` field
+fn split_authors(filters: Vec<Filter>, max_values: usize) -> Vec<Filter> {
+    let mut splitted: Vec<Filter> = Vec::with_capacity(filters.len());
+    for mut filter in filters.into_iter() {
+        let authors: Option<Vec<XOnlyPublicKey>> = filter.authors.take();
+        let prefixes: Option<Vec<String>> = filter.author_prefixes.take();
+        let authors_len: usize = authors.as_ref().map(|a| a.len()).unwrap_or(0);
+        let count: usize = authors_len + prefixes.as_ref().map(|p| p.len()).unwrap_or(0);
+
+        if count <= max_values {
+            filter.authors = authors;
+            filter.author_prefixes = prefixes;
+            splitted.push(filter);
+            continue;
+        }
+
+        let authors: Vec<XOnlyPublicKey> = authors.unwrap_or_default();
+        let prefixes: Vec<String> = prefixes.unwrap_or_default();
+        let mut start: usize = 0;
+        while start < count {
+            let end: usize = std::cmp::min(start + max_values, count);
+            let chunk_authors = &authors[start.min(authors_len)..end.min(authors_len)];
+            let chunk_prefixes =
+                &prefixes[start.saturating_sub(authors_len)..end.saturating_sub(authors_len)];
+            let mut f = filter.clone();
+            f.authors = (!chunk_authors.is_empty()).then(|| chunk_authors.to_vec());
+            f.author_prefixes = (!chunk_prefixes.is_empty()).then(|| chunk_prefixes.to_vec());
+            splitted.push(f);
+            start = end;
+        }
+    }
+    splitted
+}
+
+impl Serialize for Filter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(ids) = &self.ids {
+            map.serialize_entry("ids", ids)?;
+        }
+        if self.authors.is_some() || self.author_prefixes.is_some() {
+            let authors: Vec<String> = self
+                .authors
+                .iter()
+                .flatten()
+                .map(|author| author.to_string())
+                .chain(self.author_prefixes.iter().flatten().cloned())
+                .collect();
+            map.serialize_entry("authors", &authors)?;
+        }
+        if let Some(kinds) = &self.kinds {
+            map.serialize_entry("kinds", kinds)?;
+        }
+        for (tag, values) in self.tag_values().iter() {
+            map.serialize_entry(&format!("#{tag}"), values)?;
+        }
+        if let Some(search) = &self.search {
+            map.serialize_entry("search", search)?;
+        }
+        if let Some(since) = &self.since {
+            map.serialize_entry("since", since)?;
+        }
+        if let Some(until) = &self.until {
+            map.serialize_entry("until", until)?;
+        }
+        if let Some(limit) = &self.limit {
+            map.serialize_entry("limit", limit)?;
+        }
+        map.end()
+    }
+}
+
+fn deserialize_generic_tags<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<SingleLetterTag, Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct GenericTagsVisitor;
+
+    impl<'de> Visitor<'de> for GenericTagsVisitor {
+        type Value = BTreeMap<SingleLetterTag, Vec<String>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a map of single-letter tag queries")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut generic_tags = BTreeMap::new();
+            while let Some(key) = map.next_key::<String>()? {
+                match key
+                    .strip_prefix('#')
+                    .and_then(|tag| SingleLetterTag::from_str(tag).ok())
+                {
+                    Some(tag) => {
+                        generic_tags.insert(tag, map.next_value()?);
+                    }
+                    None => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+            }
+            Ok(generic_tags)
+        }
+    }
+
+    deserializer.deserialize_map(GenericTagsVisitor)
+}
+
+/// Public keys and prefixes of the `authors`
+type Authors = (Vec<XOnlyPublicKey>, Vec<String>);
+
+/// Split the `authors` in the public keys and the prefixes (shorter hex strings)
+fn deserialize_authors_field<'de, D>(deserializer: D) -> Result<Option<Authors>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct AuthorsVisitor;
+
+    impl<'de> Visitor<'de> for AuthorsVisitor {
+        type Value = Option<Authors>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a map with the authors public keys or prefixes")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut authors = None;
+            while let Some(key) = map.next_key::<String>()? {
+                if key != "authors" {
+                    map.next_value::<IgnoredAny>()?;
+                    continue;
+                }
+
+                let mut public_keys: Vec<XOnlyPublicKey> = Vec::new();
+                let mut prefixes: Vec<String> = Vec::new();
+                for value in map.next_value::<Vec<String>>()?.into_iter() {
+                    if value.len() == 64 {
+                        public_keys.push(
+                            XOnlyPublicKey::from_str(&value).map_err(serde::de::Error::custom)?,
+                        );
+                    } else if value.len() < 64 && value.chars().all(|c| c.is_ascii_hexdigit()) {
+                        prefixes.push(value);
+                    } else {
+                        return Err(serde::de::Error::custom(format!("invalid author: {value}")));
+                    }
+                }
+                authors = Some((public_keys, prefixes));
+            }
+            Ok(authors)
+        }
+    }
+
+    deserializer.deserialize_map(AuthorsVisitor)
+}
+
+fn deserialize_authors<'de, D>(deserializer: D) -> Result<Option<Vec<XOnlyPublicKey>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(
+        deserialize_authors_field(deserializer)?.and_then(|(public_keys, prefixes)| {
+            // Keep an empty `authors` list, if there aren't prefixes
+            if public_keys.is_empty() && !prefixes.is_empty() {
+                None
+            } else {
+                Some(public_keys)
+            }
+        }),
+    )
+}
+
+fn deserialize_author_prefixes<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserialize_authors_field(deserializer)?
+        .map(|(_, prefixes)| prefixes)
+        .filter(|prefixes| !prefixes.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::event::TagKind;
+    use crate::{EventBuilder, Keys};
+
+    const PUBKEY: &str = "379e863e8357163b5bce5d2688dc4f1dcc2d505222fb8d74db600f30535dfdfe";
+    const EVENT_ID: &str = "70b10f70c1318967eddf12527799411b1a9780ad9c43858f5e5fcd45486a13a5";
+
+    #[test]
+    fn test_match_event() {
+        let keys = Keys::generate();
+        let pubkey = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+        let event_id = EventId::from_hex(EVENT_ID).unwrap();
+        let event: Event = EventBuilder::new_text_note(
+            "hello",
+            &[
+                Tag::Event(event_id, None, None),
+                Tag::PubKey(pubkey, None),
+                Tag::Hashtag(String::from("nostr")),
+            ],
+        )
+        .to_event(&keys)
+        .unwrap();
+
+        assert!(Filter::new().match_event(&event));
+
+        // Ids and authors
+        assert!(Filter::new()
+            .id(&event.id.to_hex()[..8])
+            .match_event(&event));
+        assert!(!Filter::new().id(&EVENT_ID[..8]).match_event(&event));
+        assert!(Filter::new().author(keys.public_key()).match_event(&event));
+        assert!(!Filter::new().author(pubkey).match_event(&event));
+
+        // Kinds
+        assert!(Filter::new()
+            .kinds(vec![Kind::Metadata, Kind::TextNote])
+            .match_event(&event));
+        assert!(!Filter::new().kind(Kind::Metadata).match_event(&event));
+
+        // Tags
+        assert!(Filter::new().event(event_id).match_event(&event));
+        assert!(Filter::new().pubkey(pubkey).match_event(&event));
+        assert!(Filter::new().hashtag("nostr").match_event(&event));
+        assert!(Filter::new()
+            .hashtags(vec![String::from("rust"), String::from("nostr")])
+            .pubkey(pubkey)
+            .match_event(&event));
+        assert!(!Filter::new().hashtag("rust").match_event(&event));
+        assert!(!Filter::new().pubkey(keys.public_key()).match_event(&event));
+        assert!(!Filter::new().identifier("ipsum").match_event(&event));
+
+        // Since and until
+        assert!(Filter::new()
+            .since(event.created_at)
+            .until(event.created_at)
+            .match_event(&event));
+        assert!(!Filter::new()
+            .since(Timestamp::from(event.created_at.as_u64() + 1))
+            .match_event(&event));
+        assert!(!Filter::new()
+            .until(Timestamp::from(event.created_at.as_u64() - 1))
+            .match_event(&event));
+
+        // Multiple filters
+        let filters = vec![
+            Filter::new().kind(Kind::Metadata),
+            Filter::new().author(keys.public_key()).hashtag("nostr"),
+        ];
+        assert!(Filter::match_any(&filters, &event));
+        assert!(!Filter::match_any(&filters[..1], &event));
+        assert!(!Filter::match_any(&[], &event));
+    }
+
+    #[test]
+    fn test_author_prefixes() {
+        let keys = Keys::generate();
+        let event: Event = EventBuilder::new_text_note("hello", &[])
+            .to_event(&keys)
+            .unwrap();
+        let author: String = keys.public_key().to_string();
+        let other = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+
+        assert!(Filter::new()
+            .author_prefix(&author[..8])
+            .match_event(&event));
+        assert!(!Filter::new()
+            .author_prefix(&PUBKEY[..8])
+            .match_event(&event));
+        // Authors and prefixes are alternatives
+        assert!(Filter::new()
+            .author(other)
+            .author_prefix(&author[..4])
+            .match_event(&event));
+        assert!(Filter::new()
+            .author(keys.public_key())
+            .author_prefix(&PUBKEY[..4])
+            .match_event(&event));
+        assert!(!Filter::new()
+            .author(other)
+            .author_prefix(&PUBKEY[..4])
+            .match_event(&event));
+
+        // Serialized together in `authors`
+        let filter = Filter::new().author(other).author_prefix("abc");
+        let json = serde_json::to_string(&filter).unwrap();
+        assert_eq!(json, format!(r#"{{"authors":["{PUBKEY}","abc"]}}"#));
+        assert_eq!(serde_json::from_str::<Filter>(&json).unwrap(), filter);
+        let filter: Filter = serde_json::from_str(r#"{"authors":["abc"]}"#).unwrap();
+        assert_eq!(filter, Filter::new().author_prefix("abc"));
+        let filter: Filter = serde_json::from_str(r#"{"authors":[]}"#).unwrap();
+        assert_eq!(filter, Filter::new().authors(Vec::new()));
+        assert!(serde_json::from_str::<Filter>(r#"{"authors":["xyz"]}"#).is_err());
+
+        // Covers and merge
+        assert!(Filter::new()
+            .author_prefix(&PUBKEY[..4])
+            .covers(&Filter::new().author(other).author_prefix(&PUBKEY[..8])));
+        assert!(!Filter::new()
+            .author_prefix(&PUBKEY[..8])
+            .covers(&Filter::new().author_prefix(&PUBKEY[..4])));
+        assert!(!Filter::new().author_prefix("abc").covers(&Filter::new()));
+        assert_eq!(
+            Filter::new()
+                .author(other)
+                .merge(&Filter::new().author_prefix("abc")),
+            Some(Filter::new().author(other).author_prefix("abc"))
+        );
+        assert_eq!(
+            Filter::new().author_prefix("abc").merge(&Filter::new()),
+            Some(Filter::new())
+        );
+    }
+
+    #[test]
+    fn test_single_letter_tag() {
+        let tag = SingleLetterTag::from_str("L").unwrap();
+        assert_eq!(tag, SingleLetterTag::uppercase(Alphabet::L));
+        assert_eq!(tag.to_string(), "L");
+        assert_eq!(SingleLetterTag::from(Alphabet::D).to_string(), "d");
+        assert!(SingleLetterTag::from_str("dd").is_err());
+        assert!(SingleLetterTag::from_str("1").is_err());
+    }
+
+    #[test]
+    fn test_generic_tags_serde() {
+        let pubkey = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+        let filter = Filter::new()
+            .pubkey(pubkey)
+            .hashtag("nostr")
+            .custom_tag(Alphabet::G, vec!["u4pruydqqvj"])
+            .custom_tag(SingleLetterTag::uppercase(Alphabet::L), vec!["ISO-639-1"])
+            .custom_tag(Alphabet::T, vec!["nostr", "rust"]);
+
+        let json = serde_json::to_string(&filter).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r##"{{"#g":["u4pruydqqvj"],"#L":["ISO-639-1"],"#p":["{PUBKEY}"],"#t":["nostr","rust"]}}"##
+            )
+        );
+
+        let deserialized: Filter = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.pubkeys, Some(vec![pubkey]));
+        assert_eq!(
+            deserialized.hashtags,
+            Some(vec![String::from("nostr"), String::from("rust")])
+        );
+        assert_eq!(deserialized.tag_values(), filter.tag_values());
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+
+        // Dedicated fields round-trip
+        let json = format!(
+            r##"{{"ids":["70b1"],"authors":["{PUBKEY}"],"kinds":[1],"#e":["{EVENT_ID}"],"#p":["{PUBKEY}"],"#r":["wss://relay.damus.io"],"since":1,"until":2,"limit":10}}"##
+        );
+        let filter: Filter = serde_json::from_str(&json).unwrap();
+        assert!(filter.generic_tags.is_empty());
+        assert_eq!(
+            filter.events,
+            Some(vec![EventId::from_hex(EVENT_ID).unwrap()])
+        );
+        assert_eq!(serde_json::to_string(&filter).unwrap(), json);
+
+        // Not single-letter keys are ignored
+        let filter: Filter =
+            serde_json::from_str(r##"{"#dd":["a"],"other":1,"#k":["1"]}"##).unwrap();
+        assert_eq!(filter, Filter::new().custom_tag(Alphabet::K, vec!["1"]));
+    }
+
+    #[test]
+    fn test_match_generic_tags() {
+        let keys = Keys::generate();
+        let event: Event = EventBuilder::new(
+            Kind::Custom(1985),
+            "",
+            &[
+                Tag::Generic(
+                    TagKind::Custom(String::from("L")),
+                    vec![String::from("ISO-639-1")],
+                ),
+                Tag::Geohash(String::from("u4pruydqqvj")),
+            ],
+        )
+        .to_event(&keys)
+        .unwrap();
+
+        assert!(Filter::new()
+            .custom_tag(SingleLetterTag::uppercase(Alphabet::L), vec!["ISO-639-1"])
+            .custom_tag(Alphabet::G, vec!["u4pruydqqvj"])
+            .match_event(&event));
+        assert!(!Filter::new()
+            .custom_tag(Alphabet::L, vec!["ISO-639-1"])
+            .match_event(&event));
+    }
+
+    #[test]
+    fn test_merge_filters() {
+        let keys_a = Keys::generate();
+        let keys_b = Keys::generate();
+
+        let a = Filter::new()
+            .author(keys_a.public_key())
+            .kind(Kind::Metadata);
+        let b = Filter::new()
+            .author(keys_b.public_key())
+            .kind(Kind::Metadata);
+        assert_eq!(
+            a.merge(&b),
+            Some(
+                Filter::new()
+                    .authors(vec![keys_a.public_key(), keys_b.public_key()])
+                    .kind(Kind::Metadata)
+            )
+        );
+
+        // Different in two fields
+        let c = Filter::new()
+            .author(keys_b.public_key())
+            .kind(Kind::TextNote);
+        assert_eq!(a.merge(&c), None);
+
+        // Limit
+        assert_eq!(a.clone().limit(1).merge(&b.clone().limit(1)), None);
+
+        // Generic tags
+        let a = Filter::new().custom_tag(Alphabet::G, vec!["a"]);
+        let b = Filter::new().custom_tag(Alphabet::G, vec!["b"]);
+        assert_eq!(
+            a.merge(&b),
+            Some(Filter::new().custom_tag(Alphabet::G, vec!["a", "b"]))
+        );
+        let c = Filter::new()
+            .custom_tag(Alphabet::G, vec!["a"])
+            .custom_tag(Alphabet::K, vec!["1"]);
+        assert_eq!(b.merge(&c), None);
+
+        // Dedicated and generic tags
+        let a = Filter::new()
+            .hashtag("x")
+            .custom_tag(SingleLetterTag::uppercase(Alphabet::T), vec!["z"]);
+        let b = Filter::new().custom_tag(SingleLetterTag::uppercase(Alphabet::T), vec!["z"]);
+        assert_eq!(a.merge(&b), Some(b.clone()));
+        assert_eq!(b.merge(&a), Some(b));
+
+        // Same tag set with the dedicated field and the custom tag
+        let a = Filter::new()
+            .hashtag("x")
+            .custom_tag(Alphabet::T, vec!["y"]);
+        let b = Filter::new().custom_tag(Alphabet::T, vec!["y"]);
+        assert_eq!(b, Filter::new().hashtag("y"));
+        assert_eq!(
+            a.merge(&b),
+            Some(Filter::new().hashtags(vec![String::from("x"), String::from("y")]))
+        );
+    }
+
+    #[test]
+    fn test_custom_tag_dedicated_fields() {
+        let pubkey = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+        let event_id = EventId::from_hex(EVENT_ID).unwrap();
+
+        let filter = Filter::new()
+            .custom_tag(Alphabet::E, vec![EVENT_ID, "invalid"])
+            .custom_tag(Alphabet::P, vec![PUBKEY])
+            .custom_tag(Alphabet::R, vec!["wss://relay.damus.io"])
+            .custom_tag(Alphabet::D, vec!["id"]);
+        assert!(filter.generic_tags.is_empty());
+        assert_eq!(
+            filter,
+            Filter::new()
+                .event(event_id)
+                .pubkey(pubkey)
+                .reference("wss://relay.damus.io")
+                .identifier("id")
+        );
+
+        // Only invalid values: still constrained
+        let filter = Filter::new().custom_tag(Alphabet::E, vec!["invalid"]);
+        assert_eq!(filter.events, Some(Vec::new()));
+
+        let filter = filter
+            .hashtag("nostr")
+            .remove_custom_tag(Alphabet::E)
+            .remove_custom_tag(Alphabet::T);
+        assert_eq!(filter, Filter::new());
+    }
+
+    #[test]
+    fn test_compact_filters() {
+        let keys: Vec<Keys> = (0..3).map(|_| Keys::generate()).collect();
+        let mut filters: Vec<Filter> = keys
+            .iter()
+            .map(|k| Filter::new().author(k.public_key()).kind(Kind::Metadata))
+            .collect();
+        // Duplicated and redundant filters
+        filters.push(filters[0].clone());
+        filters.push(
+            Filter::new()
+                .author(keys[1].public_key())
+                .kind(Kind::Metadata)
+                .since(Timestamp::from(1000)),
+        );
+        filters.push(Filter::new().kind(Kind::TextNote).limit(10));
+
+        let compacted = Filter::compact(filters);
+        assert_eq!(
+            compacted,
+            vec![
+                Filter::new()
+                    .authors(keys.iter().map(|k| k.public_key()).collect())
+                    .kind(Kind::Metadata),
+                Filter::new().kind(Kind::TextNote).limit(10),
+            ]
+        );
+
+        // Covered by a less restrictive filter
+        let compacted = Filter::compact(vec![
+            Filter::new().kind(Kind::TextNote).hashtag("nostr"),
+            Filter::new().kinds(vec![Kind::TextNote, Kind::Reaction]),
+        ]);
+        assert_eq!(
+            compacted,
+            vec![Filter::new().kinds(vec![Kind::TextNote, Kind::Reaction])]
+        );
+    }
+
+    #[test]
+    fn test_split_filters() {
+        let authors: Vec<XOnlyPublicKey> = (0..5).map(|_| Keys::generate().public_key()).collect();
+        let filter = Filter::new().authors(authors.clone()).kinds(vec![
+            Kind::Metadata,
+            Kind::TextNote,
+            Kind::ContactList,
+        ]);
+
+        let filters = filter.clone().split(2);
+        assert_eq!(filters.len(), 6);
+        for f in filters.iter() {
+            assert!(f.authors.as_ref().unwrap().len() <= 2);
+            assert!(f.kinds.as_ref().unwrap().len() <= 2);
+        }
+        assert_eq!(filter.clone().split(5), vec![filter.clone()]);
+
+        // Authors and prefixes are serialized in the same field
+        let filter = Filter::new()
+            .authors(authors[..3].to_vec())
+            .author_prefixes(vec![String::from("ab"), String::from("cd")]);
+        let filters = filter.clone().split(2);
+        assert_eq!(filters.len(), 3);
+        for f in filters.iter() {
+            let json: serde_json::Value = serde_json::to_value(f).unwrap();
+            assert!(json["authors"].as_array().unwrap().len() <= 2);
+        }
+        assert_eq!(filters[1].authors, Some(vec![authors[2]]));
+        assert_eq!(filters[1].author_prefixes, Some(vec![String::from("ab")]));
+        assert_eq!(filters[2].authors, None);
+        assert_eq!(filter.clone().split(5), vec![filter.clone()]);
+
+        let filter = Filter::new().authors(authors.clone()).kinds(vec![
+            Kind::Metadata,
+            Kind::TextNote,
+            Kind::ContactList,
+        ]);
+
+        let batches = Filter::batch(vec![filter], Some(2), Some(4));
+        assert_eq!(
+            batches.iter().map(|b| b.len()).collect::<Vec<_>>(),
+            vec![4, 2]
+        );
+        assert!(Filter::batch(Vec::new(), None, None).is_empty());
+    }
 }
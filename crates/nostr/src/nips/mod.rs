@@ -5,12 +5,16 @@
 //!
 //! See all at <https://github.com/nostr-protocol/nips>
 
+#[cfg(all(feature = "nip03", feature = "base"))]
+pub mod nip03;
 #[cfg(feature = "nip04")]
 pub mod nip04;
 #[cfg(feature = "nip05")]
 pub mod nip05;
 #[cfg(feature = "nip06")]
 pub mod nip06;
+#[cfg(all(feature = "nip10", feature = "base"))]
+pub mod nip10;
 #[cfg(feature = "nip11")]
 pub mod nip11;
 #[cfg(feature = "nip13")]
@@ -19,5 +23,15 @@ pub mod nip13;
 pub mod nip19;
 #[cfg(feature = "nip26")]
 pub mod nip26;
+#[cfg(all(feature = "nip27", feature = "base"))]
+pub mod nip27;
+#[cfg(feature = "nip44")]
+pub mod nip44;
+#[cfg(all(feature = "nip46", feature = "base"))]
+pub mod nip46;
+#[cfg(feature = "nip49")]
+pub mod nip49;
+#[cfg(all(feature = "nip59", feature = "base"))]
+pub mod nip59;
 #[cfg(all(feature = "nip65", feature = "base"))]
 pub mod nip65;
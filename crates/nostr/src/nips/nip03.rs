@@ -0,0 +1,714 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP03
+//!
+//! Parsing, verification and upgrade of OpenTimestamps proofs.
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/03.md>
+
+use base64::engine::{general_purpose, Engine};
+use bitcoin::BlockHeader;
+use bitcoin_hashes::ripemd160::Hash as Ripemd160Hash;
+use bitcoin_hashes::sha1::Hash as Sha1Hash;
+use bitcoin_hashes::sha256::Hash as Sha256Hash;
+use bitcoin_hashes::Hash;
+
+use crate::event::builder::EventBuilder;
+use crate::event::tag::TagKind;
+use crate::util::bytes::{write_varbytes, write_varint, Reader, ReaderError};
+use crate::{Event, EventId, Kind, Tag};
+
+const HEADER_MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+const MAJOR_VERSION: u64 = 1;
+const MAX_MSG_LENGTH: usize = 4096;
+const MAX_RECURSION_DEPTH: usize = 256;
+
+const TAG_ATTESTATION: u8 = 0x00;
+const TAG_FORK: u8 = 0xff;
+const TAG_SHA1: u8 = 0x02;
+const TAG_RIPEMD160: u8 = 0x03;
+const TAG_SHA256: u8 = 0x08;
+const TAG_APPEND: u8 = 0xf0;
+const TAG_PREPEND: u8 = 0xf1;
+const TAG_REVERSE: u8 = 0xf2;
+const TAG_HEXLIFY: u8 = 0xf3;
+
+const ATTESTATION_BITCOIN: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+const ATTESTATION_PENDING: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+
+/// NIP03 error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Invalid header magic
+    #[error("invalid OpenTimestamps header")]
+    InvalidHeader,
+    /// Unsupported major version
+    #[error("unsupported OpenTimestamps version: {0}")]
+    UnsupportedVersion(u64),
+    /// Unsupported operation
+    #[error("unsupported operation: {0:#04x}")]
+    UnsupportedOp(u8),
+    /// Unexpected end of data
+    #[error("unexpected end of data")]
+    UnexpectedEof,
+    /// Trailing bytes
+    #[error("trailing bytes")]
+    TrailingBytes,
+    /// Invalid data
+    #[error("invalid data")]
+    InvalidData,
+    /// Recursion limit reached
+    #[error("recursion limit reached")]
+    RecursionLimit,
+    /// The event has no OpenTimestamps attestation
+    #[error("no OpenTimestamps attestation")]
+    NoAttestation,
+    /// The attestation doesn't reference any event
+    #[error("attested event not found")]
+    AttestedEventNotFound,
+    /// The proof doesn't commit to the event id
+    #[error("the proof doesn't commit to the event id")]
+    DigestMismatch,
+    /// The block header required to verify the attestation is missing
+    #[error("missing block header at height {0}")]
+    MissingBlockHeader(u32),
+    /// The attestation doesn't match the merkle root of the block header
+    #[error("attestation doesn't match block header at height {0}")]
+    InvalidAttestation(u32),
+    /// Base64 decode error
+    #[error(transparent)]
+    Base64(#[from] base64::DecodeError),
+    /// OpenTimestamps calendar error
+    #[error(transparent)]
+    Calendar(#[from] nostr_ots::Error),
+}
+
+/// Commitment operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OtsOp {
+    /// SHA1
+    Sha1,
+    /// RIPEMD160
+    Ripemd160,
+    /// SHA256
+    Sha256,
+    /// Append data
+    Append(Vec<u8>),
+    /// Prepend data
+    Prepend(Vec<u8>),
+    /// Reverse the message
+    Reverse,
+    /// Hex encode the message
+    Hexlify,
+}
+
+impl OtsOp {
+    fn tag(&self) -> u8 {
+        match self {
+            Self::Sha1 => TAG_SHA1,
+            Self::Ripemd160 => TAG_RIPEMD160,
+            Self::Sha256 => TAG_SHA256,
+            Self::Append(_) => TAG_APPEND,
+            Self::Prepend(_) => TAG_PREPEND,
+            Self::Reverse => TAG_REVERSE,
+            Self::Hexlify => TAG_HEXLIFY,
+        }
+    }
+
+    /// Apply the operation to a message
+    pub fn apply(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1Hash::hash(msg).into_inner().to_vec(),
+            Self::Ripemd160 => Ripemd160Hash::hash(msg).into_inner().to_vec(),
+            Self::Sha256 => Sha256Hash::hash(msg).into_inner().to_vec(),
+            Self::Append(data) => [msg, data].concat(),
+            Self::Prepend(data) => [data, msg].concat(),
+            Self::Reverse => msg.iter().rev().copied().collect(),
+            Self::Hexlify => msg
+                .iter()
+                .flat_map(|b| format!("{b:02x}").into_bytes())
+                .collect(),
+        }
+    }
+}
+
+/// Time attestation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OtsAttestation {
+    /// Pending confirmation by the calendar at this URI
+    Pending(String),
+    /// Committed to the merkle root of the Bitcoin block at this height
+    Bitcoin(u32),
+    /// Unknown attestation
+    Unknown {
+        /// Attestation tag
+        tag: [u8; 8],
+        /// Raw payload
+        payload: Vec<u8>,
+    },
+}
+
+/// Commitment to a message and the attestations and operations that depend on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtsTimestamp {
+    /// Message
+    pub msg: Vec<u8>,
+    /// Attestations of the message
+    pub attestations: Vec<OtsAttestation>,
+    /// Operations applied to the message, with the resulting commitments
+    pub ops: Vec<(OtsOp, OtsTimestamp)>,
+}
+
+impl OtsTimestamp {
+    /// Deserialize [`OtsTimestamp`] of `msg`, as returned by calendars
+    pub fn from_bytes(msg: Vec<u8>, bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader: Reader<Error> = Reader::new(bytes);
+        let timestamp = Self::read(&mut reader, msg, 0)?;
+        reader.finish()?;
+        Ok(timestamp)
+    }
+
+    /// Serialize [`OtsTimestamp`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf: Vec<u8> = Vec::new();
+        self.write(&mut buf);
+        buf
+    }
+
+    fn read(reader: &mut Reader<Error>, msg: Vec<u8>, depth: usize) -> Result<Self, Error> {
+        if depth > MAX_RECURSION_DEPTH {
+            return Err(Error::RecursionLimit);
+        }
+
+        let mut timestamp = Self {
+            msg,
+            attestations: Vec::new(),
+            ops: Vec::new(),
+        };
+
+        let mut tag: u8 = reader.read_u8()?;
+        while tag == TAG_FORK {
+            let current: u8 = reader.read_u8()?;
+            timestamp.read_tag(reader, current, depth)?;
+            tag = reader.read_u8()?;
+        }
+        timestamp.read_tag(reader, tag, depth)?;
+
+        Ok(timestamp)
+    }
+
+    fn read_tag(&mut self, reader: &mut Reader<Error>, tag: u8, depth: usize) -> Result<(), Error> {
+        if tag == TAG_ATTESTATION {
+            let attestation_tag: [u8; 8] = reader
+                .read(8)?
+                .try_into()
+                .map_err(|_| Error::UnexpectedEof)?;
+            let payload: &[u8] = read_varbytes(reader)?;
+            let mut payload_reader: Reader<Error> = Reader::new(payload);
+            let attestation = match attestation_tag {
+                ATTESTATION_BITCOIN => {
+                    let height = u32::try_from(payload_reader.read_varint()?)
+                        .map_err(|_| Error::InvalidData)?;
+                    payload_reader.finish()?;
+                    OtsAttestation::Bitcoin(height)
+                }
+                ATTESTATION_PENDING => {
+                    let uri: &[u8] = read_varbytes(&mut payload_reader)?;
+                    payload_reader.finish()?;
+                    OtsAttestation::Pending(
+                        String::from_utf8(uri.to_vec()).map_err(|_| Error::InvalidData)?,
+                    )
+                }
+                tag => OtsAttestation::Unknown {
+                    tag,
+                    payload: payload.to_vec(),
+                },
+            };
+            self.attestations.push(attestation);
+        } else {
+            let op: OtsOp = match tag {
+                TAG_SHA1 => OtsOp::Sha1,
+                TAG_RIPEMD160 => OtsOp::Ripemd160,
+                TAG_SHA256 => OtsOp::Sha256,
+                TAG_APPEND => OtsOp::Append(read_varbytes(reader)?.to_vec()),
+                TAG_PREPEND => OtsOp::Prepend(read_varbytes(reader)?.to_vec()),
+                TAG_REVERSE => OtsOp::Reverse,
+                TAG_HEXLIFY => OtsOp::Hexlify,
+                tag => return Err(Error::UnsupportedOp(tag)),
+            };
+            let result: Vec<u8> = op.apply(&self.msg);
+            if result.len() > MAX_MSG_LENGTH {
+                return Err(Error::InvalidData);
+            }
+            let timestamp = Self::read(reader, result, depth + 1)?;
+            self.ops.push((op, timestamp));
+        }
+        Ok(())
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        let items: usize = self.attestations.len() + self.ops.len();
+        let mut index: usize = 0;
+
+        for attestation in self.attestations.iter() {
+            index += 1;
+            if index < items {
+                buf.push(TAG_FORK);
+            }
+            buf.push(TAG_ATTESTATION);
+            let payload: Vec<u8> = match attestation {
+                OtsAttestation::Bitcoin(height) => {
+                    buf.extend_from_slice(&ATTESTATION_BITCOIN);
+                    let mut payload = Vec::new();
+                    write_varint(&mut payload, *height as u64);
+                    payload
+                }
+                OtsAttestation::Pending(uri) => {
+                    buf.extend_from_slice(&ATTESTATION_PENDING);
+                    let mut payload = Vec::new();
+                    write_varbytes(&mut payload, uri.as_bytes());
+                    payload
+                }
+                OtsAttestation::Unknown { tag, payload } => {
+                    buf.extend_from_slice(tag);
+                    payload.clone()
+                }
+            };
+            write_varbytes(buf, &payload);
+        }
+
+        for (op, timestamp) in self.ops.iter() {
+            index += 1;
+            if index < items {
+                buf.push(TAG_FORK);
+            }
+            buf.push(op.tag());
+            if let OtsOp::Append(data) | OtsOp::Prepend(data) = op {
+                write_varbytes(buf, data);
+            }
+            timestamp.write(buf);
+        }
+    }
+
+    /// Get all the attestations, with the commitment they attest
+    pub fn all_attestations(&self) -> Vec<(&[u8], &OtsAttestation)> {
+        let mut attestations: Vec<(&[u8], &OtsAttestation)> = self
+            .attestations
+            .iter()
+            .map(|a| (self.msg.as_slice(), a))
+            .collect();
+        for (_, timestamp) in self.ops.iter() {
+            attestations.extend(timestamp.all_attestations());
+        }
+        attestations
+    }
+
+    /// Merge `other` in the commitment with the same message
+    ///
+    /// Pending attestations of the upgraded commitment are removed.
+    /// Return `false` if no commitment matches the message of `other`.
+    pub fn merge(&mut self, other: OtsTimestamp) -> bool {
+        if self.msg == other.msg {
+            self.attestations
+                .retain(|a| !matches!(a, OtsAttestation::Pending(_)));
+            for attestation in other.attestations.into_iter() {
+                if !self.attestations.contains(&attestation) {
+                    self.attestations.push(attestation);
+                }
+            }
+            for (op, timestamp) in other.ops.into_iter() {
+                match self.ops.iter_mut().find(|(o, _)| *o == op) {
+                    Some((_, t)) => {
+                        t.merge(timestamp);
+                    }
+                    None => self.ops.push((op, timestamp)),
+                }
+            }
+            return true;
+        }
+
+        match self.ops.iter_mut().find(|(_, t)| t.contains(&other.msg)) {
+            Some((_, timestamp)) => timestamp.merge(other),
+            None => false,
+        }
+    }
+
+    fn contains(&self, msg: &[u8]) -> bool {
+        self.msg == msg || self.ops.iter().any(|(_, t)| t.contains(msg))
+    }
+
+    /// Verify the attestations against the block headers supplied by `block_header`
+    ///
+    /// Return the lowest verified Bitcoin block height or, if there are only calendar attestations,
+    /// the calendars to query to upgrade the proof.
+    pub fn verify<F>(&self, block_header: F) -> Result<OtsStatus, Error>
+    where
+        F: Fn(u32) -> Option<BlockHeader>,
+    {
+        let mut bitcoin: Vec<(u32, &[u8])> = Vec::new();
+        let mut pending: Vec<String> = Vec::new();
+        for (msg, attestation) in self.all_attestations().into_iter() {
+            match attestation {
+                OtsAttestation::Bitcoin(height) => bitcoin.push((*height, msg)),
+                OtsAttestation::Pending(uri) => pending.push(uri.clone()),
+                OtsAttestation::Unknown { .. } => (),
+            }
+        }
+        bitcoin.sort();
+
+        let mut missing: Option<u32> = None;
+        for (height, msg) in bitcoin.into_iter() {
+            match block_header(height) {
+                Some(header) => {
+                    if header.merkle_root.into_inner()[..] == *msg {
+                        return Ok(OtsStatus::Confirmed(height));
+                    } else {
+                        return Err(Error::InvalidAttestation(height));
+                    }
+                }
+                None => {
+                    missing.get_or_insert(height);
+                }
+            }
+        }
+
+        match missing {
+            Some(height) => Err(Error::MissingBlockHeader(height)),
+            None if pending.is_empty() => Err(Error::NoAttestation),
+            None => Ok(OtsStatus::Pending(pending)),
+        }
+    }
+}
+
+/// Status of an OpenTimestamps proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OtsStatus {
+    /// Waiting to be committed to a Bitcoin block by these calendars
+    Pending(Vec<String>),
+    /// Committed to the Bitcoin block at this height
+    Confirmed(u32),
+}
+
+/// OpenTimestamps proof of a SHA256 digest (`.ots` file)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachedTimestamp {
+    /// Timestamp of the digest
+    pub timestamp: OtsTimestamp,
+}
+
+impl DetachedTimestamp {
+    /// Deserialize [`DetachedTimestamp`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader: Reader<Error> = Reader::new(bytes);
+        if reader.read(HEADER_MAGIC.len())? != HEADER_MAGIC {
+            return Err(Error::InvalidHeader);
+        }
+        let version: u64 = reader.read_varint()?;
+        if version != MAJOR_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let tag: u8 = reader.read_u8()?;
+        if tag != TAG_SHA256 {
+            return Err(Error::UnsupportedOp(tag));
+        }
+        let digest: Vec<u8> = reader.read(32)?.to_vec();
+        let timestamp = OtsTimestamp::read(&mut reader, digest, 0)?;
+        reader.finish()?;
+        Ok(Self { timestamp })
+    }
+
+    /// Serialize [`DetachedTimestamp`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf: Vec<u8> = HEADER_MAGIC.to_vec();
+        write_varint(&mut buf, MAJOR_VERSION);
+        buf.push(TAG_SHA256);
+        buf.extend_from_slice(&self.timestamp.msg);
+        self.timestamp.write(&mut buf);
+        buf
+    }
+
+    /// Deserialize [`DetachedTimestamp`] from base64, as stored in events
+    pub fn from_base64<S>(data: S) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        Self::from_bytes(&general_purpose::STANDARD.decode(data.as_ref())?)
+    }
+
+    /// Serialize [`DetachedTimestamp`] as base64
+    pub fn to_base64(&self) -> String {
+        general_purpose::STANDARD.encode(self.to_bytes())
+    }
+
+    /// Get timestamped digest
+    pub fn digest(&self) -> &[u8] {
+        &self.timestamp.msg
+    }
+
+    /// Get the pending attestations, as `(calendar URI, commitment)`
+    ///
+    /// Upgraded timestamps can be requested to `<calendar URI>/timestamp/<hex commitment>`
+    /// and merged with [`DetachedTimestamp::upgrade`].
+    pub fn pending_attestations(&self) -> Vec<(String, Vec<u8>)> {
+        self.timestamp
+            .all_attestations()
+            .into_iter()
+            .filter_map(|(msg, attestation)| match attestation {
+                OtsAttestation::Pending(uri) => Some((uri.clone(), msg.to_vec())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Upgrade a pending commitment with the timestamp returned by the calendar
+    pub fn upgrade(&mut self, commitment: &[u8], timestamp: &[u8]) -> Result<bool, Error> {
+        let timestamp = OtsTimestamp::from_bytes(commitment.to_vec(), timestamp)?;
+        Ok(self.timestamp.merge(timestamp))
+    }
+}
+
+/// Timestamp [`EventId`] using the OpenTimestamps calendars
+pub fn timestamp_event(event_id: EventId) -> Result<DetachedTimestamp, Error> {
+    let ots: String = nostr_ots::timestamp_event(&event_id.to_hex())?;
+    DetachedTimestamp::from_base64(ots)
+}
+
+impl Event {
+    /// Get the attested [`EventId`] and its OpenTimestamps proof
+    ///
+    /// For [`Kind::OpenTimestamps`] events the proof is the content and the attested event is
+    /// referenced by the `e` tag, otherwise the proof is the `ots` field of the event itself.
+    pub fn ots_attestation(&self) -> Result<(EventId, DetachedTimestamp), Error> {
+        if self.kind == Kind::OpenTimestamps {
+            let event_id: EventId = self
+                .tags
+                .iter()
+                .find_map(|tag| match tag {
+                    Tag::Event(event_id, ..) => Some(*event_id),
+                    _ => None,
+                })
+                .ok_or(Error::AttestedEventNotFound)?;
+            Ok((event_id, DetachedTimestamp::from_base64(&self.content)?))
+        } else {
+            let ots: &String = self.ots.as_ref().ok_or(Error::NoAttestation)?;
+            Ok((self.id, DetachedTimestamp::from_base64(ots)?))
+        }
+    }
+
+    /// Verify the OpenTimestamps proof of the event
+    ///
+    /// `block_header` supplies the Bitcoin block header at the requested height, if known.
+    pub fn verify_ots<F>(&self, block_header: F) -> Result<OtsStatus, Error>
+    where
+        F: Fn(u32) -> Option<BlockHeader>,
+    {
+        let (event_id, ots) = self.ots_attestation()?;
+        if ots.digest() != event_id.as_bytes() {
+            return Err(Error::DigestMismatch);
+        }
+        ots.timestamp.verify(block_header)
+    }
+}
+
+impl EventBuilder {
+    /// OpenTimestamps attestation of an event
+    ///
+    /// <https://github.com/nostr-protocol/nips/blob/master/03.md>
+    pub fn opentimestamps(
+        event: &Event,
+        relay_url: Option<String>,
+        ots: &DetachedTimestamp,
+    ) -> Self {
+        Self::new(
+            Kind::OpenTimestamps,
+            ots.to_base64(),
+            &[
+                Tag::Event(event.id, relay_url, None),
+                Tag::Generic(
+                    TagKind::Custom(String::from("k")),
+                    vec![event.kind.as_u64().to_string()],
+                ),
+            ],
+        )
+    }
+}
+
+impl ReaderError for Error {
+    fn unexpected_eof() -> Self {
+        Self::UnexpectedEof
+    }
+
+    fn varint_overflow() -> Self {
+        Self::InvalidData
+    }
+
+    fn trailing_bytes() -> Self {
+        Self::TrailingBytes
+    }
+}
+
+fn read_varbytes<'a>(reader: &mut Reader<'a, Error>) -> Result<&'a [u8], Error> {
+    let data: &[u8] = reader.read_varbytes()?;
+    if data.len() > MAX_MSG_LENGTH {
+        return Err(Error::InvalidData);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hash_types::{BlockHash, TxMerkleNode};
+
+    use super::*;
+    use crate::{EventBuilder, Keys};
+
+    const CALENDAR: &str = "https://alice.btc.calendar.opentimestamps.org";
+    const HEIGHT: u32 = 800_000;
+
+    fn block_header(merkle_root: &[u8]) -> BlockHeader {
+        BlockHeader {
+            version: 2,
+            prev_blockhash: BlockHash::all_zeros(),
+            merkle_root: TxMerkleNode::from_slice(merkle_root).unwrap(),
+            time: 0,
+            bits: 0,
+            nonce: 0,
+        }
+    }
+
+    /// `digest -> append(nonce) -> sha256 -> [pending, prepend(data) -> sha256 -> bitcoin]`
+    fn proof(digest: &[u8]) -> (DetachedTimestamp, Vec<u8>) {
+        let mut buf: Vec<u8> = HEADER_MAGIC.to_vec();
+        buf.extend([0x01, TAG_SHA256]);
+        buf.extend_from_slice(digest);
+        buf.extend([TAG_APPEND, 0x04, 0xde, 0xad, 0xbe, 0xef]);
+        buf.push(TAG_SHA256);
+        // Fork: pending attestation
+        buf.extend([TAG_FORK, TAG_ATTESTATION]);
+        buf.extend_from_slice(&ATTESTATION_PENDING);
+        buf.push(CALENDAR.len() as u8 + 1);
+        buf.push(CALENDAR.len() as u8);
+        buf.extend_from_slice(CALENDAR.as_bytes());
+        // Bitcoin attestation
+        buf.extend([TAG_PREPEND, 0x02, 0xca, 0xfe, TAG_SHA256, TAG_ATTESTATION]);
+        buf.extend_from_slice(&ATTESTATION_BITCOIN);
+        buf.extend([0x03, 0x80, 0xea, 0x30]);
+
+        let msg = OtsOp::Sha256.apply(&OtsOp::Append(vec![0xde, 0xad, 0xbe, 0xef]).apply(digest));
+        let merkle_root = OtsOp::Sha256.apply(&OtsOp::Prepend(vec![0xca, 0xfe]).apply(&msg));
+
+        let ots = DetachedTimestamp::from_bytes(&buf).unwrap();
+        assert_eq!(ots.to_bytes(), buf);
+        (ots, merkle_root)
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        let digest = Sha256Hash::hash(b"nostr");
+        let (ots, merkle_root) = proof(digest.as_ref());
+
+        assert_eq!(ots.digest(), digest.as_ref());
+        assert_eq!(ots.pending_attestations().len(), 1);
+        assert_eq!(ots.pending_attestations()[0].0, CALENDAR);
+
+        let status = ots
+            .timestamp
+            .verify(|height| (height == HEIGHT).then(|| block_header(&merkle_root)))
+            .unwrap();
+        assert_eq!(status, OtsStatus::Confirmed(HEIGHT));
+
+        assert!(matches!(
+            ots.timestamp.verify(|_| None),
+            Err(Error::MissingBlockHeader(HEIGHT))
+        ));
+        assert!(matches!(
+            ots.timestamp.verify(|_| Some(block_header(&[0; 32]))),
+            Err(Error::InvalidAttestation(HEIGHT))
+        ));
+
+        assert!(matches!(
+            DetachedTimestamp::from_bytes(&ots.to_bytes()[1..]),
+            Err(Error::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_upgrade_pending() {
+        let digest = Sha256Hash::hash(b"nostr");
+        let mut buf: Vec<u8> = HEADER_MAGIC.to_vec();
+        buf.extend([0x01, TAG_SHA256]);
+        buf.extend_from_slice(digest.as_ref());
+        buf.push(TAG_ATTESTATION);
+        buf.extend_from_slice(&ATTESTATION_PENDING);
+        buf.push(CALENDAR.len() as u8 + 1);
+        buf.push(CALENDAR.len() as u8);
+        buf.extend_from_slice(CALENDAR.as_bytes());
+
+        let mut ots = DetachedTimestamp::from_bytes(&buf).unwrap();
+        assert_eq!(
+            ots.timestamp.verify(|_| None).unwrap(),
+            OtsStatus::Pending(vec![String::from(CALENDAR)])
+        );
+
+        // Calendar response: sha256 -> bitcoin attestation
+        let mut response: Vec<u8> = vec![TAG_SHA256, TAG_ATTESTATION];
+        response.extend_from_slice(&ATTESTATION_BITCOIN);
+        response.extend([0x03, 0x80, 0xea, 0x30]);
+        let (calendar, commitment) = ots.pending_attestations().remove(0);
+        assert_eq!(calendar, CALENDAR);
+        assert!(ots.upgrade(&commitment, &response).unwrap());
+        assert!(!ots.upgrade(&[0; 32], &response).unwrap());
+        assert!(ots.pending_attestations().is_empty());
+
+        let merkle_root = OtsOp::Sha256.apply(digest.as_ref());
+        assert_eq!(
+            ots.timestamp
+                .verify(|_| Some(block_header(&merkle_root)))
+                .unwrap(),
+            OtsStatus::Confirmed(HEIGHT)
+        );
+        assert_eq!(DetachedTimestamp::from_bytes(&ots.to_bytes()).unwrap(), ots);
+    }
+
+    #[test]
+    fn test_event_ots() {
+        let keys = Keys::generate();
+        let mut event: Event = EventBuilder::new_text_note("Hello", &[])
+            .to_event(&keys)
+            .unwrap();
+        assert!(matches!(
+            event.verify_ots(|_| None),
+            Err(Error::NoAttestation)
+        ));
+
+        let (ots, merkle_root) = proof(event.id.as_bytes());
+        event.ots = Some(ots.to_base64());
+        assert_eq!(
+            event
+                .verify_ots(|_| Some(block_header(&merkle_root)))
+                .unwrap(),
+            OtsStatus::Confirmed(HEIGHT)
+        );
+
+        let attestation: Event =
+            EventBuilder::opentimestamps(&event, Some(String::from("wss://relay.damus.io")), &ots)
+                .to_event(&keys)
+                .unwrap();
+        assert_eq!(attestation.kind, Kind::OpenTimestamps);
+        assert_eq!(attestation.ots_attestation().unwrap(), (event.id, ots));
+        assert_eq!(
+            attestation
+                .verify_ots(|_| Some(block_header(&merkle_root)))
+                .unwrap(),
+            OtsStatus::Confirmed(HEIGHT)
+        );
+
+        let (other, _) = proof(&[0; 32]);
+        event.ots = Some(other.to_base64());
+        assert!(matches!(
+            event.verify_ots(|_| None),
+            Err(Error::DigestMismatch)
+        ));
+    }
+}
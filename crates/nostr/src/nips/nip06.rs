@@ -5,15 +5,17 @@
 //!
 //! <https://github.com/nostr-protocol/nips/blob/master/06.md>
 
+use std::fmt;
 use std::str::FromStr;
 
-use bip39::Mnemonic;
+use bip39::{Language, Mnemonic};
 use bitcoin::util::bip32::{DerivationPath, ExtendedPrivKey};
 use bitcoin::Network;
 use bitcoin_hashes::hmac::{Hmac, HmacEngine};
 use bitcoin_hashes::{sha512, Hash, HashEngine};
 use secp256k1::rand::rngs::OsRng;
 use secp256k1::rand::RngCore;
+use zeroize::Zeroizing;
 
 use crate::{Keys, SECP256K1};
 
@@ -28,49 +30,174 @@ pub enum Error {
     BIP39(#[from] bip39::Error),
 }
 
-#[allow(missing_docs)]
+/// Derive keys from BIP-39 mnemonics
+///
+/// The language of the mnemonic is detected from its words.
 pub trait FromMnemonic: Sized {
+    /// Error
     type Err;
+
+    /// Derive the first account (`m/44'/1237'/0'/0/0`)
     fn from_mnemonic<S>(mnemonic: S, passphrase: Option<S>) -> Result<Self, Self::Err>
+    where
+        S: Into<String>,
+    {
+        Self::from_mnemonic_with_account(mnemonic, passphrase, 0)
+    }
+
+    /// Derive the account `m/44'/1237'/<account>'/0/0`
+    fn from_mnemonic_with_account<S>(
+        mnemonic: S,
+        passphrase: Option<S>,
+        account: u32,
+    ) -> Result<Self, Self::Err>
     where
         S: Into<String>;
 }
 
-#[allow(missing_docs)]
+/// Generate BIP-39 mnemonics
 pub trait GenerateMnemonic {
+    /// Error
     type Err;
-    fn generate_mnemonic(word_count: usize) -> Result<Mnemonic, Self::Err>;
+
+    /// Generate new English `mnemonic`
+    fn generate_mnemonic(word_count: usize) -> Result<Mnemonic, Self::Err> {
+        Self::generate_mnemonic_in(Language::English, word_count)
+    }
+
+    /// Generate new `mnemonic` in `language`
+    fn generate_mnemonic_in(language: Language, word_count: usize) -> Result<Mnemonic, Self::Err>;
+}
+
+/// Account derived from a BIP-39 mnemonic
+///
+/// Keeps the mnemonic the [`Keys`] came from: the [`Keys`] alone never hold it.
+#[derive(Clone)]
+pub struct MnemonicAccount {
+    mnemonic: Mnemonic,
+    account: u32,
+    keys: Keys,
+}
+
+impl fmt::Debug for MnemonicAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MnemonicAccount")
+            .field("account", &self.account)
+            .field("keys", &self.keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MnemonicAccount {
+    /// Derive the account `m/44'/1237'/<account>'/0/0`
+    pub fn derive<S>(mnemonic: S, passphrase: Option<S>, account: u32) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let mnemonic: Mnemonic = parse_mnemonic(mnemonic)?;
+        let keys: Keys = derive(&root_key(&mnemonic, passphrase)?, account)?;
+        Ok(Self {
+            mnemonic,
+            account,
+            keys,
+        })
+    }
+
+    /// Derive the first `count` accounts
+    ///
+    /// The seed derivation is the expensive part: it's done once for all the accounts.
+    pub fn derive_accounts<S>(
+        mnemonic: S,
+        passphrase: Option<S>,
+        count: u32,
+    ) -> Result<Vec<Self>, Error>
+    where
+        S: Into<String>,
+    {
+        let mnemonic: Mnemonic = parse_mnemonic(mnemonic)?;
+        let root_key: ExtendedPrivKey = root_key(&mnemonic, passphrase)?;
+        (0..count)
+            .map(|account| {
+                Ok(Self {
+                    mnemonic: mnemonic.clone(),
+                    account,
+                    keys: derive(&root_key, account)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Get the mnemonic
+    pub fn mnemonic(&self) -> &Mnemonic {
+        &self.mnemonic
+    }
+
+    /// Get the account index
+    pub fn account(&self) -> u32 {
+        self.account
+    }
+
+    /// Get the [`Keys`] of the account
+    pub fn keys(&self) -> &Keys {
+        &self.keys
+    }
+}
+
+fn parse_mnemonic<S>(mnemonic: S) -> Result<Mnemonic, Error>
+where
+    S: Into<String>,
+{
+    Ok(Mnemonic::from_str(&Zeroizing::new(mnemonic.into()))?)
+}
+
+/// Derive the root key from the seed of the `mnemonic`
+fn root_key<S>(mnemonic: &Mnemonic, passphrase: Option<S>) -> Result<ExtendedPrivKey, Error>
+where
+    S: Into<String>,
+{
+    let passphrase: Zeroizing<String> =
+        Zeroizing::new(passphrase.map(|p| p.into()).unwrap_or_default());
+    let seed = Zeroizing::new(mnemonic.to_seed(passphrase.as_str()));
+    Ok(ExtendedPrivKey::new_master(
+        Network::Bitcoin,
+        seed.as_ref(),
+    )?)
+}
+
+/// Derive the keys of the account `m/44'/1237'/<account>'/0/0`
+fn derive(root_key: &ExtendedPrivKey, account: u32) -> Result<Keys, Error> {
+    let path = DerivationPath::from_str(&format!("m/44'/1237'/{account}'/0/0"))?;
+    let child_xprv = root_key.derive_priv(SECP256K1, &path)?;
+    Ok(Keys::new(child_xprv.private_key))
 }
 
 impl FromMnemonic for Keys {
     type Err = Error;
 
-    /// Derive keys from BIP-39 mnemonics (ENGLISH wordlist).
-    fn from_mnemonic<S>(mnemonic: S, passphrase: Option<S>) -> Result<Self, Self::Err>
+    fn from_mnemonic_with_account<S>(
+        mnemonic: S,
+        passphrase: Option<S>,
+        account: u32,
+    ) -> Result<Self, Self::Err>
     where
         S: Into<String>,
     {
-        let mnemonic = Mnemonic::from_str(&mnemonic.into())?;
-        let seed = mnemonic.to_seed(passphrase.map(|p| p.into()).unwrap_or_default());
-        let root_key = ExtendedPrivKey::new_master(Network::Bitcoin, &seed)?;
-        let path = DerivationPath::from_str("m/44'/1237'/0'/0/0")?;
-        let child_xprv = root_key.derive_priv(SECP256K1, &path)?;
-        Ok(Self::new(child_xprv.private_key))
+        let mnemonic: Mnemonic = parse_mnemonic(mnemonic)?;
+        derive(&root_key(&mnemonic, passphrase)?, account)
     }
 }
 
 impl GenerateMnemonic for Keys {
     type Err = Error;
 
-    /// Generate new `mnemonic`
-    fn generate_mnemonic(word_count: usize) -> Result<Mnemonic, Self::Err> {
+    fn generate_mnemonic_in(language: Language, word_count: usize) -> Result<Mnemonic, Self::Err> {
         let mut h = HmacEngine::<sha512::Hash>::new(b"nostr");
         let mut os_random = [0u8; 32];
         OsRng.fill_bytes(&mut os_random);
         h.input(&os_random);
         let entropy: [u8; 64] = Hmac::from_engine(h).into_inner();
         let len: usize = word_count * 4 / 3;
-        Ok(Mnemonic::from_entropy(&entropy[0..len])?)
+        Ok(Mnemonic::from_entropy_in(language, &entropy[0..len])?)
     }
 }
 
@@ -96,4 +223,70 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_nip06_vectors() -> Result<()> {
+        let keys = Keys::from_mnemonic(
+            "leader monkey parrot ring guide accident before fence cannon height naive bean",
+            None,
+        )?;
+        assert_eq!(
+            keys.secret_key()?.display_secret().to_string(),
+            "7f7ff03d123792d6ac594bfa67bf6d0c0ab55b6b1fdb6249303fe861f1ccba9a"
+        );
+
+        let keys = Keys::from_mnemonic("what bleak badge arrange retreat wolf trade produce cricket blur garlic valid proud rude strong choose busy staff weather area salt hollow arm fade", None)?;
+        assert_eq!(
+            keys.secret_key()?.display_secret().to_string(),
+            "c15d739894c81a2fcfd3a2df85a0d2c0dbc47a280d092799f144d73d7ae78add"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accounts() -> Result<()> {
+        let mnemonic: &str =
+            "leader monkey parrot ring guide accident before fence cannon height naive bean";
+        let accounts = MnemonicAccount::derive_accounts(mnemonic, Some("passphrase"), 3)?;
+        assert_eq!(accounts.len(), 3);
+        assert_eq!(
+            accounts[0].keys(),
+            &Keys::from_mnemonic(mnemonic, Some("passphrase"))?
+        );
+        assert_eq!(accounts[2].account(), 2);
+        assert_eq!(
+            accounts[2].keys(),
+            &Keys::from_mnemonic_with_account(mnemonic, Some("passphrase"), 2)?
+        );
+        assert_ne!(accounts[0].keys(), accounts[1].keys());
+        assert_ne!(accounts[1].keys(), accounts[2].keys());
+
+        // Hardened indexes must be lower than 2^31
+        assert!(Keys::from_mnemonic_with_account(mnemonic, None, 1 << 31).is_err());
+        assert!(Keys::from_mnemonic_with_account(mnemonic, None, u32::MAX).is_err());
+        assert!(MnemonicAccount::derive(mnemonic, None, u32::MAX).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_languages() -> Result<()> {
+        let mnemonic = Keys::generate_mnemonic_in(Language::Spanish, 24)?;
+        assert_eq!(mnemonic.language(), Language::Spanish);
+        assert_eq!(mnemonic.to_string().split_whitespace().count(), 24);
+
+        let account = MnemonicAccount::derive(mnemonic.to_string(), None, 1)?;
+        assert_eq!(account.mnemonic(), &mnemonic);
+        assert_eq!(account.account(), 1);
+        assert_eq!(
+            account.keys(),
+            &Keys::from_mnemonic_with_account(mnemonic.to_string(), None, 1)?
+        );
+
+        let accounts = MnemonicAccount::derive_accounts(mnemonic.to_string(), None, 2)?;
+        assert_eq!(accounts[1].keys(), account.keys());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,261 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP10
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/10.md>
+
+use secp256k1::XOnlyPublicKey;
+
+use crate::event::tag::{Marker, Tag};
+use crate::{Event, EventId};
+
+/// Event referenced by an `e` tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventReference {
+    /// Event id
+    pub id: EventId,
+    /// Recommended relay url
+    pub relay_url: Option<String>,
+}
+
+impl EventReference {
+    fn new(id: EventId, relay_url: Option<String>) -> Self {
+        Self {
+            id,
+            relay_url: relay_url.filter(|url| !url.is_empty()),
+        }
+    }
+}
+
+/// Thread of an [`Event`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Thread {
+    /// Root of the thread
+    pub root: Option<EventReference>,
+    /// Direct parent of the event
+    pub reply: Option<EventReference>,
+    /// Mentioned events
+    pub mentions: Vec<EventReference>,
+    /// Tagged public keys
+    pub pubkeys: Vec<XOnlyPublicKey>,
+}
+
+impl Thread {
+    /// Check if the event is a reply
+    pub fn is_reply(&self) -> bool {
+        self.reply.is_some()
+    }
+}
+
+impl Event {
+    /// Get the [`Thread`] of the event
+    ///
+    /// Both marked `e` tags and the deprecated positional `e` tags are supported.
+    pub fn thread(&self) -> Thread {
+        let mut marked: bool = false;
+        let mut refs: Vec<(EventReference, Option<&Marker>)> = Vec::new();
+        let mut pubkeys: Vec<XOnlyPublicKey> = Vec::new();
+
+        for tag in self.tags.iter() {
+            match tag {
+                Tag::Event(id, relay_url, marker) => {
+                    // Any marker (ex. `mention`) means the marked format is used
+                    if marker.is_some() {
+                        marked = true;
+                    }
+                    refs.push((EventReference::new(*id, relay_url.clone()), marker.as_ref()));
+                }
+                Tag::PubKey(pubkey, _) if !pubkeys.contains(pubkey) => pubkeys.push(*pubkey),
+                _ => (),
+            }
+        }
+
+        let mut thread = Thread {
+            pubkeys,
+            ..Default::default()
+        };
+
+        if marked {
+            for (reference, marker) in refs.into_iter() {
+                match marker {
+                    Some(Marker::Root) if thread.root.is_none() => thread.root = Some(reference),
+                    Some(Marker::Reply) if thread.reply.is_none() => thread.reply = Some(reference),
+                    Some(Marker::Root) | Some(Marker::Reply) => (),
+                    _ => thread.mentions.push(reference),
+                }
+            }
+
+            // A reply to the root event has only the `root` marker
+            if thread.reply.is_none() {
+                thread.reply = thread.root.clone();
+            }
+        } else {
+            // Deprecated positional `e` tags:
+            // first is the root, last is the reply, the others are mentions
+            let mut refs: Vec<EventReference> = refs.into_iter().map(|(r, _)| r).collect();
+            if !refs.is_empty() {
+                let root: EventReference = refs.remove(0);
+                let reply: EventReference = refs.pop().unwrap_or_else(|| root.clone());
+                thread.root = Some(root);
+                thread.reply = Some(reply);
+                thread.mentions = refs;
+            }
+        }
+
+        thread
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{EventBuilder, Keys, Kind};
+
+    const ROOT_ID: &str = "378f145897eea948952674269945e88612420db35791784abf0616b4fed56ef7";
+    const REPLY_ID: &str = "2be17aa3031bdcb006f0fce80c146dea9c1c0268b0af2398bb673365c6444d45";
+    const MENTION_ID: &str = "b3e392b11f5d4f28321cedd09303a748acfd0487aea5a7450b3481c60b6e4f87";
+    const PUBKEY: &str = "13adc511de7e1cfcf1c6b7f6365fb5a03442d7bcacf565ea57fa7770912c023d";
+
+    fn event_with_tags(tags: &[Tag]) -> Event {
+        EventBuilder::new(Kind::TextNote, "", tags)
+            .to_event(&Keys::generate())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_marked_thread() {
+        let root = EventId::from_hex(ROOT_ID).unwrap();
+        let reply = EventId::from_hex(REPLY_ID).unwrap();
+        let mention = EventId::from_hex(MENTION_ID).unwrap();
+        let pubkey = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+
+        let event = event_with_tags(&[
+            Tag::Event(mention, None, Some(Marker::Custom(String::from("mention")))),
+            Tag::Event(reply, None, Some(Marker::Reply)),
+            Tag::Event(
+                root,
+                Some(String::from("wss://relay.damus.io")),
+                Some(Marker::Root),
+            ),
+            Tag::PubKey(pubkey, None),
+            Tag::PubKey(pubkey, None),
+        ]);
+        let thread = event.thread();
+
+        assert_eq!(
+            thread.root,
+            Some(EventReference {
+                id: root,
+                relay_url: Some(String::from("wss://relay.damus.io"))
+            })
+        );
+        assert_eq!(thread.reply.map(|r| r.id), Some(reply));
+        assert_eq!(
+            thread.mentions,
+            vec![EventReference {
+                id: mention,
+                relay_url: None
+            }]
+        );
+        assert_eq!(thread.pubkeys, vec![pubkey]);
+    }
+
+    #[test]
+    fn test_marked_reply_to_root() {
+        let root = EventId::from_hex(ROOT_ID).unwrap();
+        let event = event_with_tags(&[Tag::Event(root, None, Some(Marker::Root))]);
+        let thread = event.thread();
+
+        assert_eq!(thread.root.map(|r| r.id), Some(root));
+        assert_eq!(thread.reply.map(|r| r.id), Some(root));
+        assert!(thread.mentions.is_empty());
+    }
+
+    #[test]
+    fn test_marked_mentions_only() {
+        let mention = EventId::from_hex(MENTION_ID).unwrap();
+        let event = event_with_tags(&[Tag::Event(
+            mention,
+            None,
+            Some(Marker::Custom(String::from("mention"))),
+        )]);
+        let thread = event.thread();
+
+        assert!(thread.root.is_none());
+        assert!(!thread.is_reply());
+        assert_eq!(
+            thread
+                .mentions
+                .into_iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>(),
+            vec![mention]
+        );
+    }
+
+    #[test]
+    fn test_positional_thread() {
+        let root = EventId::from_hex(ROOT_ID).unwrap();
+        let reply = EventId::from_hex(REPLY_ID).unwrap();
+        let mention = EventId::from_hex(MENTION_ID).unwrap();
+
+        let thread = event_with_tags(&[]).thread();
+        assert_eq!(thread, Thread::default());
+        assert!(!thread.is_reply());
+
+        let thread = event_with_tags(&[Tag::Event(root, None, None)]).thread();
+        assert_eq!(thread.root.map(|r| r.id), Some(root));
+        assert_eq!(thread.reply.map(|r| r.id), Some(root));
+
+        let thread = event_with_tags(&[
+            Tag::Event(root, Some(String::new()), None),
+            Tag::Event(mention, None, None),
+            Tag::Event(reply, None, None),
+        ])
+        .thread();
+        assert_eq!(
+            thread.root,
+            Some(EventReference {
+                id: root,
+                relay_url: None
+            })
+        );
+        assert_eq!(thread.reply.map(|r| r.id), Some(reply));
+        assert_eq!(
+            thread
+                .mentions
+                .into_iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>(),
+            vec![mention]
+        );
+    }
+
+    #[test]
+    fn test_reply_to() {
+        let keys = Keys::generate();
+        let pubkey = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+
+        let root: Event = EventBuilder::new_text_note("root", &[Tag::PubKey(pubkey, None)])
+            .to_event(&keys)
+            .unwrap();
+        let reply: Event = EventBuilder::reply_to(&root, "reply")
+            .to_event(&keys)
+            .unwrap();
+        let thread = reply.thread();
+        assert_eq!(thread.root.map(|r| r.id), Some(root.id));
+        assert_eq!(thread.reply.map(|r| r.id), Some(root.id));
+        assert_eq!(thread.pubkeys, vec![keys.public_key(), pubkey]);
+
+        let reply_of_reply: Event = EventBuilder::reply_to(&reply, "reply of reply")
+            .to_event(&keys)
+            .unwrap();
+        let thread = reply_of_reply.thread();
+        assert_eq!(thread.root.map(|r| r.id), Some(root.id));
+        assert_eq!(thread.reply.map(|r| r.id), Some(reply.id));
+        assert_eq!(thread.pubkeys, vec![keys.public_key(), pubkey]);
+    }
+}
@@ -6,7 +6,14 @@
 //!
 //! <https://github.com/nostr-protocol/nips/blob/master/13.md>
 
-use bitcoin_hashes::sha256::Hash as Sha256Hash;
+use bitcoin_hashes::sha256::{Hash as Sha256Hash, HashEngine};
+use bitcoin_hashes::{Hash, HashEngine as _};
+
+use crate::util::search;
+pub use crate::util::{CancellationToken, SearchError, SearchOptions, SearchProgress};
+
+/// Number of attempts between two checks of the stop conditions
+const BATCH_SIZE: u64 = 1024;
 
 /// Gets the number of leading zero bits of a hash. Result is between 0 and 255.
 pub fn get_leading_zero_bits(h: Sha256Hash) -> u8 {
@@ -51,8 +58,78 @@ pub fn get_prefixes_for_difficulty(leading_zero_bits: u8) -> Vec<String> {
     r
 }
 
+/// Proof of work error
+pub type PowError = SearchError;
+
+/// Proof of work progress
+pub type PowProgress = SearchProgress;
+
+/// Proof of work options
+pub type PowOptions = SearchOptions;
+
+/// Write the decimal representation of `n` at the end of `buf`, returning the used part
+fn write_decimal(buf: &mut [u8; 39], mut n: u128) -> &[u8] {
+    let mut pos: usize = buf.len();
+    loop {
+        pos -= 1;
+        buf[pos] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            return &buf[pos..];
+        }
+    }
+}
+
+/// Try `BATCH_SIZE` nonces, starting from `nonce` and incrementing by `step`
+fn mine_batch(
+    engine: &HashEngine,
+    suffix: &[u8],
+    difficulty: u8,
+    nonce: &mut u128,
+    step: u128,
+) -> Option<(u128, Sha256Hash)> {
+    let mut buf = [0u8; 39];
+    for _ in 0..BATCH_SIZE {
+        let mut e: HashEngine = engine.clone();
+        e.input(write_decimal(&mut buf, *nonce));
+        e.input(suffix);
+        let hash = Sha256Hash::from_engine(e);
+        if get_leading_zero_bits(hash) >= difficulty {
+            return Some((*nonce, hash));
+        }
+        *nonce += step;
+    }
+    None
+}
+
+/// Find a nonce such that `sha256(prefix || nonce || suffix)` has at least `difficulty` leading zero bits
+///
+/// The nonce is written in decimal. The `prefix` is hashed only once
+/// and the work is split on the threads set in [`PowOptions`].
+///
+/// Return the nonce and the hash.
+pub fn mine(
+    prefix: &[u8],
+    suffix: &[u8],
+    difficulty: u8,
+    opts: &PowOptions,
+) -> Result<(u128, Sha256Hash), PowError> {
+    let mut engine = Sha256Hash::engine();
+    engine.input(prefix);
+
+    search::run(opts, BATCH_SIZE, |index, threads| {
+        let engine = &engine;
+        let mut nonce: u128 = index as u128 + 1;
+        move || mine_batch(engine, suffix, difficulty, &mut nonce, threads as u128)
+    })
+}
+
 #[cfg(test)]
 pub mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
     use super::*;
     use bitcoin_hashes::hex::FromHex;
 
@@ -426,4 +503,51 @@ pub mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_write_decimal() {
+        let mut buf = [0u8; 39];
+        assert_eq!(write_decimal(&mut buf, 0), b"0");
+        assert_eq!(write_decimal(&mut buf, 1234567890), b"1234567890");
+        assert_eq!(
+            write_decimal(&mut buf, u128::MAX),
+            u128::MAX.to_string().as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_mine() {
+        for threads in [1, 4] {
+            let opts = PowOptions::new().threads(threads);
+            let (nonce, hash) = mine(b"prefix-", b"-suffix", 12, &opts).unwrap();
+            assert!(get_leading_zero_bits(hash) >= 12);
+            assert_eq!(
+                hash,
+                Sha256Hash::hash(format!("prefix-{nonce}-suffix").as_bytes())
+            );
+        }
+    }
+
+    #[test]
+    fn test_mine_cancel_and_timeout() {
+        let token = CancellationToken::new();
+        token.cancel();
+        for threads in [1, 2] {
+            let opts = PowOptions::new()
+                .threads(threads)
+                .cancellation_token(token.clone());
+            assert_eq!(mine(b"", b"", 255, &opts), Err(PowError::Cancelled));
+
+            let calls = Arc::new(AtomicU64::new(0));
+            let c = calls.clone();
+            let opts = PowOptions::new()
+                .threads(threads)
+                .timeout(Duration::from_millis(200))
+                .progress(Duration::from_millis(10), move |_| {
+                    c.fetch_add(1, Ordering::SeqCst);
+                });
+            assert_eq!(mine(b"", b"", 255, &opts), Err(PowError::Timeout));
+            assert!(calls.load(Ordering::SeqCst) > 0);
+        }
+    }
 }
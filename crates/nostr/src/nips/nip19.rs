@@ -14,10 +14,13 @@ use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "base")]
 use crate::event::id::{self, EventId};
+#[cfg(feature = "nip49")]
+use crate::nips::nip49::{self, EncryptedSecretKey};
 #[cfg(feature = "base")]
 use crate::Kind;
 
 pub const PREFIX_BECH32_SECRET_KEY: &str = "nsec";
+pub const PREFIX_BECH32_SECRET_KEY_ENCRYPTED: &str = "ncryptsec";
 pub const PREFIX_BECH32_PUBLIC_KEY: &str = "npub";
 pub const PREFIX_BECH32_NOTE_ID: &str = "note";
 pub const PREFIX_BECH32_CHANNEL: &str = "nchannel";
@@ -61,6 +64,10 @@ pub enum Error {
     #[cfg(feature = "base")]
     #[error(transparent)]
     EventId(#[from] id::Error),
+    /// NIP49 error
+    #[cfg(feature = "nip49")]
+    #[error(transparent)]
+    NIP49(Box<nip49::Error>),
 }
 
 pub trait FromBech32: Sized {
@@ -87,6 +94,24 @@ impl FromBech32 for SecretKey {
     }
 }
 
+#[cfg(feature = "nip49")]
+impl FromBech32 for EncryptedSecretKey {
+    type Err = Error;
+    fn from_bech32<S>(encrypted_secret_key: S) -> Result<Self, Self::Err>
+    where
+        S: Into<String>,
+    {
+        let (hrp, data, checksum) = bech32::decode(&encrypted_secret_key.into())?;
+
+        if hrp != PREFIX_BECH32_SECRET_KEY_ENCRYPTED || checksum != Variant::Bech32 {
+            return Err(Error::WrongPrefixOrVariant);
+        }
+
+        let data = Vec::<u8>::from_base32(&data)?;
+        Self::from_slice(data.as_slice()).map_err(|e| Error::NIP49(Box::new(e)))
+    }
+}
+
 impl FromBech32 for XOnlyPublicKey {
     type Err = Error;
     fn from_bech32<S>(public_key: S) -> Result<Self, Self::Err>
@@ -153,6 +178,20 @@ impl ToBech32 for SecretKey {
     }
 }
 
+#[cfg(feature = "nip49")]
+impl ToBech32 for EncryptedSecretKey {
+    type Err = Error;
+
+    fn to_bech32(&self) -> Result<String, Self::Err> {
+        let data = self.as_vec().to_base32();
+        Ok(bech32::encode(
+            PREFIX_BECH32_SECRET_KEY_ENCRYPTED,
+            data,
+            Variant::Bech32,
+        )?)
+    }
+}
+
 // Note ID
 #[cfg(feature = "base")]
 impl ToBech32 for EventId {
@@ -186,6 +225,16 @@ impl Nip19Event {
             relays: relays.into_iter().map(|u| u.into()).collect(),
         }
     }
+
+    /// Get event id
+    pub fn event_id(&self) -> EventId {
+        self.event_id
+    }
+
+    /// Get relays
+    pub fn relays(&self) -> &[String] {
+        &self.relays
+    }
 }
 
 #[cfg(feature = "base")]
@@ -253,6 +302,105 @@ impl ToBech32 for Nip19Event {
     }
 }
 
+#[cfg(feature = "base")]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct Nip19Profile {
+    public_key: XOnlyPublicKey,
+    relays: Vec<String>,
+}
+
+#[cfg(feature = "base")]
+impl Nip19Profile {
+    pub fn new<S>(public_key: XOnlyPublicKey, relays: Vec<S>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            public_key,
+            relays: relays.into_iter().map(|u| u.into()).collect(),
+        }
+    }
+
+    /// Get public key
+    pub fn public_key(&self) -> XOnlyPublicKey {
+        self.public_key
+    }
+
+    /// Get relays
+    pub fn relays(&self) -> &[String] {
+        &self.relays
+    }
+}
+
+#[cfg(feature = "base")]
+impl FromBech32 for Nip19Profile {
+    type Err = Error;
+    fn from_bech32<S>(s: S) -> Result<Self, Self::Err>
+    where
+        S: Into<String>,
+    {
+        let (hrp, data, checksum) = bech32::decode(&s.into())?;
+
+        if hrp != PREFIX_BECH32_PROFILE || checksum != Variant::Bech32 {
+            return Err(Error::WrongPrefixOrVariant);
+        }
+
+        let mut data: Vec<u8> = Vec::from_base32(&data)?;
+
+        let mut public_key: Option<XOnlyPublicKey> = None;
+        let mut relays: Vec<String> = Vec::new();
+
+        while !data.is_empty() {
+            let t = data.first().ok_or(Error::TLV)?;
+            let l = data.get(1).ok_or(Error::TLV)?;
+            let l = *l as usize;
+
+            let bytes = data.get(2..l + 2).ok_or(Error::TLV)?;
+
+            match *t {
+                SPECIAL => {
+                    if public_key.is_none() {
+                        public_key = Some(XOnlyPublicKey::from_slice(bytes)?);
+                    }
+                }
+                RELAY => {
+                    relays.push(String::from_utf8(bytes.to_vec())?);
+                }
+                _ => (),
+            };
+
+            data.drain(..l + 2);
+        }
+
+        Ok(Self {
+            public_key: public_key.ok_or_else(|| Error::FieldMissing("pubkey".to_string()))?,
+            relays,
+        })
+    }
+}
+
+#[cfg(feature = "base")]
+impl ToBech32 for Nip19Profile {
+    type Err = Error;
+
+    fn to_bech32(&self) -> Result<String, Self::Err> {
+        let mut bytes: Vec<u8> = vec![SPECIAL, 32];
+        bytes.extend(self.public_key.serialize());
+
+        for relay in self.relays.iter() {
+            bytes.extend([RELAY, relay.len() as u8]);
+            bytes.extend(relay.as_bytes());
+        }
+
+        let data = bytes.to_base32();
+        Ok(bech32::encode(
+            PREFIX_BECH32_PROFILE,
+            data,
+            Variant::Bech32,
+        )?)
+    }
+}
+
 #[cfg(feature = "base")]
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub struct ParameterizedReplaceableEvent {
@@ -262,6 +410,41 @@ pub struct ParameterizedReplaceableEvent {
     relays: Vec<String>,
 }
 
+#[cfg(feature = "base")]
+impl ParameterizedReplaceableEvent {
+    pub fn new<S>(kind: Kind, pubkey: XOnlyPublicKey, identifier: S, relays: Vec<String>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            kind,
+            pubkey,
+            identifier: identifier.into(),
+            relays,
+        }
+    }
+
+    /// Get kind
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Get author public key
+    pub fn pubkey(&self) -> XOnlyPublicKey {
+        self.pubkey
+    }
+
+    /// Get identifier
+    pub fn identifier(&self) -> &str {
+        &self.identifier
+    }
+
+    /// Get relays
+    pub fn relays(&self) -> &[String] {
+        &self.relays
+    }
+}
+
 #[cfg(feature = "base")]
 impl FromBech32 for ParameterizedReplaceableEvent {
     type Err = Error;
@@ -401,4 +584,26 @@ mod tests {
         );
         Ok(())
     }
+
+    #[cfg(feature = "base")]
+    #[test]
+    fn nprofile() -> Result<()> {
+        let nprofile = "nprofile1qqsrhuxx8l9ex335q7he0f09aej04zpazpl0ne2cgukyawd24mayt8gpp4mhxue69uhhytnc9e3k7mgpz4mhxue69uhkg6nzv9ejuumpv34kytnrdaksjlyr9p";
+        let profile = Nip19Profile::from_bech32(nprofile)?;
+        assert_eq!(
+            profile.public_key(),
+            XOnlyPublicKey::from_str(
+                "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d"
+            )?
+        );
+        assert_eq!(
+            profile.relays(),
+            &[
+                String::from("wss://r.x.com"),
+                String::from("wss://djbas.sadkb.com")
+            ]
+        );
+        assert_eq!(profile.to_bech32()?, nprofile);
+        Ok(())
+    }
 }
@@ -0,0 +1,300 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP27
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/27.md>
+
+use secp256k1::XOnlyPublicKey;
+use url::Url;
+
+use super::nip19::{
+    self, FromBech32, Nip19Event, Nip19Profile, ParameterizedReplaceableEvent, ToBech32,
+    PREFIX_BECH32_EVENT, PREFIX_BECH32_NOTE_ID, PREFIX_BECH32_PARAMETERIZED_REPLACEABLE_EVENT,
+    PREFIX_BECH32_PROFILE, PREFIX_BECH32_PUBLIC_KEY,
+};
+use crate::event::tag::{Marker, Tag};
+use crate::{Event, EventId};
+
+/// `nostr:` URI scheme
+pub const URI_SCHEME: &str = "nostr:";
+
+const MENTION_MARKER: &str = "mention";
+const URL_TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '}', '"', '\''];
+
+/// Entity referenced by a `nostr:` URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mention {
+    /// `npub`
+    PublicKey(XOnlyPublicKey),
+    /// `nprofile`
+    Profile(Nip19Profile),
+    /// `note`
+    EventId(EventId),
+    /// `nevent`
+    Event(Nip19Event),
+    /// `naddr`
+    ParameterizedReplaceableEvent(ParameterizedReplaceableEvent),
+}
+
+impl Mention {
+    /// Parse [`Mention`] from `nostr:` URI
+    pub fn from_uri<S>(uri: S) -> Result<Self, nip19::Error>
+    where
+        S: Into<String>,
+    {
+        let uri: String = uri.into();
+        match uri.strip_prefix(URI_SCHEME) {
+            Some(data) => Self::from_bech32(data),
+            None => Err(nip19::Error::WrongPrefixOrVariant),
+        }
+    }
+
+    /// Get `nostr:` URI
+    pub fn to_uri(&self) -> Result<String, nip19::Error> {
+        Ok(format!("{URI_SCHEME}{}", self.to_bech32()?))
+    }
+
+    /// Get the [`Tag`] that reference the mentioned entity
+    pub fn to_tag(&self) -> Tag {
+        match self {
+            Self::PublicKey(public_key) => Tag::PubKey(*public_key, None),
+            Self::Profile(profile) => Tag::PubKey(profile.public_key(), None),
+            Self::EventId(event_id) => {
+                Tag::Event(*event_id, None, Some(Marker::from(MENTION_MARKER)))
+            }
+            Self::Event(event) => Tag::Event(
+                event.event_id(),
+                event.relays().first().cloned(),
+                Some(Marker::from(MENTION_MARKER)),
+            ),
+            Self::ParameterizedReplaceableEvent(addr) => Tag::A {
+                kind: addr.kind(),
+                public_key: addr.pubkey(),
+                identifier: addr.identifier().to_string(),
+                relay_url: addr.relays().first().cloned().unwrap_or_default(),
+            },
+        }
+    }
+}
+
+impl FromBech32 for Mention {
+    type Err = nip19::Error;
+    fn from_bech32<S>(s: S) -> Result<Self, Self::Err>
+    where
+        S: Into<String>,
+    {
+        let s: String = s.into();
+        let hrp: &str = s.split('1').next().unwrap_or_default();
+        match hrp {
+            PREFIX_BECH32_PUBLIC_KEY => Ok(Self::PublicKey(XOnlyPublicKey::from_bech32(s)?)),
+            PREFIX_BECH32_PROFILE => Ok(Self::Profile(Nip19Profile::from_bech32(s)?)),
+            PREFIX_BECH32_NOTE_ID => Ok(Self::EventId(EventId::from_bech32(s)?)),
+            PREFIX_BECH32_EVENT => Ok(Self::Event(Nip19Event::from_bech32(s)?)),
+            PREFIX_BECH32_PARAMETERIZED_REPLACEABLE_EVENT => Ok(
+                Self::ParameterizedReplaceableEvent(ParameterizedReplaceableEvent::from_bech32(s)?),
+            ),
+            _ => Err(nip19::Error::WrongPrefixOrVariant),
+        }
+    }
+}
+
+impl ToBech32 for Mention {
+    type Err = nip19::Error;
+
+    fn to_bech32(&self) -> Result<String, Self::Err> {
+        match self {
+            Self::PublicKey(public_key) => public_key.to_bech32(),
+            Self::Profile(profile) => profile.to_bech32(),
+            Self::EventId(event_id) => event_id.to_bech32(),
+            Self::Event(event) => event.to_bech32(),
+            Self::ParameterizedReplaceableEvent(addr) => addr.to_bech32(),
+        }
+    }
+}
+
+/// Content token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// Plain text
+    Text(String),
+    /// `nostr:` URI
+    Mention(Mention),
+    /// Hashtag (without the `#`)
+    Hashtag(String),
+    /// URL
+    Url(Url),
+}
+
+/// Split the content of a note in [`Token`]s
+pub fn parse(content: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut text_start: usize = 0;
+    let mut index: usize = 0;
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = content[index..].chars().next() {
+        // Tokens must start at the beginning of a word
+        if !prev.map(|p| p.is_alphanumeric()).unwrap_or(false) {
+            if let Some((token, len)) = parse_token(&content[index..]) {
+                if text_start < index {
+                    tokens.push(Token::Text(content[text_start..index].to_string()));
+                }
+                tokens.push(token);
+                index += len;
+                text_start = index;
+                prev = content[..index].chars().next_back();
+                continue;
+            }
+        }
+
+        prev = Some(c);
+        index += c.len_utf8();
+    }
+
+    if text_start < content.len() {
+        tokens.push(Token::Text(content[text_start..].to_string()));
+    }
+
+    tokens
+}
+
+/// Try to parse a [`Token`] at the start of `s`, returning it with its length in bytes
+fn parse_token(s: &str) -> Option<(Token, usize)> {
+    if let Some(data) = s.strip_prefix(URI_SCHEME) {
+        let len: usize = data
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(data.len());
+        let mention = Mention::from_bech32(&data[..len]).ok()?;
+        return Some((Token::Mention(mention), URI_SCHEME.len() + len));
+    }
+
+    if let Some(data) = s.strip_prefix('#') {
+        let len: usize = data
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(data.len());
+        if len == 0 {
+            return None;
+        }
+        return Some((Token::Hashtag(data[..len].to_string()), len + 1));
+    }
+
+    if s.starts_with("https://") || s.starts_with("http://") {
+        let len: usize = s.find(char::is_whitespace).unwrap_or(s.len());
+        let url: &str = s[..len].trim_end_matches(URL_TRAILING_PUNCTUATION);
+        let url = Url::parse(url).ok()?;
+        let len: usize = s[..len].trim_end_matches(URL_TRAILING_PUNCTUATION).len();
+        return Some((Token::Url(url), len));
+    }
+
+    None
+}
+
+/// Get the `p`, `e`, `a` and `t` tags that reference the mentions and the hashtags of the content
+pub fn extract_tags(content: &str) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = Vec::new();
+    for token in parse(content).into_iter() {
+        let tag: Tag = match token {
+            Token::Mention(mention) => mention.to_tag(),
+            Token::Hashtag(hashtag) => Tag::Hashtag(hashtag.to_lowercase()),
+            _ => continue,
+        };
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+impl Event {
+    /// Split the content in [`Token`]s
+    pub fn content_tokens(&self) -> Vec<Token> {
+        parse(&self.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{EventBuilder, Kind};
+
+    const PUBKEY: &str = "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d";
+    const NPUB: &str = "npub180cvv07tjdrrgpa0j7j7tmnyl2yr6yr7l8j4s3evf6u64th6gkwsyjh6w6";
+    const NPROFILE: &str = "nprofile1qqsrhuxx8l9ex335q7he0f09aej04zpazpl0ne2cgukyawd24mayt8gpp4mhxue69uhhytnc9e3k7mgpz4mhxue69uhkg6nzv9ejuumpv34kytnrdaksjlyr9p";
+    const NOTE: &str = "note1m99r7nwc0wdrkzldrqan96gklg5usqspq7z9696j6unf0ljnpxjspqfw99";
+
+    #[test]
+    fn test_parse_content() {
+        let public_key = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+        let content = format!(
+            "Hello nostr:{NPUB}, check #Nostr at https://example.com/page. Ciao#no nostr:invalid"
+        );
+
+        assert_eq!(
+            parse(&content),
+            vec![
+                Token::Text(String::from("Hello ")),
+                Token::Mention(Mention::PublicKey(public_key)),
+                Token::Text(String::from(", check ")),
+                Token::Hashtag(String::from("Nostr")),
+                Token::Text(String::from(" at ")),
+                Token::Url(Url::parse("https://example.com/page").unwrap()),
+                Token::Text(String::from(". Ciao#no nostr:invalid")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mention_uri() {
+        let uri = format!("nostr:{NPROFILE}");
+        let mention = Mention::from_uri(&uri).unwrap();
+        assert!(matches!(mention, Mention::Profile(_)));
+        assert_eq!(mention.to_uri().unwrap(), uri);
+
+        let uri = format!("nostr:{NOTE}");
+        let mention = Mention::from_uri(&uri).unwrap();
+        assert!(matches!(mention, Mention::EventId(_)));
+        assert_eq!(mention.to_uri().unwrap(), uri);
+
+        assert!(Mention::from_uri(NOTE).is_err());
+    }
+
+    #[test]
+    fn test_extract_tags() {
+        let public_key = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+        let event_id = EventId::from_bech32(NOTE).unwrap();
+        let naddr = ParameterizedReplaceableEvent::new(
+            Kind::LongFormTextNote,
+            public_key,
+            "ipsum",
+            vec![String::from("wss://relay.nostr.org")],
+        )
+        .to_bech32()
+        .unwrap();
+        let content =
+            format!("nostr:{NPUB} nostr:{NPROFILE} nostr:{NOTE} nostr:{naddr} #Nostr #nostr");
+
+        assert_eq!(
+            extract_tags(&content),
+            vec![
+                Tag::PubKey(public_key, None),
+                Tag::Event(event_id, None, Some(Marker::from("mention"))),
+                Tag::A {
+                    kind: Kind::LongFormTextNote,
+                    public_key,
+                    identifier: String::from("ipsum"),
+                    relay_url: String::from("wss://relay.nostr.org"),
+                },
+                Tag::Hashtag(String::from("nostr")),
+            ]
+        );
+
+        let event = EventBuilder::new_text_note_with_references(content, &[])
+            .to_event(&crate::Keys::generate())
+            .unwrap();
+        assert_eq!(event.tags.len(), 4);
+        assert_eq!(event.content_tokens().len(), 11);
+    }
+}
@@ -0,0 +1,635 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP44
+//!
+//! Versioned encrypted payloads
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/44.md>
+
+use std::fmt;
+
+use base64::engine::{general_purpose, Engine};
+use bitcoin_hashes::hmac::{Hmac, HmacEngine};
+use bitcoin_hashes::{sha256, Hash, HashEngine};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::ChaCha20;
+use secp256k1::rand::rngs::OsRng;
+use secp256k1::rand::RngCore;
+use secp256k1::{ecdh, Parity, PublicKey, SecretKey, XOnlyPublicKey};
+use zeroize::{Zeroize, Zeroizing};
+
+const SALT: &[u8] = b"nip44-v2";
+const NONCE_SIZE: usize = 32;
+const MAC_SIZE: usize = 32;
+const MESSAGE_KEYS_SIZE: usize = 76;
+
+const MIN_PLAINTEXT_SIZE: usize = 1;
+const MAX_PLAINTEXT_SIZE: usize = 65535;
+
+const MIN_PAYLOAD_SIZE: usize = 132;
+const MAX_PAYLOAD_SIZE: usize = 87472;
+const MIN_DECODED_PAYLOAD_SIZE: usize = 99;
+const MAX_DECODED_PAYLOAD_SIZE: usize = 65603;
+
+/// `NIP44` error
+#[derive(Debug, Eq, PartialEq, thiserror::Error)]
+pub enum Error {
+    /// Unknown version
+    #[error("unknown version: {0}")]
+    UnknownVersion(u8),
+    /// Version not found in payload
+    #[error("version not found in payload")]
+    VersionNotFound,
+    /// Message is empty or longer than 65535 bytes
+    #[error("invalid message length: {0}")]
+    InvalidMessageLength(usize),
+    /// Invalid payload length
+    #[error("invalid payload length: {0}")]
+    InvalidPayloadLength(usize),
+    /// Error while decoding from base64
+    #[error("error while decoding from base64")]
+    Base64Decode,
+    /// Invalid MAC
+    #[error("invalid MAC")]
+    InvalidMac,
+    /// Invalid padding
+    #[error("invalid padding")]
+    InvalidPadding,
+    /// Error while encoding to UTF-8
+    #[error("error while encoding to UTF-8")]
+    Utf8Encode,
+    /// Secp256k1 error
+    #[error(transparent)]
+    Secp256k1(#[from] secp256k1::Error),
+}
+
+/// Payload version
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Version {
+    /// Version 2: secp256k1 ECDH, HKDF, ChaCha20 and HMAC-SHA256
+    #[default]
+    V2 = 0x02,
+}
+
+impl Version {
+    /// Get version as `u8`
+    pub fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl TryFrom<u8> for Version {
+    type Error = Error;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            0x02 => Ok(Self::V2),
+            v => Err(Error::UnknownVersion(v)),
+        }
+    }
+}
+
+/// Conversation key
+///
+/// Shared by the two parties: derive it once to encrypt or decrypt many messages.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ConversationKey([u8; 32]);
+
+impl fmt::Debug for ConversationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ConversationKey(<sensitive>)")
+    }
+}
+
+impl Drop for ConversationKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ConversationKey {
+    /// Derive the [`ConversationKey`] from the secret key of one party and the public key of the other
+    pub fn derive(secret_key: &SecretKey, public_key: &XOnlyPublicKey) -> Result<Self, Error> {
+        let public_key = PublicKey::from_x_only_public_key(*public_key, Parity::Even);
+        let mut shared_point: [u8; 64] = ecdh::shared_secret_point(&public_key, secret_key);
+        let mut engine: HmacEngine<sha256::Hash> = HmacEngine::new(SALT);
+        engine.input(&shared_point[..32]);
+        shared_point.zeroize();
+        Ok(Self(Hmac::from_engine(engine).into_inner()))
+    }
+
+    /// Get the [`ConversationKey`] as bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Keys derived from the [`ConversationKey`] and the nonce of a message
+struct MessageKeys(Zeroizing<[u8; MESSAGE_KEYS_SIZE]>);
+
+impl MessageKeys {
+    /// HKDF-expand
+    fn derive(conversation_key: &ConversationKey, nonce: &[u8; NONCE_SIZE]) -> Self {
+        let mut okm = Zeroizing::new([0u8; MESSAGE_KEYS_SIZE]);
+        let mut previous: Option<[u8; 32]> = None;
+        for (counter, chunk) in (1u8..).zip(okm.chunks_mut(32)) {
+            let mut engine: HmacEngine<sha256::Hash> = HmacEngine::new(&conversation_key.0);
+            if let Some(previous) = previous.as_ref() {
+                engine.input(previous);
+            }
+            engine.input(nonce);
+            engine.input(&[counter]);
+            let block: [u8; 32] = Hmac::from_engine(engine).into_inner();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            previous = Some(block);
+        }
+        if let Some(mut previous) = previous {
+            previous.zeroize();
+        }
+        Self(okm)
+    }
+
+    fn chacha_key(&self) -> &[u8] {
+        &self.0[..32]
+    }
+
+    fn chacha_nonce(&self) -> &[u8] {
+        &self.0[32..44]
+    }
+
+    fn hmac_key(&self) -> &[u8] {
+        &self.0[44..]
+    }
+
+    fn mac(&self, nonce: &[u8], ciphertext: &[u8]) -> [u8; MAC_SIZE] {
+        let mut engine: HmacEngine<sha256::Hash> = HmacEngine::new(self.hmac_key());
+        engine.input(nonce);
+        engine.input(ciphertext);
+        Hmac::from_engine(engine).into_inner()
+    }
+
+    fn apply_keystream(&self, buf: &mut [u8]) {
+        let mut cipher = ChaCha20::new(self.chacha_key().into(), self.chacha_nonce().into());
+        cipher.apply_keystream(buf);
+    }
+}
+
+/// Length of the padded plaintext (without the 2-bytes length prefix)
+fn calc_padded_len(unpadded_len: usize) -> usize {
+    if unpadded_len <= 32 {
+        return 32;
+    }
+
+    let next_power: usize = 1 << (usize::BITS - (unpadded_len - 1).leading_zeros());
+    let chunk: usize = if next_power <= 256 {
+        32
+    } else {
+        next_power / 8
+    };
+    chunk * ((unpadded_len - 1) / chunk + 1)
+}
+
+fn pad(plaintext: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let len: usize = plaintext.len();
+    if !(MIN_PLAINTEXT_SIZE..=MAX_PLAINTEXT_SIZE).contains(&len) {
+        return Err(Error::InvalidMessageLength(len));
+    }
+
+    let mut padded = Zeroizing::new(Vec::with_capacity(2 + calc_padded_len(len)));
+    padded.extend_from_slice(&(len as u16).to_be_bytes());
+    padded.extend_from_slice(plaintext);
+    padded.resize(2 + calc_padded_len(len), 0);
+    Ok(padded)
+}
+
+fn unpad(padded: &[u8]) -> Result<&[u8], Error> {
+    if padded.len() < 2 {
+        return Err(Error::InvalidPadding);
+    }
+
+    let len: usize = u16::from_be_bytes([padded[0], padded[1]]) as usize;
+    if len < MIN_PLAINTEXT_SIZE || padded.len() != 2 + calc_padded_len(len) {
+        return Err(Error::InvalidPadding);
+    }
+
+    Ok(&padded[2..2 + len])
+}
+
+/// Compare in constant time
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn encrypt_with_nonce(
+    conversation_key: &ConversationKey,
+    plaintext: &[u8],
+    nonce: [u8; NONCE_SIZE],
+) -> Result<String, Error> {
+    let keys = MessageKeys::derive(conversation_key, &nonce);
+
+    let mut ciphertext = pad(plaintext)?;
+    keys.apply_keystream(&mut ciphertext);
+    let mac: [u8; MAC_SIZE] = keys.mac(&nonce, &ciphertext);
+
+    let mut payload: Vec<u8> = Vec::with_capacity(1 + NONCE_SIZE + ciphertext.len() + MAC_SIZE);
+    payload.push(Version::V2.as_u8());
+    payload.extend_from_slice(&nonce);
+    payload.extend_from_slice(&ciphertext);
+    payload.extend_from_slice(&mac);
+
+    Ok(general_purpose::STANDARD.encode(payload))
+}
+
+/// Encrypt `plaintext` with the [`ConversationKey`]
+pub fn encrypt_with_conversation_key<T>(
+    conversation_key: &ConversationKey,
+    plaintext: T,
+) -> Result<String, Error>
+where
+    T: AsRef<[u8]>,
+{
+    let mut nonce = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce);
+    encrypt_with_nonce(conversation_key, plaintext.as_ref(), nonce)
+}
+
+/// Decrypt `payload` with the [`ConversationKey`]
+pub fn decrypt_with_conversation_key<S>(
+    conversation_key: &ConversationKey,
+    payload: S,
+) -> Result<String, Error>
+where
+    S: AsRef<str>,
+{
+    let payload: &str = payload.as_ref();
+
+    // `#` is reserved for a future, non-base64 encoding
+    match payload.as_bytes().first() {
+        None => return Err(Error::VersionNotFound),
+        Some(b'#') => return Err(Error::UnknownVersion(b'#')),
+        Some(_) => (),
+    }
+
+    if !(MIN_PAYLOAD_SIZE..=MAX_PAYLOAD_SIZE).contains(&payload.len()) {
+        return Err(Error::InvalidPayloadLength(payload.len()));
+    }
+
+    let data: Vec<u8> = general_purpose::STANDARD
+        .decode(payload)
+        .map_err(|_| Error::Base64Decode)?;
+    if !(MIN_DECODED_PAYLOAD_SIZE..=MAX_DECODED_PAYLOAD_SIZE).contains(&data.len()) {
+        return Err(Error::InvalidPayloadLength(data.len()));
+    }
+
+    match Version::try_from(data[0])? {
+        Version::V2 => {
+            let mut nonce = [0u8; NONCE_SIZE];
+            nonce.copy_from_slice(&data[1..1 + NONCE_SIZE]);
+            let ciphertext: &[u8] = &data[1 + NONCE_SIZE..data.len() - MAC_SIZE];
+            let mac: &[u8] = &data[data.len() - MAC_SIZE..];
+
+            let keys = MessageKeys::derive(conversation_key, &nonce);
+            if !constant_time_eq(&keys.mac(&nonce, ciphertext), mac) {
+                return Err(Error::InvalidMac);
+            }
+
+            let mut padded = Zeroizing::new(ciphertext.to_vec());
+            keys.apply_keystream(&mut padded);
+            let plaintext: &[u8] = unpad(&padded)?;
+            String::from_utf8(plaintext.to_vec()).map_err(|_| Error::Utf8Encode)
+        }
+    }
+}
+
+/// Encrypt
+pub fn encrypt<T>(
+    secret_key: &SecretKey,
+    public_key: &XOnlyPublicKey,
+    content: T,
+    version: Version,
+) -> Result<String, Error>
+where
+    T: AsRef<[u8]>,
+{
+    match version {
+        Version::V2 => {
+            let conversation_key = ConversationKey::derive(secret_key, public_key)?;
+            encrypt_with_conversation_key(&conversation_key, content)
+        }
+    }
+}
+
+/// Decrypt
+///
+/// The version is read from the payload.
+pub fn decrypt<S>(
+    secret_key: &SecretKey,
+    public_key: &XOnlyPublicKey,
+    payload: S,
+) -> Result<String, Error>
+where
+    S: AsRef<str>,
+{
+    let conversation_key = ConversationKey::derive(secret_key, public_key)?;
+    decrypt_with_conversation_key(&conversation_key, payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::SECP256K1;
+
+    fn secret_key(hex: &str) -> SecretKey {
+        SecretKey::from_str(hex).unwrap()
+    }
+
+    fn public_key(secret_key: &SecretKey) -> XOnlyPublicKey {
+        secret_key.x_only_public_key(SECP256K1).0
+    }
+
+    fn nonce(hex: &str) -> [u8; NONCE_SIZE] {
+        let mut nonce = [0u8; NONCE_SIZE];
+        for (i, byte) in nonce.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        nonce
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[derive(Deserialize)]
+    struct Vectors {
+        v2: VectorsV2,
+    }
+
+    #[derive(Deserialize)]
+    struct VectorsV2 {
+        valid: ValidVectors,
+        invalid: InvalidVectors,
+    }
+
+    #[derive(Deserialize)]
+    struct ValidVectors {
+        get_conversation_key: Vec<ConversationKeyVector>,
+        #[serde(default)]
+        get_message_keys: Option<MessageKeysVectors>,
+        calc_padded_len: Vec<(usize, usize)>,
+        encrypt_decrypt: Vec<EncryptDecryptVector>,
+        #[serde(default)]
+        encrypt_decrypt_long_msg: Vec<LongMessageVector>,
+    }
+
+    #[derive(Deserialize)]
+    struct InvalidVectors {
+        encrypt_msg_lengths: Vec<usize>,
+        get_conversation_key: Vec<ConversationKeyVector>,
+        decrypt: Vec<DecryptVector>,
+    }
+
+    #[derive(Deserialize)]
+    struct ConversationKeyVector {
+        sec1: String,
+        pub2: String,
+        conversation_key: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct EncryptDecryptVector {
+        sec1: String,
+        sec2: String,
+        conversation_key: String,
+        nonce: String,
+        plaintext: String,
+        payload: String,
+    }
+
+    #[derive(Deserialize)]
+    struct MessageKeysVectors {
+        conversation_key: String,
+        keys: Vec<MessageKeysVector>,
+    }
+
+    #[derive(Deserialize)]
+    struct MessageKeysVector {
+        nonce: String,
+        chacha_key: String,
+        chacha_nonce: String,
+        hmac_key: String,
+    }
+
+    #[derive(Deserialize)]
+    struct LongMessageVector {
+        conversation_key: String,
+        nonce: String,
+        pattern: String,
+        repeat: usize,
+        plaintext_sha256: String,
+        payload_sha256: String,
+    }
+
+    #[derive(Deserialize)]
+    struct DecryptVector {
+        conversation_key: String,
+        payload: String,
+        note: String,
+    }
+
+    /// Test vectors in the format of the official `nip44.vectors.json`
+    ///
+    /// Every section of the official file is run: the optional ones are skipped if missing.
+    fn vectors() -> VectorsV2 {
+        let json: &str = include_str!("../../test_vectors/nip44.vectors.json");
+        serde_json::from_str::<Vectors>(json).unwrap().v2
+    }
+
+    #[test]
+    fn test_conversation_key() {
+        let vectors = vectors();
+
+        for vector in vectors.valid.get_conversation_key.into_iter() {
+            let pk = XOnlyPublicKey::from_str(&vector.pub2).unwrap();
+            let key = ConversationKey::derive(&secret_key(&vector.sec1), &pk).unwrap();
+            assert_eq!(Some(to_hex(key.as_bytes())), vector.conversation_key);
+        }
+
+        for vector in vectors.invalid.get_conversation_key.into_iter() {
+            assert!(
+                SecretKey::from_str(&vector.sec1).is_err()
+                    || XOnlyPublicKey::from_str(&vector.pub2).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_valid_encrypt_decrypt() {
+        for vector in vectors().valid.encrypt_decrypt.into_iter() {
+            let sk1 = secret_key(&vector.sec1);
+            let sk2 = secret_key(&vector.sec2);
+
+            let key = ConversationKey::derive(&sk1, &public_key(&sk2)).unwrap();
+            assert_eq!(to_hex(key.as_bytes()), vector.conversation_key);
+            assert_eq!(
+                ConversationKey::derive(&sk2, &public_key(&sk1)).unwrap(),
+                key
+            );
+
+            assert_eq!(
+                encrypt_with_nonce(&key, vector.plaintext.as_bytes(), nonce(&vector.nonce))
+                    .unwrap(),
+                vector.payload
+            );
+            assert_eq!(
+                decrypt_with_conversation_key(&key, &vector.payload).unwrap(),
+                vector.plaintext
+            );
+        }
+    }
+
+    #[test]
+    fn test_message_keys() {
+        if let Some(vectors) = vectors().valid.get_message_keys {
+            let key = ConversationKey(nonce(&vectors.conversation_key));
+            for vector in vectors.keys.into_iter() {
+                let keys = MessageKeys::derive(&key, &nonce(&vector.nonce));
+                assert_eq!(to_hex(keys.chacha_key()), vector.chacha_key);
+                assert_eq!(to_hex(keys.chacha_nonce()), vector.chacha_nonce);
+                assert_eq!(to_hex(keys.hmac_key()), vector.hmac_key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_messages() {
+        for vector in vectors().valid.encrypt_decrypt_long_msg.into_iter() {
+            let key = ConversationKey(nonce(&vector.conversation_key));
+            let plaintext: String = vector.pattern.repeat(vector.repeat);
+            assert_eq!(
+                sha256::Hash::hash(plaintext.as_bytes()).to_string(),
+                vector.plaintext_sha256
+            );
+
+            let payload: String =
+                encrypt_with_nonce(&key, plaintext.as_bytes(), nonce(&vector.nonce)).unwrap();
+            assert_eq!(
+                sha256::Hash::hash(payload.as_bytes()).to_string(),
+                vector.payload_sha256
+            );
+            assert_eq!(
+                decrypt_with_conversation_key(&key, &payload).unwrap(),
+                plaintext
+            );
+        }
+    }
+
+    #[test]
+    fn test_padding() {
+        for (unpadded, padded) in vectors().valid.calc_padded_len.into_iter() {
+            assert_eq!(calc_padded_len(unpadded), padded, "len {unpadded}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_vectors() {
+        let vectors = vectors();
+
+        let key = ConversationKey(nonce(
+            "c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d",
+        ));
+        for len in vectors.invalid.encrypt_msg_lengths.into_iter() {
+            assert_eq!(
+                encrypt_with_conversation_key(&key, "x".repeat(len)).unwrap_err(),
+                Error::InvalidMessageLength(len)
+            );
+        }
+
+        for vector in vectors.invalid.decrypt.into_iter() {
+            let key = ConversationKey(nonce(&vector.conversation_key));
+            assert!(
+                decrypt_with_conversation_key(&key, &vector.payload).is_err(),
+                "{}",
+                vector.note
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sk1 = secret_key("5c0c523f52a5b6fad39ed2403092df8cebc36318b39383bca6c00808626fab3a");
+        let sk2 = secret_key("4b22aa260e4acb7021e32f38a6cdf4b673c6a277755bfce287e370c924dc936d");
+
+        for len in [1, 32, 33, 1000, MAX_PLAINTEXT_SIZE] {
+            let plaintext: String = "x".repeat(len);
+            let payload = encrypt(&sk1, &public_key(&sk2), &plaintext, Version::V2).unwrap();
+            assert_eq!(
+                decrypt(&sk2, &public_key(&sk1), &payload).unwrap(),
+                plaintext
+            );
+        }
+
+        assert_eq!(
+            encrypt(&sk1, &public_key(&sk2), "", Version::V2).unwrap_err(),
+            Error::InvalidMessageLength(0)
+        );
+        assert_eq!(
+            encrypt(&sk1, &public_key(&sk2), "x".repeat(65536), Version::V2).unwrap_err(),
+            Error::InvalidMessageLength(65536)
+        );
+    }
+
+    #[test]
+    fn test_invalid_decrypt() {
+        let sk1 = secret_key("0000000000000000000000000000000000000000000000000000000000000001");
+        let sk2 = secret_key("0000000000000000000000000000000000000000000000000000000000000002");
+        let key = ConversationKey::derive(&sk1, &public_key(&sk2)).unwrap();
+        let payload = encrypt_with_conversation_key(&key, "hello").unwrap();
+
+        assert_eq!(
+            decrypt_with_conversation_key(&key, "").unwrap_err(),
+            Error::VersionNotFound
+        );
+        assert_eq!(
+            decrypt_with_conversation_key(&key, "#Atqupco0WyaOW2IGDKcshwxI9xO8HgD/P8Ddt46CbxDbrhdG8VmJZE0UICD06CUvEvdnr1cp1fiMtlM/GrE92xAc1EwsVCQEgWEu2gsHUVf4JAa3TpgkmFc3TWsax0v6n/Wq").unwrap_err(),
+            Error::UnknownVersion(b'#')
+        );
+        assert_eq!(
+            decrypt_with_conversation_key(&key, "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAB")
+                .unwrap_err(),
+            Error::InvalidPayloadLength(44)
+        );
+
+        // Tampered ciphertext
+        let mut data = general_purpose::STANDARD.decode(&payload).unwrap();
+        data[40] ^= 0x01;
+        assert_eq!(
+            decrypt_with_conversation_key(&key, general_purpose::STANDARD.encode(&data))
+                .unwrap_err(),
+            Error::InvalidMac
+        );
+
+        // Unknown version
+        data[0] = 0x01;
+        assert_eq!(
+            decrypt_with_conversation_key(&key, general_purpose::STANDARD.encode(&data))
+                .unwrap_err(),
+            Error::UnknownVersion(0x01)
+        );
+
+        // Wrong conversation key
+        let other = ConversationKey::derive(&sk1, &public_key(&sk1)).unwrap();
+        assert_eq!(
+            decrypt_with_conversation_key(&other, &payload).unwrap_err(),
+            Error::InvalidMac
+        );
+    }
+}
@@ -0,0 +1,788 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP46
+//!
+//! Nostr Connect
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/46.md>
+
+use std::fmt;
+use std::str::FromStr;
+
+use secp256k1::XOnlyPublicKey;
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded::Serializer;
+use url::Url;
+
+use super::nip04;
+use crate::event::{self, unsigned};
+use crate::key::{self, Keys};
+use crate::{Event, Kind, UnsignedEvent};
+
+const BUNKER_SCHEME: &str = "bunker";
+const NOSTR_CONNECT_SCHEME: &str = "nostrconnect";
+
+/// `NIP46` error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Key error
+    #[error(transparent)]
+    Key(#[from] key::Error),
+    /// JSON error
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Url parse error
+    #[error(transparent)]
+    Url(#[from] url::ParseError),
+    /// Secp256k1 error
+    #[error(transparent)]
+    Secp256k1(#[from] secp256k1::Error),
+    /// NIP04 error
+    #[error(transparent)]
+    NIP04(#[from] nip04::Error),
+    /// Event error
+    #[error(transparent)]
+    Event(#[from] event::Error),
+    /// Unsigned event error
+    #[error(transparent)]
+    Unsigned(#[from] unsigned::Error),
+    /// The event is not a Nostr Connect event
+    #[error("invalid kind: {}", .0.as_u64())]
+    InvalidKind(Kind),
+    /// Unsupported method
+    #[error("unsupported method: {0}")]
+    UnsupportedMethod(String),
+    /// Invalid request params
+    #[error("invalid params for {0}")]
+    InvalidParams(NostrConnectMethod),
+    /// The message is not a request
+    #[error("not a request")]
+    NotRequest,
+    /// The message is not a response
+    #[error("not a response")]
+    NotResponse,
+    /// The response has neither a result nor an error
+    #[error("empty response")]
+    EmptyResponse,
+    /// The remote signer replied with an error
+    #[error("remote signer error: {0}")]
+    Response(String),
+    /// Unexpected result
+    #[error("unexpected result for {0}: {1}")]
+    UnexpectedResult(NostrConnectMethod, String),
+    /// Invalid URI
+    #[error("invalid uri")]
+    InvalidURI,
+    /// Invalid URI scheme
+    #[error("invalid uri scheme: {0}")]
+    InvalidURIScheme(String),
+}
+
+/// Nostr Connect request method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NostrConnectMethod {
+    /// Connect
+    Connect,
+    /// Get public key
+    GetPublicKey,
+    /// Sign event
+    SignEvent,
+    /// Encrypt (NIP04)
+    Nip04Encrypt,
+    /// Decrypt (NIP04)
+    Nip04Decrypt,
+    /// Encrypt (NIP44)
+    Nip44Encrypt,
+    /// Decrypt (NIP44)
+    Nip44Decrypt,
+    /// Ping
+    Ping,
+}
+
+impl fmt::Display for NostrConnectMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connect => write!(f, "connect"),
+            Self::GetPublicKey => write!(f, "get_public_key"),
+            Self::SignEvent => write!(f, "sign_event"),
+            Self::Nip04Encrypt => write!(f, "nip04_encrypt"),
+            Self::Nip04Decrypt => write!(f, "nip04_decrypt"),
+            Self::Nip44Encrypt => write!(f, "nip44_encrypt"),
+            Self::Nip44Decrypt => write!(f, "nip44_decrypt"),
+            Self::Ping => write!(f, "ping"),
+        }
+    }
+}
+
+impl FromStr for NostrConnectMethod {
+    type Err = Error;
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        match method {
+            "connect" => Ok(Self::Connect),
+            "get_public_key" => Ok(Self::GetPublicKey),
+            "sign_event" => Ok(Self::SignEvent),
+            "nip04_encrypt" => Ok(Self::Nip04Encrypt),
+            "nip04_decrypt" => Ok(Self::Nip04Decrypt),
+            "nip44_encrypt" => Ok(Self::Nip44Encrypt),
+            "nip44_decrypt" => Ok(Self::Nip44Decrypt),
+            "ping" => Ok(Self::Ping),
+            other => Err(Error::UnsupportedMethod(other.to_string())),
+        }
+    }
+}
+
+/// Nostr Connect request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NostrConnectRequest {
+    /// Connect to the remote signer, with the optional secret shared out of band
+    Connect {
+        /// Public key of the remote signer
+        public_key: XOnlyPublicKey,
+        /// Secret
+        secret: Option<String>,
+    },
+    /// Get the public key of the user
+    GetPublicKey,
+    /// Sign an event
+    SignEvent(UnsignedEvent),
+    /// Encrypt `text` for `public_key`
+    Nip04Encrypt {
+        /// Public key of the third party
+        public_key: XOnlyPublicKey,
+        /// Plain text
+        text: String,
+    },
+    /// Decrypt `ciphertext` received from `public_key`
+    Nip04Decrypt {
+        /// Public key of the third party
+        public_key: XOnlyPublicKey,
+        /// Cipher text
+        ciphertext: String,
+    },
+    /// Encrypt `text` for `public_key` (NIP44)
+    Nip44Encrypt {
+        /// Public key of the third party
+        public_key: XOnlyPublicKey,
+        /// Plain text
+        text: String,
+    },
+    /// Decrypt `ciphertext` received from `public_key` (NIP44)
+    Nip44Decrypt {
+        /// Public key of the third party
+        public_key: XOnlyPublicKey,
+        /// Cipher text
+        ciphertext: String,
+    },
+    /// Ping
+    Ping,
+}
+
+impl NostrConnectRequest {
+    /// Get request [`NostrConnectMethod`]
+    pub fn method(&self) -> NostrConnectMethod {
+        match self {
+            Self::Connect { .. } => NostrConnectMethod::Connect,
+            Self::GetPublicKey => NostrConnectMethod::GetPublicKey,
+            Self::SignEvent(..) => NostrConnectMethod::SignEvent,
+            Self::Nip04Encrypt { .. } => NostrConnectMethod::Nip04Encrypt,
+            Self::Nip04Decrypt { .. } => NostrConnectMethod::Nip04Decrypt,
+            Self::Nip44Encrypt { .. } => NostrConnectMethod::Nip44Encrypt,
+            Self::Nip44Decrypt { .. } => NostrConnectMethod::Nip44Decrypt,
+            Self::Ping => NostrConnectMethod::Ping,
+        }
+    }
+
+    /// Get request params
+    pub fn params(&self) -> Vec<String> {
+        match self {
+            Self::Connect { public_key, secret } => {
+                let mut params = vec![public_key.to_string()];
+                if let Some(secret) = secret {
+                    params.push(secret.clone());
+                }
+                params
+            }
+            Self::GetPublicKey | Self::Ping => Vec::new(),
+            Self::SignEvent(unsigned) => vec![unsigned.as_json()],
+            Self::Nip04Encrypt { public_key, text } | Self::Nip44Encrypt { public_key, text } => {
+                vec![public_key.to_string(), text.clone()]
+            }
+            Self::Nip04Decrypt {
+                public_key,
+                ciphertext,
+            }
+            | Self::Nip44Decrypt {
+                public_key,
+                ciphertext,
+            } => vec![public_key.to_string(), ciphertext.clone()],
+        }
+    }
+
+    /// Compose [`NostrConnectRequest`] from [`NostrConnectMethod`] and params
+    pub fn from_params(method: NostrConnectMethod, params: Vec<String>) -> Result<Self, Error> {
+        let mut params = params.into_iter();
+        let mut next_param = || params.next().ok_or(Error::InvalidParams(method));
+        match method {
+            NostrConnectMethod::Connect => Ok(Self::Connect {
+                public_key: XOnlyPublicKey::from_str(&next_param()?)?,
+                secret: next_param().ok(),
+            }),
+            NostrConnectMethod::GetPublicKey => Ok(Self::GetPublicKey),
+            NostrConnectMethod::SignEvent => {
+                Ok(Self::SignEvent(UnsignedEvent::from_json(next_param()?)?))
+            }
+            NostrConnectMethod::Nip04Encrypt => Ok(Self::Nip04Encrypt {
+                public_key: XOnlyPublicKey::from_str(&next_param()?)?,
+                text: next_param()?,
+            }),
+            NostrConnectMethod::Nip04Decrypt => Ok(Self::Nip04Decrypt {
+                public_key: XOnlyPublicKey::from_str(&next_param()?)?,
+                ciphertext: next_param()?,
+            }),
+            NostrConnectMethod::Nip44Encrypt => Ok(Self::Nip44Encrypt {
+                public_key: XOnlyPublicKey::from_str(&next_param()?)?,
+                text: next_param()?,
+            }),
+            NostrConnectMethod::Nip44Decrypt => Ok(Self::Nip44Decrypt {
+                public_key: XOnlyPublicKey::from_str(&next_param()?)?,
+                ciphertext: next_param()?,
+            }),
+            NostrConnectMethod::Ping => Ok(Self::Ping),
+        }
+    }
+}
+
+/// Result of a successful request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResponseResult {
+    /// Connection acknowledged
+    Ack,
+    /// Public key of the user
+    GetPublicKey(XOnlyPublicKey),
+    /// Signed event
+    SignEvent(Box<Event>),
+    /// Encrypted content (NIP04)
+    Nip04Encrypt(String),
+    /// Decrypted content (NIP04)
+    Nip04Decrypt(String),
+    /// Encrypted content (NIP44)
+    Nip44Encrypt(String),
+    /// Decrypted content (NIP44)
+    Nip44Decrypt(String),
+    /// Pong
+    Pong,
+}
+
+impl ResponseResult {
+    /// Parse the `result` of a [`NostrConnectMethod`] request
+    pub fn parse<S>(method: NostrConnectMethod, result: S) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let result: String = result.into();
+        match method {
+            NostrConnectMethod::Connect => Ok(Self::Ack),
+            NostrConnectMethod::GetPublicKey => {
+                Ok(Self::GetPublicKey(XOnlyPublicKey::from_str(&result)?))
+            }
+            NostrConnectMethod::SignEvent => {
+                Ok(Self::SignEvent(Box::new(Event::from_json(result)?)))
+            }
+            NostrConnectMethod::Nip04Encrypt => Ok(Self::Nip04Encrypt(result)),
+            NostrConnectMethod::Nip04Decrypt => Ok(Self::Nip04Decrypt(result)),
+            NostrConnectMethod::Nip44Encrypt => Ok(Self::Nip44Encrypt(result)),
+            NostrConnectMethod::Nip44Decrypt => Ok(Self::Nip44Decrypt(result)),
+            NostrConnectMethod::Ping => {
+                if result == "pong" {
+                    Ok(Self::Pong)
+                } else {
+                    Err(Error::UnexpectedResult(method, result))
+                }
+            }
+        }
+    }
+
+    /// Serialize as `result` string
+    pub fn as_string(&self) -> String {
+        match self {
+            Self::Ack => String::from("ack"),
+            Self::GetPublicKey(public_key) => public_key.to_string(),
+            Self::SignEvent(event) => event.as_json(),
+            Self::Nip04Encrypt(content)
+            | Self::Nip04Decrypt(content)
+            | Self::Nip44Encrypt(content)
+            | Self::Nip44Decrypt(content) => content.clone(),
+            Self::Pong => String::from("pong"),
+        }
+    }
+}
+
+/// Nostr Connect message, carried encrypted in [`Kind::NostrConnect`] events
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NostrConnectMessage {
+    /// Request
+    Request {
+        /// Request id
+        id: String,
+        /// Request
+        req: NostrConnectRequest,
+    },
+    /// Response
+    Response {
+        /// Id of the request
+        id: String,
+        /// Result
+        result: Option<String>,
+        /// Error
+        error: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawMessage {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl NostrConnectMessage {
+    /// New request with a random id
+    pub fn request(req: NostrConnectRequest) -> Self {
+        let id: u64 = secp256k1::rand::random();
+        Self::Request {
+            id: format!("{id:016x}"),
+            req,
+        }
+    }
+
+    /// New response to request `id`
+    pub fn response<S>(id: S, result: Option<ResponseResult>, error: Option<String>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::Response {
+            id: id.into(),
+            result: result.map(|r| r.as_string()),
+            error,
+        }
+    }
+
+    /// Get message id
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Request { id, .. } => id,
+            Self::Response { id, .. } => id,
+        }
+    }
+
+    /// Get [`NostrConnectRequest`]
+    pub fn to_request(&self) -> Result<NostrConnectRequest, Error> {
+        match self {
+            Self::Request { req, .. } => Ok(req.clone()),
+            Self::Response { .. } => Err(Error::NotRequest),
+        }
+    }
+
+    /// Get the [`ResponseResult`] of a [`NostrConnectMethod`] request
+    ///
+    /// Returns [`Error::Response`] if the remote signer replied with an error.
+    pub fn to_response(&self, method: NostrConnectMethod) -> Result<ResponseResult, Error> {
+        match self {
+            Self::Request { .. } => Err(Error::NotResponse),
+            Self::Response {
+                error: Some(error), ..
+            } => Err(Error::Response(error.clone())),
+            Self::Response {
+                result: Some(result),
+                ..
+            } => ResponseResult::parse(method, result.clone()),
+            Self::Response { .. } => Err(Error::EmptyResponse),
+        }
+    }
+
+    /// Deserialize from JSON string
+    pub fn from_json<S>(json: S) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let raw: RawMessage = serde_json::from_str(&json.into())?;
+        match raw.method {
+            Some(method) => {
+                let method = NostrConnectMethod::from_str(&method)?;
+                Ok(Self::Request {
+                    id: raw.id,
+                    req: NostrConnectRequest::from_params(method, raw.params.unwrap_or_default())?,
+                })
+            }
+            None => Ok(Self::Response {
+                id: raw.id,
+                result: raw.result,
+                error: raw.error,
+            }),
+        }
+    }
+
+    /// Serialize as JSON string
+    pub fn as_json(&self) -> String {
+        let raw = match self {
+            Self::Request { id, req } => RawMessage {
+                id: id.clone(),
+                method: Some(req.method().to_string()),
+                params: Some(req.params()),
+                result: None,
+                error: None,
+            },
+            Self::Response { id, result, error } => RawMessage {
+                id: id.clone(),
+                method: None,
+                params: None,
+                result: result.clone(),
+                error: error.clone(),
+            },
+        };
+        // Serializing strings never fails
+        serde_json::to_string(&raw).unwrap_or_default()
+    }
+
+    /// Decrypt the [`NostrConnectMessage`] of a [`Kind::NostrConnect`] event sent to `receiver_keys`
+    pub fn from_event(receiver_keys: &Keys, event: &Event) -> Result<Self, Error> {
+        if event.kind != Kind::NostrConnect {
+            return Err(Error::InvalidKind(event.kind));
+        }
+        let json: String =
+            nip04::decrypt(&receiver_keys.secret_key()?, &event.pubkey, &event.content)?;
+        Self::from_json(json)
+    }
+}
+
+/// App metadata shared in `nostrconnect://` URIs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NostrConnectMetadata {
+    /// Human-readable name of the app
+    pub name: String,
+    /// URL of the website
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<Url>,
+    /// Description of the app
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Array of URLs for icons
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icons: Option<Vec<Url>>,
+}
+
+impl NostrConnectMetadata {
+    /// New [`NostrConnectMetadata`]
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            url: None,
+            description: None,
+            icons: None,
+        }
+    }
+
+    /// Set url
+    pub fn url(self, url: Url) -> Self {
+        Self {
+            url: Some(url),
+            ..self
+        }
+    }
+
+    /// Set description
+    pub fn description<S>(self, description: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    /// Set icons
+    pub fn icons(self, icons: Vec<Url>) -> Self {
+        Self {
+            icons: Some(icons),
+            ..self
+        }
+    }
+}
+
+/// Nostr Connect URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NostrConnectURI {
+    /// `bunker://`: shared by the remote signer, the app connects to it
+    Bunker {
+        /// Public key of the remote signer
+        signer_public_key: XOnlyPublicKey,
+        /// Relays the remote signer listens on
+        relays: Vec<Url>,
+        /// Optional secret to send with the [`NostrConnectRequest::Connect`]
+        secret: Option<String>,
+    },
+    /// `nostrconnect://`: shared by the app, the remote signer connects to it
+    Client {
+        /// Public key of the app
+        public_key: XOnlyPublicKey,
+        /// Relays the app listens on
+        relays: Vec<Url>,
+        /// App metadata
+        metadata: NostrConnectMetadata,
+    },
+}
+
+impl NostrConnectURI {
+    /// Parse `bunker://` or `nostrconnect://` URI
+    pub fn parse<S>(uri: S) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let url = Url::parse(uri.as_ref())?;
+        let public_key = XOnlyPublicKey::from_str(url.host_str().ok_or(Error::InvalidURI)?)?;
+
+        let mut relays: Vec<Url> = Vec::new();
+        let mut secret: Option<String> = None;
+        let mut metadata: Option<NostrConnectMetadata> = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "relay" => relays.push(Url::parse(&value)?),
+                "secret" => secret = Some(value.to_string()),
+                "metadata" => metadata = Some(serde_json::from_str(&value)?),
+                _ => (),
+            }
+        }
+
+        if relays.is_empty() {
+            return Err(Error::InvalidURI);
+        }
+
+        match url.scheme() {
+            BUNKER_SCHEME => Ok(Self::Bunker {
+                signer_public_key: public_key,
+                relays,
+                secret,
+            }),
+            NOSTR_CONNECT_SCHEME => Ok(Self::Client {
+                public_key,
+                relays,
+                metadata: metadata.ok_or(Error::InvalidURI)?,
+            }),
+            scheme => Err(Error::InvalidURIScheme(scheme.to_string())),
+        }
+    }
+
+    /// Get the public key of the party that shared the URI
+    pub fn public_key(&self) -> XOnlyPublicKey {
+        match self {
+            Self::Bunker {
+                signer_public_key, ..
+            } => *signer_public_key,
+            Self::Client { public_key, .. } => *public_key,
+        }
+    }
+
+    /// Get relays
+    pub fn relays(&self) -> Vec<Url> {
+        match self {
+            Self::Bunker { relays, .. } => relays.clone(),
+            Self::Client { relays, .. } => relays.clone(),
+        }
+    }
+
+    /// Get secret
+    pub fn secret(&self) -> Option<String> {
+        match self {
+            Self::Bunker { secret, .. } => secret.clone(),
+            Self::Client { .. } => None,
+        }
+    }
+}
+
+impl FromStr for NostrConnectURI {
+    type Err = Error;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        Self::parse(uri)
+    }
+}
+
+impl fmt::Display for NostrConnectURI {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut query = Serializer::new(String::new());
+        for relay in self.relays().iter() {
+            query.append_pair("relay", relay.as_str());
+        }
+        match self {
+            Self::Bunker {
+                signer_public_key,
+                secret,
+                ..
+            } => {
+                if let Some(secret) = secret {
+                    query.append_pair("secret", secret);
+                }
+                write!(
+                    f,
+                    "{BUNKER_SCHEME}://{signer_public_key}?{}",
+                    query.finish()
+                )
+            }
+            Self::Client {
+                public_key,
+                metadata,
+                ..
+            } => {
+                let metadata: String = serde_json::to_string(metadata).map_err(|_| fmt::Error)?;
+                query.append_pair("metadata", &metadata);
+                write!(
+                    f,
+                    "{NOSTR_CONNECT_SCHEME}://{public_key}?{}",
+                    query.finish()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::SecretKey;
+
+    use super::*;
+    use crate::EventBuilder;
+
+    const PUBLIC_KEY: &str = "79dff8f82963424e0bb02708a22e44b4980893e3a4be0fa3cb60a43b946764e3";
+
+    #[test]
+    fn test_request_serialization() {
+        let public_key = XOnlyPublicKey::from_str(PUBLIC_KEY).unwrap();
+        let msg = NostrConnectMessage::Request {
+            id: String::from("abcd"),
+            req: NostrConnectRequest::Nip04Encrypt {
+                public_key,
+                text: String::from("hello"),
+            },
+        };
+        let json = msg.as_json();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"id":"abcd","method":"nip04_encrypt","params":["{PUBLIC_KEY}","hello"]}}"#
+            )
+        );
+        assert_eq!(NostrConnectMessage::from_json(json).unwrap(), msg);
+
+        let msg =
+            NostrConnectMessage::from_json(r#"{"id":"1","method":"ping","params":[]}"#).unwrap();
+        assert_eq!(msg.to_request().unwrap(), NostrConnectRequest::Ping);
+
+        assert!(matches!(
+            NostrConnectMessage::from_json(r#"{"id":"1","method":"sign_message","params":[]}"#),
+            Err(Error::UnsupportedMethod(..))
+        ));
+        assert!(matches!(
+            NostrConnectMessage::from_json(r#"{"id":"1","method":"connect","params":[]}"#),
+            Err(Error::InvalidParams(NostrConnectMethod::Connect))
+        ));
+    }
+
+    #[test]
+    fn test_response() {
+        let keys = Keys::generate();
+        let unsigned =
+            EventBuilder::new_text_note("hello", &[]).to_unsigned_event(keys.public_key());
+        let event = unsigned.sign(&keys).unwrap();
+
+        let msg = NostrConnectMessage::response(
+            "abcd",
+            Some(ResponseResult::SignEvent(Box::new(event.clone()))),
+            None,
+        );
+        let msg = NostrConnectMessage::from_json(msg.as_json()).unwrap();
+        assert_eq!(msg.id(), "abcd");
+        assert_eq!(
+            msg.to_response(NostrConnectMethod::SignEvent).unwrap(),
+            ResponseResult::SignEvent(Box::new(event))
+        );
+
+        let msg = NostrConnectMessage::from_json(r#"{"id":"1","result":"pong"}"#).unwrap();
+        assert_eq!(
+            msg.to_response(NostrConnectMethod::Ping).unwrap(),
+            ResponseResult::Pong
+        );
+
+        let msg = NostrConnectMessage::from_json(r#"{"id":"1","error":"unauthorized"}"#).unwrap();
+        assert!(matches!(
+            msg.to_response(NostrConnectMethod::GetPublicKey),
+            Err(Error::Response(e)) if e == "unauthorized"
+        ));
+    }
+
+    #[test]
+    fn test_event_roundtrip() {
+        let app_keys = Keys::generate();
+        let signer_keys = Keys::new(
+            SecretKey::from_str("6b911fd37cdf5c81d4c0adb1ab7fa822ed253ab0ad9aa18d77257c88b29b718e")
+                .unwrap(),
+        );
+        let msg = NostrConnectMessage::request(NostrConnectRequest::GetPublicKey);
+        let event = EventBuilder::nostr_connect(&app_keys, signer_keys.public_key(), msg.clone())
+            .unwrap()
+            .to_event(&app_keys)
+            .unwrap();
+        assert_eq!(event.kind, Kind::NostrConnect);
+        assert_eq!(
+            NostrConnectMessage::from_event(&signer_keys, &event).unwrap(),
+            msg
+        );
+    }
+
+    #[test]
+    fn test_uri() {
+        let uri = format!("bunker://{PUBLIC_KEY}?relay=wss%3A%2F%2Frelay.damus.io%2F&secret=abcd");
+        let parsed = NostrConnectURI::parse(&uri).unwrap();
+        assert_eq!(
+            parsed,
+            NostrConnectURI::Bunker {
+                signer_public_key: XOnlyPublicKey::from_str(PUBLIC_KEY).unwrap(),
+                relays: vec![Url::parse("wss://relay.damus.io").unwrap()],
+                secret: Some(String::from("abcd")),
+            }
+        );
+        assert_eq!(parsed.to_string(), uri);
+
+        let uri = format!(
+            r#"nostrconnect://{PUBLIC_KEY}?relay=wss://relay.damus.io&metadata={{"name":"Example"}}"#
+        );
+        let parsed = NostrConnectURI::from_str(&uri).unwrap();
+        assert_eq!(parsed.public_key().to_string(), PUBLIC_KEY);
+        assert_eq!(
+            parsed,
+            NostrConnectURI::Client {
+                public_key: XOnlyPublicKey::from_str(PUBLIC_KEY).unwrap(),
+                relays: vec![Url::parse("wss://relay.damus.io").unwrap()],
+                metadata: NostrConnectMetadata::new("Example"),
+            }
+        );
+        assert_eq!(NostrConnectURI::parse(parsed.to_string()).unwrap(), parsed);
+
+        assert!(NostrConnectURI::parse(format!("bunker://{PUBLIC_KEY}")).is_err());
+        assert!(matches!(
+            NostrConnectURI::parse(format!("nostr://{PUBLIC_KEY}?relay=wss://relay.damus.io")),
+            Err(Error::InvalidURIScheme(..))
+        ));
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP49
+//!
+//! Private key encryption
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/49.md>
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use secp256k1::rand::rngs::OsRng;
+use secp256k1::rand::RngCore;
+use secp256k1::SecretKey;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+use super::nip19::{self, FromBech32};
+use crate::key::{self, Keys};
+
+/// Current version of the encrypted secret key format
+pub const VERSION: u8 = 0x02;
+
+/// Default scrypt `log_n` (64 MiB of memory, ~100 ms on a modern computer)
+pub const DEFAULT_LOG_N: u8 = 16;
+
+/// Max scrypt `log_n` (4 GiB of memory): the `log_n` of a parsed key comes from untrusted data
+pub const MAX_LOG_N: u8 = 22;
+
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+const CIPHERTEXT_SIZE: usize = 48;
+const ENCRYPTED_SECRET_KEY_SIZE: usize = 1 + 1 + SALT_SIZE + NONCE_SIZE + 1 + CIPHERTEXT_SIZE;
+
+/// `NIP49` error
+#[derive(Debug, Eq, PartialEq, thiserror::Error)]
+pub enum Error {
+    /// Invalid length
+    #[error("invalid length")]
+    InvalidLength,
+    /// Unsupported version
+    #[error("unsupported version: {0}")]
+    UnsupportedVersion(u8),
+    /// Unknown key security
+    #[error("unknown key security: {0}")]
+    UnknownKeySecurity(u8),
+    /// Invalid scrypt params
+    #[error("invalid scrypt params")]
+    InvalidScryptParams,
+    /// Encryption failed
+    #[error("encryption failed")]
+    Encryption,
+    /// Decryption failed (wrong password or corrupted data)
+    #[error("decryption failed")]
+    Decryption,
+    /// Keys error
+    #[error(transparent)]
+    Keys(#[from] key::Error),
+    /// NIP19 error
+    #[error(transparent)]
+    NIP19(#[from] nip19::Error),
+    /// Secp256k1 error
+    #[error(transparent)]
+    Secp256k1(#[from] secp256k1::Error),
+}
+
+/// How the secret key was handled before the encryption
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeySecurity {
+    /// The key is known to have been handled insecurely (ex. stored unencrypted, copied and pasted)
+    Weak = 0x00,
+    /// The key is not known to have been handled insecurely
+    Medium = 0x01,
+    /// The client does not track this data
+    Unknown = 0x02,
+}
+
+impl TryFrom<u8> for KeySecurity {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Weak),
+            0x01 => Ok(Self::Medium),
+            0x02 => Ok(Self::Unknown),
+            v => Err(Error::UnknownKeySecurity(v)),
+        }
+    }
+}
+
+/// Encrypted secret key
+///
+/// Use [`ToBech32`](nip19::ToBech32) and [`FromBech32`] to encode and decode it as `ncryptsec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedSecretKey {
+    log_n: u8,
+    salt: [u8; SALT_SIZE],
+    nonce: [u8; NONCE_SIZE],
+    key_security: KeySecurity,
+    ciphertext: [u8; CIPHERTEXT_SIZE],
+}
+
+impl EncryptedSecretKey {
+    /// Encrypt [`SecretKey`]
+    ///
+    /// `log_n` is the scrypt work factor: every increment doubles both time and memory required.
+    /// Must not be greater than [`MAX_LOG_N`].
+    pub fn new<S>(
+        secret_key: &SecretKey,
+        password: S,
+        log_n: u8,
+        key_security: KeySecurity,
+    ) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let mut rng = OsRng;
+
+        let mut salt = [0u8; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+
+        let mut nonce = [0u8; NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+
+        let key = derive_key(password.as_ref(), &salt, log_n)?;
+        let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+        let secret_bytes = Zeroizing::new(secret_key.secret_bytes());
+        let ciphertext: Vec<u8> = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: secret_bytes.as_ref(),
+                    aad: &[key_security as u8],
+                },
+            )
+            .map_err(|_| Error::Encryption)?;
+
+        Ok(Self {
+            log_n,
+            salt,
+            nonce,
+            key_security,
+            ciphertext: ciphertext.try_into().map_err(|_| Error::InvalidLength)?,
+        })
+    }
+
+    /// Parse encrypted secret key from bytes
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != ENCRYPTED_SECRET_KEY_SIZE {
+            return Err(Error::InvalidLength);
+        }
+
+        let version: u8 = bytes[0];
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let (salt, rest) = bytes[2..].split_at(SALT_SIZE);
+        let (nonce, rest) = rest.split_at(NONCE_SIZE);
+        let (key_security, ciphertext) = rest.split_at(1);
+
+        Ok(Self {
+            log_n: bytes[1],
+            salt: salt.try_into().map_err(|_| Error::InvalidLength)?,
+            nonce: nonce.try_into().map_err(|_| Error::InvalidLength)?,
+            key_security: KeySecurity::try_from(key_security[0])?,
+            ciphertext: ciphertext.try_into().map_err(|_| Error::InvalidLength)?,
+        })
+    }
+
+    /// Serialize encrypted secret key to bytes
+    pub fn as_vec(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(ENCRYPTED_SECRET_KEY_SIZE);
+        bytes.push(VERSION);
+        bytes.push(self.log_n);
+        bytes.extend_from_slice(&self.salt);
+        bytes.extend_from_slice(&self.nonce);
+        bytes.push(self.key_security as u8);
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+
+    /// Get scrypt `log_n`
+    pub fn log_n(&self) -> u8 {
+        self.log_n
+    }
+
+    /// Get [`KeySecurity`]
+    pub fn key_security(&self) -> KeySecurity {
+        self.key_security
+    }
+
+    /// Decrypt [`SecretKey`]
+    pub fn to_secret_key<S>(&self, password: S) -> Result<SecretKey, Error>
+    where
+        S: AsRef<str>,
+    {
+        let key = derive_key(password.as_ref(), &self.salt, self.log_n)?;
+        let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+        let secret_bytes = Zeroizing::new(
+            cipher
+                .decrypt(
+                    XNonce::from_slice(&self.nonce),
+                    Payload {
+                        msg: &self.ciphertext,
+                        aad: &[self.key_security as u8],
+                    },
+                )
+                .map_err(|_| Error::Decryption)?,
+        );
+        Ok(SecretKey::from_slice(&secret_bytes)?)
+    }
+}
+
+/// Derive the symmetric key with scrypt (`r = 8`, `p = 1`) from the NFKC normalized password
+fn derive_key(password: &str, salt: &[u8], log_n: u8) -> Result<Zeroizing<[u8; 32]>, Error> {
+    if log_n > MAX_LOG_N {
+        return Err(Error::InvalidScryptParams);
+    }
+    let password: Zeroizing<String> = Zeroizing::new(password.nfkc().collect());
+    let params = scrypt::Params::new(log_n, 8, 1).map_err(|_| Error::InvalidScryptParams)?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(password.as_bytes(), salt, &params, key.as_mut())
+        .map_err(|_| Error::InvalidScryptParams)?;
+    Ok(key)
+}
+
+impl Keys {
+    /// Encrypt the secret key with `password`, using the [`DEFAULT_LOG_N`]
+    ///
+    /// Use [`EncryptedSecretKey::new`] to customize the scrypt work factor or the [`KeySecurity`].
+    pub fn encrypt<S>(&self, password: S) -> Result<EncryptedSecretKey, Error>
+    where
+        S: AsRef<str>,
+    {
+        EncryptedSecretKey::new(
+            &self.secret_key()?,
+            password,
+            DEFAULT_LOG_N,
+            KeySecurity::Unknown,
+        )
+    }
+
+    /// Decrypt [`Keys`] from bech32 encoded `ncryptsec`
+    pub fn from_ncryptsec<S>(ncryptsec: &str, password: S) -> Result<Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let encrypted = EncryptedSecretKey::from_bech32(ncryptsec)?;
+        Ok(Self::new(encrypted.to_secret_key(password)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::nips::nip19::ToBech32;
+
+    // Low work factor to keep the tests fast
+    const TEST_LOG_N: u8 = 8;
+
+    #[test]
+    fn test_decrypt_vector() {
+        let ncryptsec = "ncryptsec1qgg9947rlpvqu76pj5ecreduf9jxhselq2nae2kghhvd5g7dgjtcxfqtd67p9m0w57lspw8gsq6yphnm8623nsl8xn9j4jdzz84zm3frztj3z7s35vpzmqf6ksu8r89qk5z2zxfmu5gv8th8wclt0h4p";
+        let encrypted = EncryptedSecretKey::from_bech32(ncryptsec).unwrap();
+        assert_eq!(encrypted.log_n(), 16);
+        assert_eq!(encrypted.to_bech32().unwrap(), ncryptsec);
+
+        let keys = Keys::from_ncryptsec(ncryptsec, "nostr").unwrap();
+        assert_eq!(
+            keys.secret_key().unwrap().display_secret().to_string(),
+            "3501454135014541350145413501453fefb02227e449e57cf4d3a3ce05378683"
+        );
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let secret_key =
+            SecretKey::from_str("6b911fd37cdf5c81d4c0adb1ab7fa822ed253ab0ad9aa18d77257c88b29b718e")
+                .unwrap();
+        // "ÅΩẛ̣" (NFC) and "ÅΩṩ" (NFKC) normalize to the same password
+        let encrypted = EncryptedSecretKey::new(
+            &secret_key,
+            "\u{212B}\u{2126}\u{1E9B}\u{0323}",
+            TEST_LOG_N,
+            KeySecurity::Medium,
+        )
+        .unwrap();
+        assert_eq!(encrypted.key_security(), KeySecurity::Medium);
+
+        let bytes: Vec<u8> = encrypted.as_vec();
+        assert_eq!(bytes.len(), ENCRYPTED_SECRET_KEY_SIZE);
+        let decoded = EncryptedSecretKey::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, encrypted);
+        assert_eq!(
+            decoded.to_secret_key("\u{00C5}\u{03A9}\u{1E69}").unwrap(),
+            secret_key
+        );
+        assert_eq!(
+            decoded.to_secret_key("wrong").unwrap_err(),
+            Error::Decryption
+        );
+
+        // Key security byte is authenticated
+        let mut tampered: Vec<u8> = bytes;
+        tampered[1 + 1 + SALT_SIZE + NONCE_SIZE] = KeySecurity::Unknown as u8;
+        assert_eq!(
+            EncryptedSecretKey::from_slice(&tampered)
+                .unwrap()
+                .to_secret_key("\u{00C5}\u{03A9}\u{1E69}")
+                .unwrap_err(),
+            Error::Decryption
+        );
+    }
+
+    #[test]
+    fn test_max_log_n() {
+        let secret_key =
+            SecretKey::from_str("6b911fd37cdf5c81d4c0adb1ab7fa822ed253ab0ad9aa18d77257c88b29b718e")
+                .unwrap();
+        assert_eq!(
+            EncryptedSecretKey::new(&secret_key, "nostr", MAX_LOG_N + 1, KeySecurity::Unknown)
+                .unwrap_err(),
+            Error::InvalidScryptParams
+        );
+
+        // A `log_n` of 40 would require 1 TiB of memory
+        let encrypted =
+            EncryptedSecretKey::new(&secret_key, "nostr", TEST_LOG_N, KeySecurity::Unknown)
+                .unwrap();
+        let mut bytes: Vec<u8> = encrypted.as_vec();
+        bytes[1] = 40;
+        let ncryptsec: String = EncryptedSecretKey::from_slice(&bytes)
+            .unwrap()
+            .to_bech32()
+            .unwrap();
+        assert_eq!(
+            Keys::from_ncryptsec(&ncryptsec, "nostr").unwrap_err(),
+            Error::InvalidScryptParams
+        );
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! NIP59
+//!
+//! Gift Wrap
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/59.md>
+
+use std::ops::Range;
+use std::time::Duration;
+
+use secp256k1::rand::{thread_rng, Rng};
+use secp256k1::XOnlyPublicKey;
+
+use super::nip44;
+use crate::event::{self, unsigned};
+use crate::key::{self, Keys};
+use crate::{Event, EventId, Kind, Timestamp, UnsignedEvent};
+
+/// Range of the random tweak (seconds) subtracted from the `created_at` of seals and gift wraps
+///
+/// Up to 2 days in the past, to not leak the time of the rumor.
+pub const RANGE_RANDOM_TIMESTAMP_TWEAK: Range<u64> = 0..172_800;
+
+/// `NIP59` error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Keys error
+    #[error(transparent)]
+    Keys(#[from] key::Error),
+    /// Event error
+    #[error(transparent)]
+    Event(#[from] event::Error),
+    /// Unsigned event error
+    #[error(transparent)]
+    Unsigned(#[from] unsigned::Error),
+    /// NIP44 error
+    #[error(transparent)]
+    NIP44(#[from] nip44::Error),
+    /// Not a gift wrap
+    #[error("not a gift wrap")]
+    NotGiftWrap,
+    /// Not a seal
+    #[error("not a seal")]
+    NotSeal,
+    /// The rumor id doesn't match its content
+    #[error("invalid rumor id")]
+    InvalidRumorId,
+    /// The rumor was not created by the signer of the seal
+    #[error("the rumor author doesn't match the seal signer")]
+    SenderMismatch,
+}
+
+/// Subtract a random tweak, in [`RANGE_RANDOM_TIMESTAMP_TWEAK`], from `timestamp`
+pub fn randomize_timestamp(timestamp: Timestamp) -> Timestamp {
+    let tweak: u64 = thread_rng().gen_range(RANGE_RANDOM_TIMESTAMP_TWEAK);
+    timestamp - Duration::from_secs(tweak)
+}
+
+/// Parse and verify the seal decrypted from a gift wrap
+pub fn parse_seal<S>(json: S) -> Result<Event, Error>
+where
+    S: Into<String>,
+{
+    // The id and the signature are verified by `Event::from_json`
+    let seal: Event = Event::from_json(json)?;
+    if seal.kind != Kind::Seal {
+        return Err(Error::NotSeal);
+    }
+    Ok(seal)
+}
+
+/// Unwrapped gift wrap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnwrappedGift {
+    /// Sender of the rumor (signer of the seal)
+    pub sender: XOnlyPublicKey,
+    /// Rumor: unsigned event, so it can't be published if leaked
+    pub rumor: UnsignedEvent,
+}
+
+impl UnwrappedGift {
+    /// Unwrap a gift wrap received by `receiver_keys`
+    ///
+    /// The seal signature and the rumor id are verified.
+    pub fn from_gift_wrap(receiver_keys: &Keys, gift_wrap: &Event) -> Result<Self, Error> {
+        if gift_wrap.kind != Kind::GiftWrap {
+            return Err(Error::NotGiftWrap);
+        }
+
+        let secret_key = receiver_keys.secret_key()?;
+        let seal: Event = parse_seal(nip44::decrypt(
+            &secret_key,
+            &gift_wrap.pubkey,
+            &gift_wrap.content,
+        )?)?;
+        let rumor: String = nip44::decrypt(&secret_key, &seal.pubkey, &seal.content)?;
+        Self::from_seal(&seal, rumor)
+    }
+
+    /// Compose [`UnwrappedGift`] from a verified seal and its decrypted content
+    ///
+    /// Useful to unwrap gift wraps with a signer that doesn't expose the secret key.
+    pub fn from_seal<S>(seal: &Event, rumor: S) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        let rumor: UnsignedEvent = UnsignedEvent::from_json(rumor)?;
+
+        if rumor.pubkey != seal.pubkey {
+            return Err(Error::SenderMismatch);
+        }
+
+        let id = EventId::new(
+            &rumor.pubkey,
+            rumor.created_at,
+            &rumor.kind,
+            &rumor.tags,
+            &rumor.content,
+        );
+        if id != rumor.id {
+            return Err(Error::InvalidRumorId);
+        }
+
+        Ok(Self {
+            sender: seal.pubkey,
+            rumor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventBuilder, Result, Tag};
+
+    #[test]
+    fn test_gift_wrap() -> Result<()> {
+        let sender_keys = Keys::generate();
+        let receiver_keys = Keys::generate();
+
+        let rumor: UnsignedEvent =
+            EventBuilder::private_msg_rumor(receiver_keys.public_key(), "Test", None)
+                .to_unsigned_event(sender_keys.public_key());
+        let now = Timestamp::from(1_690_000_000);
+        let gift_wrap: Event = EventBuilder::gift_wrap(
+            &sender_keys,
+            &receiver_keys.public_key(),
+            rumor.clone(),
+            now,
+        )?;
+
+        assert_eq!(gift_wrap.kind, Kind::GiftWrap);
+        assert_ne!(gift_wrap.pubkey, sender_keys.public_key());
+        assert_eq!(
+            gift_wrap.tags,
+            vec![Tag::PubKey(receiver_keys.public_key(), None)]
+        );
+        assert!(gift_wrap.created_at <= now);
+        assert!(gift_wrap.created_at > now - Duration::from_secs(RANGE_RANDOM_TIMESTAMP_TWEAK.end));
+
+        let unwrapped = UnwrappedGift::from_gift_wrap(&receiver_keys, &gift_wrap)?;
+        assert_eq!(unwrapped.sender, sender_keys.public_key());
+        assert_eq!(unwrapped.rumor, rumor);
+        assert_eq!(unwrapped.rumor.kind, Kind::PrivateDirectMessage);
+
+        // Only the receiver can unwrap it
+        assert!(matches!(
+            UnwrappedGift::from_gift_wrap(&sender_keys, &gift_wrap).unwrap_err(),
+            Error::NIP44(..)
+        ));
+
+        assert!(matches!(
+            UnwrappedGift::from_gift_wrap(&receiver_keys, &rumor.sign(&sender_keys)?).unwrap_err(),
+            Error::NotGiftWrap
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sender_mismatch() -> Result<()> {
+        let sender_keys = Keys::generate();
+        let receiver_keys = Keys::generate();
+
+        // Rumor authored by someone else than the seal signer
+        let rumor: UnsignedEvent =
+            EventBuilder::private_msg_rumor(receiver_keys.public_key(), "Test", None)
+                .to_unsigned_event(receiver_keys.public_key());
+        let now = Timestamp::now();
+        let seal: Event =
+            EventBuilder::seal(&sender_keys, &receiver_keys.public_key(), rumor, now)?
+                .to_event(&sender_keys)?;
+        let gift_wrap: Event =
+            EventBuilder::gift_wrap_from_seal(&receiver_keys.public_key(), &seal, now)?;
+
+        assert!(matches!(
+            UnwrappedGift::from_gift_wrap(&receiver_keys, &gift_wrap).unwrap_err(),
+            Error::SenderMismatch
+        ));
+
+        Ok(())
+    }
+}
@@ -3,6 +3,10 @@
 
 //! Prelude
 
+// The `Error` of the NIPs and of the external crates is ambiguous: import it from its module
+#![allow(unknown_lints)]
+#![allow(ambiguous_glob_reexports)]
+
 // External crates
 #[cfg(feature = "nip19")]
 pub use bech32::*;
@@ -21,15 +25,20 @@ pub use crate::key::*;
 pub use crate::message::*;
 #[cfg(feature = "base")]
 pub use crate::types::*;
+pub use crate::util::*;
 pub use crate::{Result, SECP256K1};
 
 // NIPs
+#[cfg(all(feature = "nip03", feature = "base"))]
+pub use crate::nips::nip03::*;
 #[cfg(feature = "nip04")]
 pub use crate::nips::nip04::*;
 #[cfg(feature = "nip05")]
 pub use crate::nips::nip05::*;
 #[cfg(feature = "nip06")]
 pub use crate::nips::nip06::*;
+#[cfg(all(feature = "nip10", feature = "base"))]
+pub use crate::nips::nip10::*;
 #[cfg(feature = "nip11")]
 pub use crate::nips::nip11::*;
 #[cfg(feature = "nip13")]
@@ -38,3 +47,14 @@ pub use crate::nips::nip13::*;
 pub use crate::nips::nip19::*;
 #[cfg(feature = "nip26")]
 pub use crate::nips::nip26::*;
+#[cfg(all(feature = "nip27", feature = "base"))]
+pub use crate::nips::nip27::*;
+// Not glob-imported: `encrypt` and `decrypt` would clash with the NIP04 ones
+#[cfg(feature = "nip44")]
+pub use crate::nips::nip44;
+#[cfg(all(feature = "nip46", feature = "base"))]
+pub use crate::nips::nip46::*;
+#[cfg(feature = "nip49")]
+pub use crate::nips::nip49::*;
+#[cfg(all(feature = "nip59", feature = "base"))]
+pub use crate::nips::nip59::*;
@@ -0,0 +1,198 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Coordinate
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use secp256k1::XOnlyPublicKey;
+
+#[cfg(feature = "nip19")]
+use crate::nips::nip19::{
+    Error as Bech32Error, FromBech32, ParameterizedReplaceableEvent, ToBech32,
+};
+use crate::{Kind, Tag};
+
+/// [`Coordinate`] error
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    /// Invalid coordinate
+    #[error("invalid coordinate")]
+    InvalidCoordinate,
+    /// Impossible to parse kind
+    #[error(transparent)]
+    ParseInt(#[from] ParseIntError),
+    /// Secp256k1 error
+    #[error(transparent)]
+    Secp256k1(#[from] secp256k1::Error),
+}
+
+/// Coordinate of a replaceable or parameterized replaceable event
+///
+/// Formatted as `<kind>:<pubkey>:<d-identifier>`. The identifier is empty for replaceable events.
+///
+/// <https://github.com/nostr-protocol/nips/blob/master/01.md>
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Coordinate {
+    /// Kind
+    pub kind: Kind,
+    /// Author public key
+    pub pubkey: XOnlyPublicKey,
+    /// `d` tag identifier
+    pub identifier: String,
+    /// Recommended relays
+    pub relays: Vec<String>,
+}
+
+impl Coordinate {
+    /// New [`Coordinate`]
+    pub fn new(kind: Kind, pubkey: XOnlyPublicKey) -> Self {
+        Self {
+            kind,
+            pubkey,
+            identifier: String::new(),
+            relays: Vec::new(),
+        }
+    }
+
+    /// Set `d` tag identifier
+    pub fn identifier<S>(self, identifier: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            identifier: identifier.into(),
+            ..self
+        }
+    }
+
+    /// Set recommended relays
+    pub fn relays(self, relays: Vec<String>) -> Self {
+        Self { relays, ..self }
+    }
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.kind.as_u64(),
+            self.pubkey,
+            self.identifier
+        )
+    }
+}
+
+impl FromStr for Coordinate {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The identifier may contain `:`
+        let mut kpi = s.splitn(3, ':');
+        match (kpi.next(), kpi.next(), kpi.next()) {
+            (Some(kind), Some(pubkey), Some(identifier)) => Ok(Self::new(
+                Kind::from_str(kind)?,
+                XOnlyPublicKey::from_str(pubkey)?,
+            )
+            .identifier(identifier)),
+            _ => Err(Error::InvalidCoordinate),
+        }
+    }
+}
+
+impl From<Coordinate> for Tag {
+    fn from(value: Coordinate) -> Self {
+        Self::A {
+            kind: value.kind,
+            public_key: value.pubkey,
+            identifier: value.identifier,
+            relay_url: value.relays.into_iter().next().unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(feature = "nip19")]
+impl From<ParameterizedReplaceableEvent> for Coordinate {
+    fn from(value: ParameterizedReplaceableEvent) -> Self {
+        Self::new(value.kind(), value.pubkey())
+            .identifier(value.identifier())
+            .relays(value.relays().to_vec())
+    }
+}
+
+#[cfg(feature = "nip19")]
+impl From<Coordinate> for ParameterizedReplaceableEvent {
+    fn from(value: Coordinate) -> Self {
+        Self::new(value.kind, value.pubkey, value.identifier, value.relays)
+    }
+}
+
+#[cfg(feature = "nip19")]
+impl FromBech32 for Coordinate {
+    type Err = Bech32Error;
+    fn from_bech32<S>(s: S) -> Result<Self, Self::Err>
+    where
+        S: Into<String>,
+    {
+        Ok(ParameterizedReplaceableEvent::from_bech32(s)?.into())
+    }
+}
+
+#[cfg(feature = "nip19")]
+impl ToBech32 for Coordinate {
+    type Err = Bech32Error;
+    fn to_bech32(&self) -> Result<String, Self::Err> {
+        ParameterizedReplaceableEvent::from(self.clone()).to_bech32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "a695f6b60119d9521934a691347d9f78e8770b56da16bb255ee286ddf9fda919";
+
+    #[test]
+    fn test_parse_coordinate() {
+        let pubkey = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+
+        let coordinate = Coordinate::from_str(&format!("30023:{PUBKEY}:ipsum")).unwrap();
+        assert_eq!(
+            coordinate,
+            Coordinate::new(Kind::LongFormTextNote, pubkey).identifier("ipsum")
+        );
+        assert_eq!(coordinate.to_string(), format!("30023:{PUBKEY}:ipsum"));
+
+        let coordinate = Coordinate::from_str(&format!("10002:{PUBKEY}:")).unwrap();
+        assert_eq!(coordinate, Coordinate::new(Kind::RelayList, pubkey));
+
+        let coordinate = Coordinate::from_str(&format!("30000:{PUBKEY}:a:b")).unwrap();
+        assert_eq!(coordinate.identifier, "a:b");
+
+        assert_eq!(
+            Coordinate::from_str(&format!("30023:{PUBKEY}")),
+            Err(Error::InvalidCoordinate)
+        );
+        assert!(Coordinate::from_str("30023:abc:ipsum").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "nip19")]
+    fn test_coordinate_bech32() {
+        let pubkey = XOnlyPublicKey::from_str(PUBKEY).unwrap();
+        let coordinate = Coordinate::new(Kind::LongFormTextNote, pubkey)
+            .identifier("ipsum")
+            .relays(vec![String::from("wss://relay.nostr.org")]);
+
+        let naddr = coordinate.to_bech32().unwrap();
+        assert_eq!(
+            naddr,
+            ParameterizedReplaceableEvent::from(coordinate.clone())
+                .to_bech32()
+                .unwrap()
+        );
+        assert_eq!(Coordinate::from_bech32(naddr).unwrap(), coordinate);
+    }
+}
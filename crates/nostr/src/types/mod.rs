@@ -5,6 +5,7 @@
 
 pub mod channel_id;
 pub mod contact;
+pub mod coordinate;
 pub mod entity;
 pub mod metadata;
 pub mod profile;
@@ -12,7 +13,8 @@ pub mod time;
 
 pub use self::channel_id::ChannelId;
 pub use self::contact::Contact;
+pub use self::coordinate::Coordinate;
 pub use self::entity::Entity;
 pub use self::metadata::Metadata;
 pub use self::profile::Profile;
-pub use self::time::Timestamp;
+pub use self::time::{Clock, FixedClock, SystemClock, Timestamp};
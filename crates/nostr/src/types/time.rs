@@ -3,6 +3,8 @@
 
 //! Time
 
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -43,6 +45,46 @@ impl Timestamp {
     }
 }
 
+/// Source of the current time
+///
+/// Allow to freeze the time (ex. in tests) to create reproducible events.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Get current UNIX timestamp
+    fn now(&self) -> Timestamp;
+}
+
+/// System [`Clock`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+}
+
+/// [`Clock`] that returns a fixed [`Timestamp`], until changed with [`FixedClock::set`]
+#[derive(Debug, Default)]
+pub struct FixedClock(AtomicU64);
+
+impl FixedClock {
+    /// New [`FixedClock`]
+    pub fn new(timestamp: Timestamp) -> Self {
+        Self(AtomicU64::new(timestamp.as_u64()))
+    }
+
+    /// Set the [`Timestamp`] returned by the clock
+    pub fn set(&self, timestamp: Timestamp) {
+        self.0.store(timestamp.as_u64(), Ordering::SeqCst);
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::from(self.0.load(Ordering::SeqCst))
+    }
+}
+
 impl From<u64> for Timestamp {
     fn from(timestamp: u64) -> Self {
         Self(timestamp)
@@ -0,0 +1,457 @@
+// Copyright (c) 2022-2023 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Util
+
+#[cfg(any(feature = "nip13", feature = "vanity"))]
+pub use self::search::{Error as SearchError, SearchOptions, SearchProgress};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Token to cancel a long-running computation (ex. proof of work, vanity keys search)
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// New [`CancellationToken`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the computation
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if the token is cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Binary data helpers (ex. compact event encoding, OpenTimestamps proofs)
+///
+/// Varints are unsigned LEB128 and byte strings are prefixed by their varint length.
+#[cfg(feature = "base")]
+pub(crate) mod bytes {
+    use std::marker::PhantomData;
+
+    /// Errors returned by the [`Reader`]
+    pub(crate) trait ReaderError {
+        fn unexpected_eof() -> Self;
+        fn varint_overflow() -> Self;
+        fn trailing_bytes() -> Self;
+    }
+
+    pub(crate) fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        buf.push(value as u8);
+    }
+
+    pub(crate) fn write_varbytes(buf: &mut Vec<u8>, data: &[u8]) {
+        write_varint(buf, data.len() as u64);
+        buf.extend_from_slice(data);
+    }
+
+    pub(crate) struct Reader<'a, E> {
+        data: &'a [u8],
+        _error: PhantomData<E>,
+    }
+
+    impl<'a, E> Reader<'a, E>
+    where
+        E: ReaderError,
+    {
+        pub(crate) fn new(data: &'a [u8]) -> Self {
+            Self {
+                data,
+                _error: PhantomData,
+            }
+        }
+
+        pub(crate) fn read(&mut self, len: usize) -> Result<&'a [u8], E> {
+            if self.data.len() < len {
+                return Err(E::unexpected_eof());
+            }
+            let (bytes, rest) = self.data.split_at(len);
+            self.data = rest;
+            Ok(bytes)
+        }
+
+        pub(crate) fn read_u8(&mut self) -> Result<u8, E> {
+            Ok(self.read(1)?[0])
+        }
+
+        pub(crate) fn read_varint(&mut self) -> Result<u64, E> {
+            let mut value: u64 = 0;
+            let mut shift: u32 = 0;
+            loop {
+                let byte: u8 = self.read_u8()?;
+                if shift == 63 && byte > 1 {
+                    return Err(E::varint_overflow());
+                }
+                value |= u64::from(byte & 0x7f) << shift;
+                if byte & 0x80 == 0 {
+                    return Ok(value);
+                }
+                shift += 7;
+            }
+        }
+
+        /// Read a varint length (ex. of a byte string or of a list)
+        ///
+        /// Every item takes at least 1 byte: a length greater than the remaining data is rejected,
+        /// to avoid huge allocations on corrupted data.
+        pub(crate) fn read_len(&mut self) -> Result<usize, E> {
+            let len = usize::try_from(self.read_varint()?).map_err(|_| E::varint_overflow())?;
+            if len > self.data.len() {
+                return Err(E::unexpected_eof());
+            }
+            Ok(len)
+        }
+
+        pub(crate) fn read_varbytes(&mut self) -> Result<&'a [u8], E> {
+            let len: usize = self.read_len()?;
+            self.read(len)
+        }
+
+        /// Check that all the data has been read
+        pub(crate) fn finish(&self) -> Result<(), E> {
+            if self.data.is_empty() {
+                Ok(())
+            } else {
+                Err(E::trailing_bytes())
+            }
+        }
+    }
+}
+
+/// Parallel brute-force search (ex. proof of work, vanity keys)
+#[cfg(any(feature = "nip13", feature = "vanity"))]
+pub(crate) mod search {
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    #[cfg(not(target_arch = "wasm32"))]
+    use std::time::Instant;
+
+    #[cfg(target_arch = "wasm32")]
+    use instant::Instant;
+
+    use super::CancellationToken;
+
+    /// Max interval between two checks of the cancellation token and of the timeout
+    #[cfg(not(target_arch = "wasm32"))]
+    const CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Search error
+    #[derive(Debug, Eq, PartialEq, thiserror::Error)]
+    pub enum Error {
+        /// Search cancelled
+        #[error("search cancelled")]
+        Cancelled,
+        /// Timeout
+        #[error("timeout")]
+        Timeout,
+        /// Thread Join failed
+        #[error("Impossible to join threads")]
+        JoinHandleError,
+    }
+
+    /// Search progress
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SearchProgress {
+        /// Number of attempts (ex. computed hashes, generated keys)
+        pub attempts: u64,
+        /// Elapsed time
+        pub elapsed: Duration,
+    }
+
+    impl SearchProgress {
+        /// Average rate (attempts/second)
+        pub fn rate(&self) -> f64 {
+            let secs: f64 = self.elapsed.as_secs_f64();
+            if secs > 0.0 {
+                self.attempts as f64 / secs
+            } else {
+                0.0
+            }
+        }
+    }
+
+    type ProgressCallback = Arc<dyn Fn(SearchProgress) + Send + Sync>;
+
+    /// Search options
+    #[derive(Clone)]
+    pub struct SearchOptions {
+        threads: usize,
+        cancellation_token: Option<CancellationToken>,
+        timeout: Option<Duration>,
+        progress: Option<(Duration, ProgressCallback)>,
+    }
+
+    impl fmt::Debug for SearchOptions {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("SearchOptions")
+                .field("threads", &self.threads)
+                .field("cancellation_token", &self.cancellation_token)
+                .field("timeout", &self.timeout)
+                .field("progress_interval", &self.progress.as_ref().map(|(i, _)| i))
+                .finish()
+        }
+    }
+
+    impl Default for SearchOptions {
+        fn default() -> Self {
+            Self {
+                threads: thread_count(),
+                cancellation_token: None,
+                timeout: None,
+                progress: None,
+            }
+        }
+    }
+
+    impl SearchOptions {
+        /// New default [`SearchOptions`]
+        ///
+        /// By default, all the available CPU cores are used.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Set number of threads (min 1)
+        ///
+        /// On `wasm32` the search always runs on the current thread.
+        pub fn threads(self, threads: usize) -> Self {
+            Self {
+                threads: std::cmp::max(threads, 1),
+                ..self
+            }
+        }
+
+        /// Set [`CancellationToken`]
+        pub fn cancellation_token(self, token: CancellationToken) -> Self {
+            Self {
+                cancellation_token: Some(token),
+                ..self
+            }
+        }
+
+        /// Stop the search after `timeout`
+        pub fn timeout(self, timeout: Duration) -> Self {
+            Self {
+                timeout: Some(timeout),
+                ..self
+            }
+        }
+
+        /// Call `callback` every `interval` with the [`SearchProgress`]
+        pub fn progress<F>(self, interval: Duration, callback: F) -> Self
+        where
+            F: Fn(SearchProgress) + Send + Sync + 'static,
+        {
+            Self {
+                progress: Some((interval, Arc::new(callback))),
+                ..self
+            }
+        }
+
+        /// Check the cancellation token and the timeout, and report the progress if due
+        fn check(
+            &self,
+            start: &Instant,
+            last_progress: &mut Instant,
+            attempts: &AtomicU64,
+        ) -> Result<(), Error> {
+            if self
+                .cancellation_token
+                .as_ref()
+                .map(|t| t.is_cancelled())
+                .unwrap_or(false)
+            {
+                return Err(Error::Cancelled);
+            }
+
+            if self
+                .timeout
+                .map(|timeout| start.elapsed() >= timeout)
+                .unwrap_or(false)
+            {
+                return Err(Error::Timeout);
+            }
+
+            if let Some((interval, callback)) = &self.progress {
+                if last_progress.elapsed() >= *interval {
+                    *last_progress = Instant::now();
+                    callback(SearchProgress {
+                        attempts: attempts.load(Ordering::Relaxed),
+                        elapsed: start.elapsed(),
+                    });
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Run the search on the threads set in [`SearchOptions`], until a result is found
+    ///
+    /// `worker(index, threads)` builds the search of the thread `index` (of `threads`):
+    /// each call of the returned closure tries `batch_size` candidates and returns the result, if found.
+    pub(crate) fn run<T, F, W>(opts: &SearchOptions, batch_size: u64, worker: F) -> Result<T, Error>
+    where
+        T: Send,
+        F: Fn(usize, usize) -> W + Sync,
+        W: FnMut() -> Option<T>,
+    {
+        let start = Instant::now();
+        let attempts = AtomicU64::new(0);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if opts.threads > 1 {
+            return run_parallel(opts, batch_size, worker, start, &attempts);
+        }
+
+        let mut batch = worker(0, 1);
+        let mut last_progress = start;
+        loop {
+            if let Some(res) = batch() {
+                return Ok(res);
+            }
+            attempts.fetch_add(batch_size, Ordering::Relaxed);
+            opts.check(&start, &mut last_progress, &attempts)?;
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run_parallel<T, F, W>(
+        opts: &SearchOptions,
+        batch_size: u64,
+        worker: F,
+        start: Instant,
+        attempts: &AtomicU64,
+    ) -> Result<T, Error>
+    where
+        T: Send,
+        F: Fn(usize, usize) -> W + Sync,
+        W: FnMut() -> Option<T>,
+    {
+        use std::sync::atomic::AtomicBool;
+        use std::sync::mpsc::{sync_channel, RecvTimeoutError};
+        use std::thread;
+
+        /// Stop the threads also if the progress callback panics
+        struct StopOnDrop<'a>(&'a AtomicBool);
+
+        impl Drop for StopOnDrop<'_> {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        /// Stop the other threads if a worker panics: the result channel is closed when all of them exit
+        struct StopOnPanic<'a>(&'a AtomicBool);
+
+        impl Drop for StopOnPanic<'_> {
+            fn drop(&mut self) {
+                if thread::panicking() {
+                    self.0.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let (tx, rx) = sync_channel::<T>(1);
+        let found = AtomicBool::new(false);
+        let threads: usize = opts.threads;
+
+        thread::scope(|s| {
+            let stop = StopOnDrop(&found);
+            let mut handles = Vec::with_capacity(threads);
+
+            for index in 0..threads {
+                let tx = tx.clone();
+                let found = &found;
+                let worker = &worker;
+                let handle = s.spawn(move || {
+                    let _stop = StopOnPanic(found);
+                    let mut batch = worker(index, threads);
+                    while !found.load(Ordering::SeqCst) {
+                        if let Some(res) = batch() {
+                            // Another thread may have already sent its result
+                            let _ = tx.try_send(res);
+                            found.store(true, Ordering::SeqCst);
+                            break;
+                        }
+                        attempts.fetch_add(batch_size, Ordering::Relaxed);
+                    }
+                });
+                handles.push(handle);
+            }
+
+            drop(tx);
+
+            let tick: Duration = match &opts.progress {
+                Some((interval, _)) => std::cmp::min(*interval, CHECK_INTERVAL),
+                None => CHECK_INTERVAL,
+            };
+            let mut last_progress = start;
+            let result = loop {
+                match rx.recv_timeout(tick) {
+                    Ok(res) => break Ok(res),
+                    Err(RecvTimeoutError::Timeout) => {
+                        if let Err(e) = opts.check(&start, &mut last_progress, attempts) {
+                            break Err(e);
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break Err(Error::JoinHandleError),
+                }
+            };
+
+            drop(stop);
+
+            for handle in handles {
+                handle.join().map_err(|_| Error::JoinHandleError)?;
+            }
+
+            result
+        })
+    }
+
+    /// Number of threads to use by default
+    #[cfg(not(target_arch = "wasm32"))]
+    fn thread_count() -> usize {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    }
+
+    /// Number of threads to use by default
+    #[cfg(target_arch = "wasm32")]
+    fn thread_count() -> usize {
+        1
+    }
+
+    #[cfg(all(test, not(target_arch = "wasm32")))]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_worker_panic() {
+            let opts = SearchOptions::new().threads(4);
+            let res: Result<(), Error> = run(&opts, 1, |index, _| {
+                move || {
+                    if index == 0 {
+                        panic!("worker panic");
+                    }
+                    None
+                }
+            });
+            assert_eq!(res, Err(Error::JoinHandleError));
+        }
+    }
+}